@@ -543,7 +543,8 @@ impl AtlasManager {
         results
     }
 
-    pub(crate) fn get_texture<'s>(&'s self, handle: &TextureHandle) -> &'s Texture {
+    /// The atlas texture the handle points into.
+    pub fn get_texture<'s>(&'s self, handle: &TextureHandle) -> &'s Texture {
         self.get_texture_from_index((handle.index.get() - 1) as usize)
     }
 
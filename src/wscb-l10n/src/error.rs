@@ -0,0 +1,44 @@
+use ::std::path::PathBuf;
+
+use ::fluent_bundle::FluentError;
+use ::fluent_syntax::parser::ParserError;
+use ::thiserror::Error;
+use ::unic_langid::LanguageIdentifier;
+use ::wscb_text::error::MarkupError;
+
+#[derive(Debug, Error)]
+pub enum L10nError {
+    #[error("failed to read {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: ::std::io::Error,
+    },
+    #[error("`{0}` is not a valid locale")]
+    InvalidLocale(String),
+    #[error("{} syntax errors in {name}, the first: {}", .errors.len(), first_error(.errors))]
+    Syntax {
+        name: String,
+        errors: Vec<ParserError>,
+    },
+    #[error("message `{id}` is defined twice for {locale}")]
+    Duplicate {
+        locale: LanguageIdentifier,
+        id: String,
+    },
+    #[error("no locale on the fallback chain has the message `{0}`")]
+    MissingMessage(String),
+    #[error("formatting `{id}` failed: {}", first_error(.errors))]
+    Format {
+        id: String,
+        errors: Vec<FluentError>,
+    },
+    #[error(transparent)]
+    Markup(#[from] MarkupError),
+}
+
+fn first_error<E: ::std::fmt::Display>(errors: &[E]) -> String {
+    errors
+        .first()
+        .map_or_else(String::new, |error| error.to_string())
+}
@@ -0,0 +1,38 @@
+//! Localized strings for the game, with message files in the
+//! [Fluent](https://projectfluent.org) syntax.
+//!
+//! Every locale is a directory of `.ftl` files:
+//!
+//! ```text
+//! locales/
+//!     en-US/ui.ftl
+//!     zh-Hans/ui.ftl
+//! ```
+//!
+//! Messages take placeholders like `{ $name }` and choose plural and gender
+//! variants with selectors:
+//!
+//! ```text
+//! items-found = { $count ->
+//!     [one] You found one item.
+//!    *[other] You found { $count } items.
+//! }
+//! greeting = { $gender ->
+//!     [female] Welcome back, heroine!
+//!     [male] Welcome back, hero!
+//!    *[other] Welcome back!
+//! }
+//! ```
+//!
+//! A [`Localizer`](localizer::Localizer) looks messages up along a
+//! [fallback chain](locale::fallback_chain) of locales and keeps the ids it
+//! could not find anywhere.
+
+pub mod error;
+pub mod locale;
+pub mod localizer;
+pub mod text;
+
+/// The arguments of a message and their values, from Fluent.
+pub use ::fluent_bundle::{FluentArgs, FluentValue};
+pub use ::unic_langid::LanguageIdentifier;
@@ -0,0 +1,80 @@
+//! Locale identifiers and the order they are tried in.
+
+use ::unic_langid::{CharacterDirection, LanguageIdentifier};
+use ::wscb_text::shape::Direction;
+
+use crate::error::L10nError;
+
+/// Parse a BCP 47 locale like `"zh-Hans-CN"`, `_` is taken for `-`.
+pub fn parse(locale: &str) -> Result<LanguageIdentifier, L10nError> {
+    locale
+        .replace('_', "-")
+        .parse()
+        .map_err(|_| L10nError::InvalidLocale(locale.to_string()))
+}
+
+/// The locales to look a message up in, most specific first.
+///
+/// Subtags are dropped from the end one at a time, then the default locale
+/// and its own parents follow: `zh-Hans-CN` gives `zh-Hans-CN`, `zh-Hans`,
+/// `zh` and then for example `en-US`, `en`. No locale appears twice.
+pub fn fallback_chain(
+    locale: &LanguageIdentifier,
+    default: &LanguageIdentifier,
+) -> Vec<LanguageIdentifier> {
+    let mut chain: Vec<LanguageIdentifier> = Vec::new();
+    for start in [locale, default] {
+        let mut current = start.clone();
+        loop {
+            if !chain.contains(&current) {
+                chain.push(current.clone());
+            }
+            if current.variants().next().is_some() {
+                current.clear_variants();
+            } else if current.region.is_some() {
+                current.region = None;
+            } else if current.script.is_some() {
+                current.script = None;
+            } else {
+                break;
+            }
+        }
+    }
+    chain
+}
+
+/// The direction text of a locale is written in, to shape localized strings with.
+pub fn direction(locale: &LanguageIdentifier) -> Direction {
+    match locale.character_direction() {
+        CharacterDirection::RTL => Direction::RightToLeft,
+        CharacterDirection::TTB => Direction::TopToBottom,
+        _ => Direction::LeftToRight,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_subtags_then_falls_back_to_default() {
+        let chain = parse("zh_Hans_CN").and_then(|locale| {
+            let default = parse("en-US")?;
+            Ok(fallback_chain(&locale, &default)
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>())
+        });
+        assert_eq!(
+            chain.ok(),
+            Some(vec![
+                "zh-Hans-CN".to_string(),
+                "zh-Hans".to_string(),
+                "zh".to_string(),
+                "en-US".to_string(),
+                "en".to_string(),
+            ])
+        );
+        assert!(parse("not a locale").is_err());
+    }
+}
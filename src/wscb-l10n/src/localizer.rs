@@ -0,0 +1,396 @@
+use ::std::{cell::RefCell, collections::BTreeSet, path::Path};
+
+use ::fluent_bundle::{FluentArgs, FluentBundle, FluentError, FluentResource};
+use ::fluent_syntax::ast;
+use ::unic_langid::LanguageIdentifier;
+
+use crate::{error::L10nError, locale};
+
+/// The messages of one locale.
+struct Messages {
+    locale: LanguageIdentifier,
+    bundle: FluentBundle<FluentResource>,
+    ids: BTreeSet<String>,
+}
+
+/// Localized messages of every loaded locale and the locale they are shown in.
+///
+/// Message ids can name an attribute with a dot, like `quit.tooltip`.
+pub struct Localizer {
+    default: LanguageIdentifier,
+    current: LanguageIdentifier,
+    messages: Vec<Messages>,
+    /// Indices into `messages`, in the order of the fallback chain.
+    chain: Vec<usize>,
+    missing: RefCell<BTreeSet<String>>,
+}
+
+impl Localizer {
+    /// A localizer without messages, showing the default locale.
+    ///
+    /// The default locale ends every fallback chain, it should have every message.
+    pub fn new(default: LanguageIdentifier) -> Self {
+        Self {
+            current: default.clone(),
+            default,
+            messages: Vec::new(),
+            chain: Vec::new(),
+            missing: RefCell::new(BTreeSet::new()),
+        }
+    }
+
+    /// Load every locale in a directory, a subdirectory named after each with its `.ftl` files.
+    ///
+    /// Files are added in the order of their names. A message defined twice
+    /// does not stop the loading, the [duplicates](L10nError::Duplicate) are
+    /// returned and every other message is loaded.
+    pub fn load_dir(&mut self, dir: impl AsRef<Path>) -> Result<Vec<L10nError>, L10nError> {
+        let dir = dir.as_ref();
+        let mut duplicates = Vec::new();
+        for locale_dir in read_dir(dir)? {
+            if !locale_dir.is_dir() {
+                continue;
+            }
+            let Some(name) = locale_dir.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let locale = locale::parse(name)?;
+
+            for file in read_dir(&locale_dir)? {
+                if file.extension().is_none_or(|extension| extension != "ftl") {
+                    continue;
+                }
+                let source = ::std::fs::read_to_string(&file).map_err(|source| L10nError::Io {
+                    path: file.clone(),
+                    source,
+                })?;
+                match self.add_source(&locale, &file.display().to_string(), source) {
+                    Err(error @ L10nError::Duplicate { .. }) => duplicates.push(error),
+                    added => added?,
+                }
+            }
+        }
+        Ok(duplicates)
+    }
+
+    /// Add messages in the Fluent syntax to a locale, `name` is used in errors.
+    ///
+    /// A source with syntax errors is not added at all. A source that defines
+    /// a message again keeps the earlier definition, its other messages are
+    /// added before the error is returned.
+    pub fn add_source(
+        &mut self,
+        locale: &LanguageIdentifier,
+        name: &str,
+        source: String,
+    ) -> Result<(), L10nError> {
+        let resource =
+            FluentResource::try_new(source).map_err(|(_, errors)| L10nError::Syntax {
+                name: name.to_string(),
+                errors,
+            })?;
+        let ids: Vec<String> = resource
+            .entries()
+            .filter_map(|entry| match entry {
+                ast::Entry::Message(message) => Some(message.id.name.to_string()),
+                _ => None,
+            })
+            .collect();
+
+        let index = match self
+            .messages
+            .iter()
+            .position(|messages| messages.locale == *locale)
+        {
+            Some(index) => index,
+            None => {
+                self.messages.push(Messages {
+                    locale: locale.clone(),
+                    bundle: FluentBundle::new(vec![locale.clone()]),
+                    ids: BTreeSet::new(),
+                });
+                self.update_chain();
+                self.messages.len() - 1
+            }
+        };
+
+        let messages = &mut self.messages[index];
+        let added = messages.bundle.add_resource(resource);
+        // the bundle keeps every message that was not defined yet
+        messages.ids.extend(ids);
+        added.map_err(|errors| {
+            let id = errors
+                .into_iter()
+                .find_map(|error| match error {
+                    FluentError::Overriding { id, .. } => Some(id),
+                    _ => None,
+                })
+                .unwrap_or_default();
+            L10nError::Duplicate {
+                locale: locale.clone(),
+                id,
+            }
+        })
+    }
+
+    pub fn default_locale(&self) -> &LanguageIdentifier {
+        &self.default
+    }
+
+    /// The locale messages are shown in.
+    pub fn locale(&self) -> &LanguageIdentifier {
+        &self.current
+    }
+
+    /// Show messages in another locale, it does not need to be loaded.
+    ///
+    /// Messages are looked up along its [fallback chain](locale::fallback_chain).
+    pub fn set_locale(&mut self, locale: LanguageIdentifier) {
+        self.current = locale;
+        self.update_chain();
+    }
+
+    /// The locales that have messages, in the order they were loaded.
+    pub fn available_locales(&self) -> impl Iterator<Item = &LanguageIdentifier> {
+        self.messages.iter().map(|messages| &messages.locale)
+    }
+
+    /// The loaded locales messages are looked up in, in order.
+    pub fn fallback_chain(&self) -> impl Iterator<Item = &LanguageIdentifier> {
+        self.chain.iter().map(|&index| &self.messages[index].locale)
+    }
+
+    fn update_chain(&mut self) {
+        self.chain = locale::fallback_chain(&self.current, &self.default)
+            .iter()
+            .filter_map(|locale| {
+                self.messages
+                    .iter()
+                    .position(|messages| messages.locale == *locale)
+            })
+            .collect();
+    }
+
+    /// Whether any locale on the fallback chain has the message.
+    pub fn has_message(&self, id: &str) -> bool {
+        let (message, _) = split_id(id);
+        self.chain
+            .iter()
+            .any(|&index| self.messages[index].bundle.has_message(message))
+    }
+
+    /// Format a message from the first locale on the fallback chain that has it.
+    ///
+    /// Fails when no locale has the message or it refers to arguments or
+    /// messages that do not exist.
+    pub fn format(&self, id: &str, args: Option<&FluentArgs>) -> Result<String, L10nError> {
+        let (text, errors) = self.resolve(id, args)?;
+        if errors.is_empty() {
+            Ok(text)
+        } else {
+            Err(L10nError::Format {
+                id: id.to_string(),
+                errors,
+            })
+        }
+    }
+
+    /// Format a message like [`Localizer::format`], but never fail.
+    ///
+    /// A missing message is shown as its id and remembered in
+    /// [`Localizer::missing_messages`]. Parts of a message that can not be
+    /// formatted are shown by their names, like `{$count}`.
+    pub fn text(&self, id: &str, args: Option<&FluentArgs>) -> String {
+        match self.resolve(id, args) {
+            Ok((text, _)) => text,
+            Err(_) => {
+                self.missing.borrow_mut().insert(id.to_string());
+                id.to_string()
+            }
+        }
+    }
+
+    fn resolve(
+        &self,
+        id: &str,
+        args: Option<&FluentArgs>,
+    ) -> Result<(String, Vec<FluentError>), L10nError> {
+        let (message, attribute) = split_id(id);
+        for &index in &self.chain {
+            let bundle = &self.messages[index].bundle;
+            let Some(found) = bundle.get_message(message) else {
+                continue;
+            };
+            let pattern = match attribute {
+                Some(attribute) => found
+                    .get_attribute(attribute)
+                    .map(|attribute| attribute.value()),
+                None => found.value(),
+            };
+            let Some(pattern) = pattern else {
+                continue;
+            };
+
+            let mut errors = Vec::new();
+            let text = bundle.format_pattern(pattern, args, &mut errors);
+            return Ok((text.into_owned(), errors));
+        }
+        Err(L10nError::MissingMessage(id.to_string()))
+    }
+
+    /// The ids [`Localizer::text`] could not find in any locale, sorted.
+    pub fn missing_messages(&self) -> Vec<String> {
+        self.missing.borrow().iter().cloned().collect()
+    }
+
+    pub fn clear_missing_messages(&self) {
+        self.missing.borrow_mut().clear();
+    }
+
+    /// The messages of the default locale that a locale has no translation of, sorted.
+    ///
+    /// Attributes are not compared. Every message is untranslated for a
+    /// locale without messages.
+    pub fn untranslated(&self, locale: &LanguageIdentifier) -> Vec<String> {
+        let ids_of = |locale: &LanguageIdentifier| {
+            self.messages
+                .iter()
+                .find(|messages| messages.locale == *locale)
+                .map(|messages| &messages.ids)
+        };
+        let Some(default) = ids_of(&self.default) else {
+            return Vec::new();
+        };
+        match ids_of(locale) {
+            Some(ids) => default.difference(ids).cloned().collect(),
+            None => default.iter().cloned().collect(),
+        }
+    }
+}
+
+/// Split `message.attribute` into its parts.
+fn split_id(id: &str) -> (&str, Option<&str>) {
+    match id.split_once('.') {
+        Some((message, attribute)) => (message, Some(attribute)),
+        None => (id, None),
+    }
+}
+
+/// The entries of a directory, sorted by name.
+fn read_dir(dir: &Path) -> Result<Vec<::std::path::PathBuf>, L10nError> {
+    let io_error = |source| L10nError::Io {
+        path: dir.to_path_buf(),
+        source,
+    };
+    let mut paths = ::std::fs::read_dir(dir)
+        .map_err(io_error)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(io_error)?;
+    paths.sort();
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EN: &str = "
+items = { $count ->
+    [one] one item
+   *[other] { $count } items
+}
+greeting = { $gender ->
+    [female] Welcome, heroine
+   *[other] Welcome, hero
+}
+quit = Quit
+    .tooltip = Leave the game
+";
+
+    const DE: &str = "
+items = { $count ->
+    [one] ein Gegenstand
+   *[other] { $count } Gegenstände
+}
+";
+
+    fn localizer() -> Result<Localizer, L10nError> {
+        let en = locale::parse("en-US")?;
+        let de = locale::parse("de")?;
+        let mut localizer = Localizer::new(en.clone());
+        localizer.add_source(&en, "en", EN.to_string())?;
+        localizer.add_source(&de, "de", DE.to_string())?;
+        localizer.set_locale(locale::parse("de-AT")?);
+        Ok(localizer)
+    }
+
+    #[test]
+    fn selects_variants_and_falls_back() -> Result<(), L10nError> {
+        let localizer = localizer()?;
+
+        let mut args = FluentArgs::new();
+        args.set("count", 3);
+        args.set("gender", "female");
+        let args = Some(&args);
+        // placeables are isolated for bidi
+        assert_eq!(
+            localizer.format("items", args)?,
+            "\u{2068}3\u{2069} Gegenstände"
+        );
+        assert_eq!(localizer.format("greeting", args)?, "Welcome, heroine");
+        assert_eq!(localizer.text("quit.tooltip", None), "Leave the game");
+
+        assert_eq!(localizer.text("nope", None), "nope");
+        assert_eq!(localizer.missing_messages(), ["nope"]);
+        assert!(localizer.format("items", None).is_err());
+
+        let de = localizer.locale().clone();
+        assert_eq!(
+            localizer.untranslated(&de),
+            ["greeting", "items", "quit"],
+            "de-AT itself has no messages"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn keeps_the_rest_of_a_source_with_duplicates() -> Result<(), L10nError> {
+        let mut localizer = localizer()?;
+        let de = locale::parse("de")?;
+
+        let added = localizer.add_source(&de, "more", "items = Dinge\nquit = Ende".to_string());
+        assert!(matches!(added, Err(L10nError::Duplicate { id, .. }) if id == "items"));
+        assert_eq!(localizer.format("quit", None)?, "Ende");
+        assert_eq!(localizer.untranslated(&de), ["greeting"]);
+        Ok(())
+    }
+
+    #[test]
+    fn loads_past_duplicates() -> Result<(), L10nError> {
+        let dir = ::std::env::temp_dir().join(format!("wscb-l10n-{}", ::std::process::id()));
+        let io_error = |source| L10nError::Io {
+            path: dir.clone(),
+            source,
+        };
+        let write = |file: &str, source: &str| {
+            let path = dir.join(file);
+            ::std::fs::create_dir_all(path.parent().unwrap_or(&dir))?;
+            ::std::fs::write(path, source)
+        };
+        write("en-US/a.ftl", "quit = Quit\nquit = Exit\n").map_err(io_error)?;
+        write("en-US/b.ftl", "items = Items\n").map_err(io_error)?;
+        write("de/a.ftl", "quit = Beenden\n").map_err(io_error)?;
+
+        let mut localizer = Localizer::new(locale::parse("en-US")?);
+        let loaded = localizer.load_dir(&dir);
+        ::std::fs::remove_dir_all(&dir).map_err(io_error)?;
+
+        let duplicates = loaded?;
+        assert!(matches!(duplicates.as_slice(), [L10nError::Duplicate { id, .. }] if id == "quit"));
+        assert_eq!(localizer.format("items", None)?, "Items");
+        localizer.set_locale(locale::parse("de")?);
+        assert_eq!(localizer.format("quit", None)?, "Beenden");
+        Ok(())
+    }
+}
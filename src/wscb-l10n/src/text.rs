@@ -0,0 +1,141 @@
+//! Localized messages as rich text for wscb-text.
+//!
+//! Messages can use the [markup](wscb_text::markup) of wscb-text, like
+//! `You found [b]{ $item }[/b]!`. String arguments are escaped, a player name
+//! with `[` in it is shown as it is.
+
+use ::fluent_bundle::{FluentArgs, FluentValue};
+use ::wscb_text::{
+    markup,
+    rich::{AttributedString, TextStyle},
+    shape::{Language, ShapeOptions},
+};
+
+use crate::{error::L10nError, locale, localizer::Localizer};
+
+impl Localizer {
+    /// Format a message and parse its markup, unstyled text uses `base`.
+    pub fn attributed(
+        &self,
+        id: &str,
+        args: Option<&FluentArgs>,
+        base: TextStyle,
+    ) -> Result<AttributedString, L10nError> {
+        let escaped = args.map(escape_args);
+        let text = self.format(id, escaped.as_ref())?;
+        Ok(AttributedString::from_markup(&text, base)?)
+    }
+
+    /// Like [`Localizer::attributed`], but never fail.
+    ///
+    /// Missing messages are shown as their ids like in [`Localizer::text`],
+    /// messages with broken markup as plain text.
+    pub fn attributed_text(
+        &self,
+        id: &str,
+        args: Option<&FluentArgs>,
+        base: TextStyle,
+    ) -> AttributedString {
+        let escaped = args.map(escape_args);
+        let text = self.text(id, escaped.as_ref());
+        AttributedString::from_markup(&text, base)
+            .unwrap_or_else(|_| AttributedString::new(text, base))
+    }
+
+    /// Shaping options for text in the current locale.
+    ///
+    /// The direction is that of the locale, and the language picks the glyphs
+    /// that differ between languages written in the same script, like Chinese
+    /// and Japanese kanji.
+    pub fn shape_options(&self) -> ShapeOptions {
+        ShapeOptions {
+            direction: Some(locale::direction(self.locale())),
+            language: Language::new(&self.locale().to_string()),
+            ..ShapeOptions::default()
+        }
+    }
+}
+
+fn escape_args<'a>(args: &'a FluentArgs<'a>) -> FluentArgs<'a> {
+    let mut escaped = FluentArgs::with_capacity(args.iter().count());
+    for (name, value) in args.iter() {
+        let value = match value {
+            FluentValue::String(text) => {
+                FluentValue::String(markup::escape(text).into_owned().into())
+            }
+            value => value.clone(),
+        };
+        escaped.set(name.to_string(), value);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use ::wscb_text::shape::Direction;
+
+    use super::*;
+
+    fn localizer(source: &str) -> Result<Localizer, L10nError> {
+        let en = locale::parse("en-US")?;
+        let mut localizer = Localizer::new(en.clone());
+        localizer.add_source(&en, "en", source.to_string())?;
+        Ok(localizer)
+    }
+
+    #[test]
+    fn escapes_string_args() {
+        let mut args = FluentArgs::new();
+        args.set("name", "[b]Eve");
+        args.set("count", 3);
+
+        let escaped = escape_args(&args);
+        assert!(matches!(
+            escaped.get("name"),
+            Some(FluentValue::String(name)) if name == "[[b]Eve"
+        ));
+        assert_eq!(escaped.get("count"), args.get("count"));
+    }
+
+    #[test]
+    fn styles_messages_but_not_args() -> Result<(), L10nError> {
+        let localizer = localizer("found = You found [b]{ $item }[/b]!\nbroken = oops[/b]")?;
+        let mut args = FluentArgs::new();
+        args.set("item", "[i]sword");
+        let base = TextStyle::default();
+
+        let string = localizer.attributed("found", Some(&args), base)?;
+        // the argument is isolated for bidi and keeps its brackets
+        assert_eq!(string.text(), "You found \u{2068}[i]sword\u{2069}!");
+        let style = |offset| string.style_at(offset).copied();
+        assert_eq!(style(0), Some(base));
+        let sword = "You found \u{2068}[i]".len();
+        assert_eq!(style(sword), Some(TextStyle { bold: true, ..base }));
+
+        assert!(localizer.attributed("broken", None, base).is_err());
+        assert_eq!(
+            localizer.attributed_text("broken", None, base).text(),
+            "oops[/b]"
+        );
+        assert_eq!(localizer.attributed_text("nope", None, base).text(), "nope");
+        Ok(())
+    }
+
+    #[test]
+    fn shapes_in_the_direction_of_the_locale() -> Result<(), L10nError> {
+        let mut localizer = localizer("quit = Quit")?;
+        assert_eq!(
+            localizer.shape_options().direction,
+            Some(Direction::LeftToRight)
+        );
+
+        localizer.set_locale(locale::parse("ar")?);
+        let options = localizer.shape_options();
+        assert_eq!(options.direction, Some(Direction::RightToLeft));
+        assert_eq!(
+            options.language.map(|language| language.as_str()),
+            Some("ar")
+        );
+        Ok(())
+    }
+}
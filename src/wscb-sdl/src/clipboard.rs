@@ -0,0 +1,33 @@
+use ::std::ffi::{CStr, CString};
+
+use ::wscb_type::error::SdlError;
+
+/// Put text on the system clipboard.
+pub fn set_clipboard_text(text: &str) -> Result<(), SdlError> {
+    let c_text =
+        CString::new(text).map_err(|_| SdlError::sdl_err("clipboard text contains a nul byte"))?;
+    unsafe {
+        if !sdl3_sys::clipboard::SDL_SetClipboardText(c_text.as_ptr()) {
+            return Err(SdlError::sdl_err("failed to set clipboard text"));
+        }
+    }
+    Ok(())
+}
+
+/// The text on the system clipboard, empty when there is none.
+pub fn clipboard_text() -> Result<String, SdlError> {
+    unsafe {
+        let text = sdl3_sys::clipboard::SDL_GetClipboardText();
+        if text.is_null() {
+            return Err(SdlError::sdl_err("failed to get clipboard text"));
+        }
+        let owned = CStr::from_ptr(text).to_string_lossy().into_owned();
+        sdl3_sys::stdinc::SDL_free(text.cast());
+        Ok(owned)
+    }
+}
+
+/// Whether the system clipboard has non-empty text.
+pub fn has_clipboard_text() -> bool {
+    unsafe { sdl3_sys::clipboard::SDL_HasClipboardText() }
+}
@@ -0,0 +1,550 @@
+use ::std::{
+    ffi::{CStr, c_char},
+    mem::MaybeUninit,
+    ops::Range,
+    path::PathBuf,
+    time::Duration,
+};
+
+use ::sdl3_sys::{
+    events::*,
+    gamepad::{SDL_GamepadAxis, SDL_GamepadButton},
+    keycode::{SDL_Keycode, SDL_Keymod},
+    scancode::SDL_Scancode,
+};
+use ::wscb_type::{HashMap, error::SdlError, graph, graph_f::Point};
+
+use crate::{
+    gamepad::Gamepad,
+    sdl::{EventsSubsystem, GamepadSubsystem},
+};
+
+/// What happened to a window, see [`Event::Window`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowEvent {
+    Shown,
+    Hidden,
+    /// Part of the window has to be drawn again.
+    Exposed,
+    /// The top left corner of the client area moved there.
+    Moved(graph::Point),
+    /// The new size in points.
+    Resized(graph::Size),
+    /// The new size of the backbuffer in pixels, it changes with the size
+    /// and with the pixel density.
+    PixelSizeChanged(graph::Size),
+    /// The window moved to a display with another scale, see
+    /// [`Window::display_scale`](crate::window::Window::display_scale).
+    DisplayScaleChanged,
+    Minimized,
+    Maximized,
+    Restored,
+    MouseEnter,
+    MouseLeave,
+    FocusGained,
+    FocusLost,
+    /// The user asked to close the window, it stays open until it is dropped.
+    CloseRequested,
+    EnterFullscreen,
+    LeaveFullscreen,
+}
+
+/// A mouse button, see [`Event::MouseButton`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+    /// The first side button, usually back.
+    X1,
+    /// The second side button, usually forward.
+    X2,
+    Other(u8),
+}
+
+impl MouseButton {
+    fn from_raw(button: u8) -> Self {
+        match i32::from(button) {
+            sdl3_sys::mouse::SDL_BUTTON_LEFT => MouseButton::Left,
+            sdl3_sys::mouse::SDL_BUTTON_MIDDLE => MouseButton::Middle,
+            sdl3_sys::mouse::SDL_BUTTON_RIGHT => MouseButton::Right,
+            sdl3_sys::mouse::SDL_BUTTON_X1 => MouseButton::X1,
+            sdl3_sys::mouse::SDL_BUTTON_X2 => MouseButton::X2,
+            _ => MouseButton::Other(button),
+        }
+    }
+}
+
+/// What happened to a gamepad, see [`Event::Gamepad`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GamepadEvent {
+    /// A gamepad was plugged in, or was already there when the gamepad subsystem started.
+    ///
+    /// Its buttons and axes are reported once it is opened, see [`Gamepad`].
+    Added,
+    Removed,
+    Button {
+        button: SDL_GamepadButton,
+        down: bool,
+    },
+    /// Sticks go from -32768 to 32767 and triggers from 0 to 32767.
+    Axis {
+        axis: SDL_GamepadAxis,
+        value: i16,
+    },
+}
+
+/// An SDL event.
+///
+/// The timestamps are the time since SDL started. Window ids match
+/// [`Window::id`](crate::window::Window::id), they are 0 for events without a window.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// The user asked to quit, like by closing the last window.
+    Quit { timestamp: Duration },
+    Window {
+        timestamp: Duration,
+        window_id: u32,
+        event: WindowEvent,
+    },
+    Key {
+        timestamp: Duration,
+        window_id: u32,
+        /// The key by its place on the keyboard, the same on every layout.
+        scancode: SDL_Scancode,
+        /// The key by what it means on the current layout.
+        keycode: SDL_Keycode,
+        keymod: SDL_Keymod,
+        down: bool,
+        /// Sent again because the key is held.
+        repeat: bool,
+    },
+    /// Committed text, from the keyboard or the IME.
+    TextInput {
+        timestamp: Duration,
+        window_id: u32,
+        text: String,
+    },
+    /// The text the IME is composing, empty when the composition ended.
+    ///
+    /// `cursor` is the byte range of the clause being worked on in `text`,
+    /// SDL counts it in characters. It is what the preedit of a `wscb_text`
+    /// text buffer takes.
+    TextEditing {
+        timestamp: Duration,
+        window_id: u32,
+        text: String,
+        cursor: Option<Range<usize>>,
+    },
+    MouseMotion {
+        timestamp: Duration,
+        window_id: u32,
+        position: Point,
+        /// The motion since the last event, also when the mouse is at the edge of the screen.
+        delta: Point,
+    },
+    MouseButton {
+        timestamp: Duration,
+        window_id: u32,
+        button: MouseButton,
+        down: bool,
+        /// 1 for a single click, 2 for a double click and so on.
+        clicks: u8,
+        position: Point,
+    },
+    MouseWheel {
+        timestamp: Duration,
+        window_id: u32,
+        /// Positive y scrolls away from the user and positive x to the
+        /// right, whether the system flips the direction or not.
+        delta: Point,
+        position: Point,
+    },
+    Gamepad {
+        timestamp: Duration,
+        /// The joystick id of the gamepad, it stays the same while it is plugged in.
+        gamepad_id: u32,
+        event: GamepadEvent,
+    },
+    DropFile {
+        timestamp: Duration,
+        window_id: u32,
+        path: PathBuf,
+        position: Point,
+    },
+    /// Render targets lost their content and have to be drawn again.
+    ///
+    /// With `device` the renderer itself was reset and every texture has
+    /// to be created again.
+    RenderReset {
+        timestamp: Duration,
+        window_id: u32,
+        device: bool,
+    },
+    /// An event without a variant of its own, by its `SDL_EventType`.
+    Other { timestamp: Duration, kind: u32 },
+}
+
+impl Event {
+    /// # Safety
+    ///
+    /// `raw` has to be an event filled in by SDL, the pointers in it have to be valid.
+    unsafe fn from_raw(raw: &SDL_Event) -> Self {
+        unsafe {
+            let kind = SDL_EventType(raw.r#type);
+            let timestamp = Duration::from_nanos(raw.common.timestamp);
+            match kind {
+                SDL_EVENT_QUIT => Event::Quit { timestamp },
+                SDL_EVENT_KEY_DOWN | SDL_EVENT_KEY_UP => Event::Key {
+                    timestamp,
+                    window_id: raw.key.windowID.0,
+                    scancode: raw.key.scancode,
+                    keycode: raw.key.key,
+                    keymod: raw.key.r#mod,
+                    down: raw.key.down,
+                    repeat: raw.key.repeat,
+                },
+                SDL_EVENT_TEXT_INPUT => Event::TextInput {
+                    timestamp,
+                    window_id: raw.text.windowID.0,
+                    text: string(raw.text.text),
+                },
+                SDL_EVENT_TEXT_EDITING => {
+                    let text = string(raw.edit.text);
+                    let cursor = byte_range(&text, raw.edit.start, raw.edit.length);
+                    Event::TextEditing {
+                        timestamp,
+                        window_id: raw.edit.windowID.0,
+                        text,
+                        cursor,
+                    }
+                }
+                SDL_EVENT_MOUSE_MOTION => Event::MouseMotion {
+                    timestamp,
+                    window_id: raw.motion.windowID.0,
+                    position: Point::new(raw.motion.x, raw.motion.y),
+                    delta: Point::new(raw.motion.xrel, raw.motion.yrel),
+                },
+                SDL_EVENT_MOUSE_BUTTON_DOWN | SDL_EVENT_MOUSE_BUTTON_UP => Event::MouseButton {
+                    timestamp,
+                    window_id: raw.button.windowID.0,
+                    button: MouseButton::from_raw(raw.button.button),
+                    down: raw.button.down,
+                    clicks: raw.button.clicks,
+                    position: Point::new(raw.button.x, raw.button.y),
+                },
+                SDL_EVENT_MOUSE_WHEEL => {
+                    let wheel = &raw.wheel;
+                    let sign = if wheel.direction == sdl3_sys::mouse::SDL_MOUSEWHEEL_FLIPPED {
+                        -1.0
+                    } else {
+                        1.0
+                    };
+                    Event::MouseWheel {
+                        timestamp,
+                        window_id: wheel.windowID.0,
+                        delta: Point::new(wheel.x * sign, wheel.y * sign),
+                        position: Point::new(wheel.mouse_x, wheel.mouse_y),
+                    }
+                }
+                SDL_EVENT_GAMEPAD_ADDED | SDL_EVENT_GAMEPAD_REMOVED => Event::Gamepad {
+                    timestamp,
+                    gamepad_id: raw.gdevice.which.0,
+                    event: if kind == SDL_EVENT_GAMEPAD_ADDED {
+                        GamepadEvent::Added
+                    } else {
+                        GamepadEvent::Removed
+                    },
+                },
+                SDL_EVENT_GAMEPAD_BUTTON_DOWN | SDL_EVENT_GAMEPAD_BUTTON_UP => Event::Gamepad {
+                    timestamp,
+                    gamepad_id: raw.gbutton.which.0,
+                    event: GamepadEvent::Button {
+                        button: SDL_GamepadButton(raw.gbutton.button.into()),
+                        down: raw.gbutton.down,
+                    },
+                },
+                SDL_EVENT_GAMEPAD_AXIS_MOTION => Event::Gamepad {
+                    timestamp,
+                    gamepad_id: raw.gaxis.which.0,
+                    event: GamepadEvent::Axis {
+                        axis: SDL_GamepadAxis(raw.gaxis.axis.into()),
+                        value: raw.gaxis.value,
+                    },
+                },
+                SDL_EVENT_DROP_FILE => Event::DropFile {
+                    timestamp,
+                    window_id: raw.drop.windowID.0,
+                    path: PathBuf::from(string(raw.drop.data)),
+                    position: Point::new(raw.drop.x, raw.drop.y),
+                },
+                SDL_EVENT_RENDER_TARGETS_RESET | SDL_EVENT_RENDER_DEVICE_RESET => {
+                    Event::RenderReset {
+                        timestamp,
+                        window_id: raw.render.windowID.0,
+                        device: kind == SDL_EVENT_RENDER_DEVICE_RESET,
+                    }
+                }
+                _ => match window_event(kind, &raw.window) {
+                    Some(event) => Event::Window {
+                        timestamp,
+                        window_id: raw.window.windowID.0,
+                        event,
+                    },
+                    None => Event::Other {
+                        timestamp,
+                        kind: kind.0,
+                    },
+                },
+            }
+        }
+    }
+
+    pub fn timestamp(&self) -> Duration {
+        match self {
+            Event::Quit { timestamp }
+            | Event::Window { timestamp, .. }
+            | Event::Key { timestamp, .. }
+            | Event::TextInput { timestamp, .. }
+            | Event::TextEditing { timestamp, .. }
+            | Event::MouseMotion { timestamp, .. }
+            | Event::MouseButton { timestamp, .. }
+            | Event::MouseWheel { timestamp, .. }
+            | Event::Gamepad { timestamp, .. }
+            | Event::DropFile { timestamp, .. }
+            | Event::RenderReset { timestamp, .. }
+            | Event::Other { timestamp, .. } => *timestamp,
+        }
+    }
+
+    /// The window the event happened in, `None` for events without one.
+    pub fn window_id(&self) -> Option<u32> {
+        match self {
+            Event::Window { window_id, .. }
+            | Event::Key { window_id, .. }
+            | Event::TextInput { window_id, .. }
+            | Event::TextEditing { window_id, .. }
+            | Event::MouseMotion { window_id, .. }
+            | Event::MouseButton { window_id, .. }
+            | Event::MouseWheel { window_id, .. }
+            | Event::DropFile { window_id, .. }
+            | Event::RenderReset { window_id, .. } => Some(*window_id).filter(|&id| id != 0),
+            Event::Quit { .. } | Event::Gamepad { .. } | Event::Other { .. } => None,
+        }
+    }
+}
+
+fn window_event(kind: SDL_EventType, window: &SDL_WindowEvent) -> Option<WindowEvent> {
+    let event = match kind {
+        SDL_EVENT_WINDOW_SHOWN => WindowEvent::Shown,
+        SDL_EVENT_WINDOW_HIDDEN => WindowEvent::Hidden,
+        SDL_EVENT_WINDOW_EXPOSED => WindowEvent::Exposed,
+        SDL_EVENT_WINDOW_MOVED => WindowEvent::Moved(graph::Point::new(window.data1, window.data2)),
+        SDL_EVENT_WINDOW_RESIZED => {
+            WindowEvent::Resized(graph::Size::new(window.data1, window.data2))
+        }
+        SDL_EVENT_WINDOW_PIXEL_SIZE_CHANGED => {
+            WindowEvent::PixelSizeChanged(graph::Size::new(window.data1, window.data2))
+        }
+        SDL_EVENT_WINDOW_DISPLAY_SCALE_CHANGED => WindowEvent::DisplayScaleChanged,
+        SDL_EVENT_WINDOW_MINIMIZED => WindowEvent::Minimized,
+        SDL_EVENT_WINDOW_MAXIMIZED => WindowEvent::Maximized,
+        SDL_EVENT_WINDOW_RESTORED => WindowEvent::Restored,
+        SDL_EVENT_WINDOW_MOUSE_ENTER => WindowEvent::MouseEnter,
+        SDL_EVENT_WINDOW_MOUSE_LEAVE => WindowEvent::MouseLeave,
+        SDL_EVENT_WINDOW_FOCUS_GAINED => WindowEvent::FocusGained,
+        SDL_EVENT_WINDOW_FOCUS_LOST => WindowEvent::FocusLost,
+        SDL_EVENT_WINDOW_CLOSE_REQUESTED => WindowEvent::CloseRequested,
+        SDL_EVENT_WINDOW_ENTER_FULLSCREEN => WindowEvent::EnterFullscreen,
+        SDL_EVENT_WINDOW_LEAVE_FULLSCREEN => WindowEvent::LeaveFullscreen,
+        _ => return None,
+    };
+    Some(event)
+}
+
+/// Copy a string owned by an SDL event, it is freed with the event.
+unsafe fn string(text: *const c_char) -> String {
+    if text.is_null() {
+        return String::new();
+    }
+    unsafe { CStr::from_ptr(text).to_string_lossy().into_owned() }
+}
+
+/// Turn a range of characters in `text`, as SDL counts them, into a range of bytes.
+fn byte_range(text: &str, start: i32, length: i32) -> Option<Range<usize>> {
+    let start = usize::try_from(start).ok()?;
+    let length = usize::try_from(length).unwrap_or(0);
+    let offset = |chars: usize| {
+        text.char_indices()
+            .nth(chars)
+            .map_or(text.len(), |(offset, _)| offset)
+    };
+    Some(offset(start)..offset(start + length))
+}
+
+/// The queue of SDL events, on the main thread.
+///
+/// Events are only queued for windows and devices of running subsystems,
+/// keep an [`EventsSubsystem`] token, like the one of the video subsystem, alive.
+#[derive(Debug)]
+pub struct EventPump {
+    _events: EventsSubsystem,
+    /// The subsystem gamepads are opened with, `None` leaves them to the caller.
+    gamepads: Option<GamepadSubsystem>,
+    open: HashMap<u32, Gamepad>,
+}
+
+impl EventPump {
+    pub fn new(events: &EventsSubsystem) -> Self {
+        Self {
+            _events: events.clone(),
+            gamepads: None,
+            open: HashMap::default(),
+        }
+    }
+
+    /// A pump that opens gamepads when they are added and closes them when
+    /// they are removed, so their button and axis events are queued.
+    ///
+    /// SDL adds the gamepads that are already plugged in when the subsystem starts.
+    pub fn with_gamepads(events: &EventsSubsystem, gamepads: &GamepadSubsystem) -> Self {
+        Self {
+            gamepads: Some(gamepads.clone()),
+            ..Self::new(events)
+        }
+    }
+
+    /// The gamepads the pump opened.
+    pub fn gamepads(&self) -> impl Iterator<Item = &Gamepad> {
+        self.open.values()
+    }
+
+    /// # Safety
+    ///
+    /// Like [`Event::from_raw`].
+    unsafe fn event(&mut self, raw: &SDL_Event) -> Event {
+        let event = unsafe { Event::from_raw(raw) };
+
+        if let Some(gamepads) = &self.gamepads
+            && let Event::Gamepad {
+                gamepad_id, event, ..
+            } = &event
+        {
+            match event {
+                // a gamepad that fails to open only sends no buttons and axes
+                GamepadEvent::Added => {
+                    if let Ok(gamepad) = Gamepad::open(gamepads, *gamepad_id) {
+                        self.open.insert(*gamepad_id, gamepad);
+                    }
+                }
+                GamepadEvent::Removed => {
+                    self.open.remove(gamepad_id);
+                }
+                GamepadEvent::Button { .. } | GamepadEvent::Axis { .. } => {}
+            }
+        }
+        event
+    }
+
+    /// The next event, `None` when the queue is empty.
+    pub fn poll(&mut self) -> Option<Event> {
+        let mut raw = MaybeUninit::<SDL_Event>::uninit();
+        unsafe {
+            if !SDL_PollEvent(raw.as_mut_ptr()) {
+                return None;
+            }
+            Some(self.event(raw.assume_init_ref()))
+        }
+    }
+
+    /// Every event in the queue, to handle once a frame.
+    pub fn poll_iter(&mut self) -> impl Iterator<Item = Event> + '_ {
+        ::std::iter::from_fn(|| self.poll())
+    }
+
+    /// Sleep until there is an event.
+    pub fn wait(&mut self) -> Result<Event, SdlError> {
+        let mut raw = MaybeUninit::<SDL_Event>::uninit();
+        unsafe {
+            if !SDL_WaitEvent(raw.as_mut_ptr()) {
+                return Err(SdlError::sdl_err("failed to wait for an event"));
+            }
+            Ok(self.event(raw.assume_init_ref()))
+        }
+    }
+
+    /// Sleep until there is an event, `None` when there is none after `timeout`.
+    pub fn wait_timeout(&mut self, timeout: Duration) -> Option<Event> {
+        let timeout = i32::try_from(timeout.as_millis()).unwrap_or(i32::MAX);
+        let mut raw = MaybeUninit::<SDL_Event>::uninit();
+        unsafe {
+            if !SDL_WaitEventTimeout(raw.as_mut_ptr(), timeout) {
+                return None;
+            }
+            Some(self.event(raw.assume_init_ref()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::sdl3_sys::video::SDL_WindowID;
+
+    use super::*;
+
+    #[test]
+    fn counts_preedit_cursor_in_chars() {
+        let text = "aé日😀b";
+        assert_eq!(byte_range(text, 0, 0), Some(0..0));
+        assert_eq!(byte_range(text, 1, 2), Some(1..6));
+        assert_eq!(byte_range(text, 3, 1), Some(6..10));
+        // a negative length is an empty selection, past the end is the end
+        assert_eq!(byte_range(text, 4, -1), Some(10..10));
+        assert_eq!(byte_range(text, 4, 9), Some(10..11));
+        assert_eq!(byte_range(text, 9, 1), Some(11..11));
+        // no cursor
+        assert_eq!(byte_range(text, -1, 0), None);
+    }
+
+    #[test]
+    fn converts_text_editing_events() {
+        let text = c"にほんご";
+        let raw = SDL_Event {
+            edit: SDL_TextEditingEvent {
+                r#type: SDL_EVENT_TEXT_EDITING,
+                windowID: SDL_WindowID(1),
+                text: text.as_ptr(),
+                start: 1,
+                length: 2,
+                ..SDL_TextEditingEvent::default()
+            },
+        };
+
+        let event = unsafe { Event::from_raw(&raw) };
+        assert_eq!(
+            event,
+            Event::TextEditing {
+                timestamp: Duration::ZERO,
+                window_id: 1,
+                text: "にほんご".to_string(),
+                cursor: Some(3..9),
+            }
+        );
+    }
+
+    #[test]
+    fn maps_mouse_buttons() {
+        let buttons: Vec<MouseButton> = (1..=6).map(MouseButton::from_raw).collect();
+        assert_eq!(
+            buttons,
+            [
+                MouseButton::Left,
+                MouseButton::Middle,
+                MouseButton::Right,
+                MouseButton::X1,
+                MouseButton::X2,
+                MouseButton::Other(6),
+            ]
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use ::std::ptr::NonNull;
+
+use ::sdl3_sys::{
+    gamepad::{SDL_CloseGamepad, SDL_Gamepad, SDL_GetGamepadName, SDL_OpenGamepad},
+    joystick::SDL_JoystickID,
+};
+use ::wscb_type::error::SdlError;
+
+use crate::sdl::GamepadSubsystem;
+
+/// An open gamepad, closed when dropped.
+///
+/// SDL only sends the button and axis events of open gamepads.
+/// [`EventPump::with_gamepads`](crate::event::EventPump::with_gamepads)
+/// opens every gamepad that is plugged in on its own.
+#[derive(Debug)]
+pub struct Gamepad {
+    pointer: NonNull<SDL_Gamepad>,
+    id: u32,
+    /// Keeps the gamepad subsystem running while the gamepad is open.
+    _gamepads: GamepadSubsystem,
+}
+
+impl Gamepad {
+    /// Open a gamepad by the id of its [`GamepadEvent::Added`](crate::event::GamepadEvent::Added) event.
+    pub fn open(gamepads: &GamepadSubsystem, gamepad_id: u32) -> Result<Self, SdlError> {
+        let pointer = unsafe { SDL_OpenGamepad(SDL_JoystickID(gamepad_id)) };
+        let pointer = NonNull::new(pointer)
+            .ok_or_else(|| SdlError::sdl_err(&format!("failed to open gamepad {gamepad_id}")))?;
+
+        Ok(Self {
+            pointer,
+            id: gamepad_id,
+            _gamepads: gamepads.clone(),
+        })
+    }
+
+    pub fn get_pointer(&self) -> *mut SDL_Gamepad {
+        self.pointer.as_ptr()
+    }
+
+    /// The joystick id of the gamepad, the one its events carry.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// The name of the gamepad, `None` when it has none.
+    pub fn name(&self) -> Option<String> {
+        unsafe {
+            let name = SDL_GetGamepadName(self.get_pointer());
+            if name.is_null() {
+                return None;
+            }
+            Some(
+                std::ffi::CStr::from_ptr(name)
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+        }
+    }
+}
+
+impl Drop for Gamepad {
+    fn drop(&mut self) {
+        unsafe {
+            SDL_CloseGamepad(self.pointer.as_ptr());
+        }
+    }
+}
@@ -0,0 +1,844 @@
+//! The state of the keyboard, mouse and gamepads, and game actions bound to them.
+//!
+//! Actions are loaded from a config file with one binding per line, an
+//! action named again gets another binding:
+//!
+//! ```text
+//! # action = binding
+//! jump = key Space
+//! jump = gamepad a
+//! fire = mouse left
+//! move_x = axis leftx
+//! move_x = key D
+//! move_x = -key A
+//! ```
+//!
+//! `key` binds a key by its place on the keyboard and `keycode` by what it
+//! means on the current layout, both take SDL key names. `mouse` takes
+//! `left`, `middle`, `right`, `x1`, `x2` or a button number, `gamepad` and
+//! `axis` take SDL gamepad button and axis names. A leading `-` negates
+//! the binding, for axes driven by keys.
+
+use ::std::{
+    collections::{BTreeMap, hash_map::Entry},
+    ffi::{CStr, CString, c_char},
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use ::sdl3_sys::{
+    gamepad::{SDL_GamepadAxis, SDL_GamepadButton},
+    keycode::SDL_Keycode,
+    scancode::SDL_Scancode,
+};
+use ::thiserror::Error;
+use ::wscb_type::{HashMap, HashSet, graph_f::Point};
+
+use crate::event::{Event, GamepadEvent, MouseButton, WindowEvent};
+
+/// An error parsing a binding or loading actions.
+#[derive(Debug, Error)]
+pub enum InputError {
+    #[error("`{0}` is not an input binding")]
+    InvalidBinding(String),
+    #[error("line {line} of the action config: {message}")]
+    ActionConfig { line: usize, message: String },
+    #[error("failed to read {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: ::std::io::Error,
+    },
+}
+
+/// Buttons held, pressed and released this frame.
+#[derive(Debug, Clone)]
+struct Buttons<T> {
+    held: HashSet<T>,
+    pressed: HashSet<T>,
+    released: HashSet<T>,
+}
+
+impl<T> Default for Buttons<T> {
+    fn default() -> Self {
+        Self {
+            held: HashSet::default(),
+            pressed: HashSet::default(),
+            released: HashSet::default(),
+        }
+    }
+}
+
+impl<T: Copy + Eq + ::std::hash::Hash> Buttons<T> {
+    fn set(&mut self, button: T, down: bool) {
+        if down {
+            if self.held.insert(button) {
+                self.pressed.insert(button);
+            }
+        } else if self.held.remove(&button) {
+            self.released.insert(button);
+        }
+    }
+
+    fn release_all(&mut self) {
+        self.released.extend(self.held.drain());
+    }
+
+    fn begin_frame(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+    }
+
+    /// Held, or pressed and released again within the frame.
+    fn is_active(&self, button: &T) -> bool {
+        self.held.contains(button) || self.pressed.contains(button)
+    }
+}
+
+/// The state of the keyboard, mouse and gamepads, made from events.
+///
+/// Call [`InputState::begin_frame`] before the events of a frame and
+/// [`InputState::handle_event`] with each. Gamepads are merged, a button
+/// is held while any gamepad holds it.
+#[derive(Debug, Clone)]
+pub struct InputState {
+    keys: Buttons<SDL_Scancode>,
+    keycodes: Buttons<SDL_Keycode>,
+    /// The keycode each held key was pressed as, to release it when the layout changed since.
+    pressed_as: HashMap<SDL_Scancode, SDL_Keycode>,
+    mouse: Buttons<MouseButton>,
+    mouse_position: Point,
+    mouse_delta: Point,
+    wheel: Point,
+    /// The buttons each gamepad holds.
+    gamepad_held: HashSet<(u32, SDL_GamepadButton)>,
+    /// The buttons of all gamepads merged, held while any gamepad holds them.
+    gamepad: Buttons<SDL_GamepadButton>,
+    axes: HashMap<(u32, SDL_GamepadAxis), f32>,
+}
+
+impl Default for InputState {
+    fn default() -> Self {
+        Self {
+            keys: Buttons::default(),
+            keycodes: Buttons::default(),
+            pressed_as: HashMap::default(),
+            mouse: Buttons::default(),
+            mouse_position: Point::new(0.0, 0.0),
+            mouse_delta: Point::new(0.0, 0.0),
+            wheel: Point::new(0.0, 0.0),
+            gamepad_held: HashSet::default(),
+            gamepad: Buttons::default(),
+            axes: HashMap::default(),
+        }
+    }
+}
+
+impl InputState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget what was pressed and released and how far the mouse moved in the last frame.
+    pub fn begin_frame(&mut self) {
+        self.keys.begin_frame();
+        self.keycodes.begin_frame();
+        self.mouse.begin_frame();
+        self.gamepad.begin_frame();
+        self.mouse_delta = Point::new(0.0, 0.0);
+        self.wheel = Point::new(0.0, 0.0);
+    }
+
+    /// Update the state with an event, other events than input are ignored.
+    ///
+    /// Everything held is released when a window loses focus, it would not
+    /// see the release otherwise.
+    pub fn handle_event(&mut self, event: &Event) {
+        match *event {
+            Event::Key {
+                scancode,
+                keycode,
+                down,
+                ..
+            } => {
+                self.keys.set(scancode, down);
+                if down {
+                    if let Entry::Vacant(entry) = self.pressed_as.entry(scancode) {
+                        entry.insert(keycode);
+                        self.keycodes.set(keycode, true);
+                    }
+                } else {
+                    let keycode = self.pressed_as.remove(&scancode).unwrap_or(keycode);
+                    self.keycodes.set(keycode, false);
+                }
+            }
+            Event::MouseMotion {
+                position, delta, ..
+            } => {
+                self.mouse_position = position;
+                self.mouse_delta.x += delta.x;
+                self.mouse_delta.y += delta.y;
+            }
+            Event::MouseButton {
+                button,
+                down,
+                position,
+                ..
+            } => {
+                self.mouse_position = position;
+                self.mouse.set(button, down);
+            }
+            Event::MouseWheel { delta, .. } => {
+                self.wheel.x += delta.x;
+                self.wheel.y += delta.y;
+            }
+            Event::Gamepad {
+                gamepad_id, event, ..
+            } => match event {
+                GamepadEvent::Button { button, down } => {
+                    self.set_gamepad_button(gamepad_id, button, down);
+                }
+                GamepadEvent::Axis { axis, value } => {
+                    let value = (f32::from(value) / f32::from(i16::MAX)).max(-1.0);
+                    self.axes.insert((gamepad_id, axis), value);
+                }
+                GamepadEvent::Removed => {
+                    let held: Vec<_> = (self.gamepad_held.iter())
+                        .filter(|(id, _)| *id == gamepad_id)
+                        .map(|&(_, button)| button)
+                        .collect();
+                    for button in held {
+                        self.set_gamepad_button(gamepad_id, button, false);
+                    }
+                    self.axes.retain(|(id, _), _| *id != gamepad_id);
+                }
+                GamepadEvent::Added => {}
+            },
+            Event::Window {
+                event: WindowEvent::FocusLost,
+                ..
+            } => {
+                self.keys.release_all();
+                self.keycodes.release_all();
+                self.pressed_as.clear();
+                self.mouse.release_all();
+                self.gamepad_held.clear();
+                self.gamepad.release_all();
+            }
+            _ => {}
+        }
+    }
+
+    /// Update the button of one gamepad, the merged button changes when the
+    /// first gamepad presses it or the last one releases it.
+    fn set_gamepad_button(&mut self, gamepad_id: u32, button: SDL_GamepadButton, down: bool) {
+        if down {
+            self.gamepad_held.insert((gamepad_id, button));
+            self.gamepad.set(button, true);
+        } else if self.gamepad_held.remove(&(gamepad_id, button))
+            && !self.gamepad_held.iter().any(|&(_, held)| held == button)
+        {
+            self.gamepad.set(button, false);
+        }
+    }
+
+    pub fn is_key_held(&self, scancode: SDL_Scancode) -> bool {
+        self.keys.held.contains(&scancode)
+    }
+
+    /// Whether the key went down this frame, repeats do not count.
+    pub fn is_key_pressed(&self, scancode: SDL_Scancode) -> bool {
+        self.keys.pressed.contains(&scancode)
+    }
+
+    pub fn is_key_released(&self, scancode: SDL_Scancode) -> bool {
+        self.keys.released.contains(&scancode)
+    }
+
+    pub fn is_keycode_held(&self, keycode: SDL_Keycode) -> bool {
+        self.keycodes.held.contains(&keycode)
+    }
+
+    pub fn is_keycode_pressed(&self, keycode: SDL_Keycode) -> bool {
+        self.keycodes.pressed.contains(&keycode)
+    }
+
+    pub fn is_keycode_released(&self, keycode: SDL_Keycode) -> bool {
+        self.keycodes.released.contains(&keycode)
+    }
+
+    pub fn is_mouse_held(&self, button: MouseButton) -> bool {
+        self.mouse.held.contains(&button)
+    }
+
+    pub fn is_mouse_pressed(&self, button: MouseButton) -> bool {
+        self.mouse.pressed.contains(&button)
+    }
+
+    pub fn is_mouse_released(&self, button: MouseButton) -> bool {
+        self.mouse.released.contains(&button)
+    }
+
+    /// Where the mouse was last seen, in the window it was in.
+    pub fn mouse_position(&self) -> Point {
+        self.mouse_position
+    }
+
+    /// How far the mouse moved this frame.
+    pub fn mouse_delta(&self) -> Point {
+        self.mouse_delta
+    }
+
+    /// How far the wheel scrolled this frame, see [`Event::MouseWheel`].
+    pub fn wheel(&self) -> Point {
+        self.wheel
+    }
+
+    pub fn is_gamepad_held(&self, button: SDL_GamepadButton) -> bool {
+        self.gamepad.held.contains(&button)
+    }
+
+    /// Whether a gamepad pressed the button this frame and no other one held it already.
+    pub fn is_gamepad_pressed(&self, button: SDL_GamepadButton) -> bool {
+        self.gamepad.pressed.contains(&button)
+    }
+
+    /// Whether a gamepad released the button this frame and no other one still holds it.
+    pub fn is_gamepad_released(&self, button: SDL_GamepadButton) -> bool {
+        self.gamepad.released.contains(&button)
+    }
+
+    /// The axis of the gamepad that moves it the furthest, from -1 to 1.
+    pub fn gamepad_axis(&self, axis: SDL_GamepadAxis) -> f32 {
+        self.axes
+            .iter()
+            .filter(|((_, other), _)| *other == axis)
+            .map(|(_, &value)| value)
+            .fold(0.0, |furthest: f32, value| {
+                if value.abs() > furthest.abs() {
+                    value
+                } else {
+                    furthest
+                }
+            })
+    }
+
+    /// The keys and buttons pressed this frame, to bind an action to what the player presses.
+    pub fn pressed_inputs(&self) -> impl Iterator<Item = Input> + '_ {
+        let keys = self.keys.pressed.iter().map(|&key| Input::Key(key));
+        let mouse = self
+            .mouse
+            .pressed
+            .iter()
+            .map(|&button| Input::Mouse(button));
+        let gamepad = (self.gamepad.pressed.iter()).map(|&button| Input::GamepadButton(button));
+        keys.chain(mouse).chain(gamepad)
+    }
+
+    /// How far an input is pushed, 1 for a held button and -1 to 1 for an axis.
+    ///
+    /// A button pressed and released within the frame counts as held.
+    pub fn value(&self, input: Input) -> f32 {
+        let button = |active: bool| if active { 1.0 } else { 0.0 };
+        match input {
+            Input::Key(scancode) => button(self.keys.is_active(&scancode)),
+            Input::Keycode(keycode) => button(self.keycodes.is_active(&keycode)),
+            Input::Mouse(mouse) => button(self.mouse.is_active(&mouse)),
+            Input::GamepadButton(gamepad) => button(self.gamepad.is_active(&gamepad)),
+            Input::GamepadAxis(axis) => self.gamepad_axis(axis),
+        }
+    }
+}
+
+/// A key, button or axis an action can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Input {
+    /// A key by its place on the keyboard, the same on every layout.
+    Key(SDL_Scancode),
+    /// A key by what it means on the current layout.
+    Keycode(SDL_Keycode),
+    Mouse(MouseButton),
+    GamepadButton(SDL_GamepadButton),
+    GamepadAxis(SDL_GamepadAxis),
+}
+
+/// An input bound to an action, negated ones push axes the other way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Binding {
+    pub input: Input,
+    pub negate: bool,
+}
+
+impl Binding {
+    pub fn new(input: Input) -> Self {
+        Self {
+            input,
+            negate: false,
+        }
+    }
+
+    pub fn negated(input: Input) -> Self {
+        Self {
+            input,
+            negate: true,
+        }
+    }
+}
+
+impl From<Input> for Binding {
+    fn from(input: Input) -> Self {
+        Binding::new(input)
+    }
+}
+
+/// Look a name up with an SDL function, `None` when it returns `invalid`.
+fn from_name<T: PartialEq>(
+    name: &str,
+    lookup: unsafe extern "C" fn(*const c_char) -> T,
+    invalid: T,
+) -> Option<T> {
+    let name = CString::new(name).ok()?;
+    let value = unsafe { lookup(name.as_ptr()) };
+    (value != invalid).then_some(value)
+}
+
+/// A name returned by an SDL function, it is owned by SDL.
+fn name(name: *const c_char) -> String {
+    if name.is_null() {
+        return String::new();
+    }
+    unsafe { CStr::from_ptr(name).to_string_lossy().into_owned() }
+}
+
+impl FromStr for Binding {
+    type Err = InputError;
+
+    /// Parse a binding like `-key A`, see the [module docs](self).
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let trimmed = text.trim();
+        let invalid = || InputError::InvalidBinding(trimmed.to_string());
+        let (negate, rest) = match trimmed.strip_prefix('-') {
+            Some(rest) => (true, rest.trim_start()),
+            None => (false, trimmed),
+        };
+        let (kind, name) = rest.split_once(char::is_whitespace).ok_or_else(invalid)?;
+        let name = name.trim();
+
+        let input = match kind {
+            "key" => Input::Key(
+                from_name(
+                    name,
+                    sdl3_sys::keyboard::SDL_GetScancodeFromName,
+                    SDL_Scancode::UNKNOWN,
+                )
+                .ok_or_else(invalid)?,
+            ),
+            "keycode" => Input::Keycode(
+                from_name(
+                    name,
+                    sdl3_sys::keyboard::SDL_GetKeyFromName,
+                    SDL_Keycode::UNKNOWN,
+                )
+                .ok_or_else(invalid)?,
+            ),
+            "mouse" => Input::Mouse(match name {
+                "left" => MouseButton::Left,
+                "middle" => MouseButton::Middle,
+                "right" => MouseButton::Right,
+                "x1" => MouseButton::X1,
+                "x2" => MouseButton::X2,
+                _ => MouseButton::Other(name.parse().map_err(|_| invalid())?),
+            }),
+            "gamepad" => Input::GamepadButton(
+                from_name(
+                    name,
+                    sdl3_sys::gamepad::SDL_GetGamepadButtonFromString,
+                    SDL_GamepadButton::INVALID,
+                )
+                .ok_or_else(invalid)?,
+            ),
+            "axis" => Input::GamepadAxis(
+                from_name(
+                    name,
+                    sdl3_sys::gamepad::SDL_GetGamepadAxisFromString,
+                    SDL_GamepadAxis::INVALID,
+                )
+                .ok_or_else(invalid)?,
+            ),
+            _ => return Err(invalid()),
+        };
+        Ok(Self { input, negate })
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negate {
+            f.write_str("-")?;
+        }
+        unsafe {
+            match self.input {
+                Input::Key(scancode) => write!(
+                    f,
+                    "key {}",
+                    name(sdl3_sys::keyboard::SDL_GetScancodeName(scancode))
+                ),
+                Input::Keycode(keycode) => write!(
+                    f,
+                    "keycode {}",
+                    name(sdl3_sys::keyboard::SDL_GetKeyName(keycode))
+                ),
+                Input::Mouse(button) => match button {
+                    MouseButton::Left => f.write_str("mouse left"),
+                    MouseButton::Middle => f.write_str("mouse middle"),
+                    MouseButton::Right => f.write_str("mouse right"),
+                    MouseButton::X1 => f.write_str("mouse x1"),
+                    MouseButton::X2 => f.write_str("mouse x2"),
+                    MouseButton::Other(button) => write!(f, "mouse {button}"),
+                },
+                Input::GamepadButton(button) => {
+                    write!(
+                        f,
+                        "gamepad {}",
+                        name(sdl3_sys::gamepad::SDL_GetGamepadStringForButton(button))
+                    )
+                }
+                Input::GamepadAxis(axis) => write!(
+                    f,
+                    "axis {}",
+                    name(sdl3_sys::gamepad::SDL_GetGamepadStringForAxis(axis))
+                ),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Action {
+    bindings: Vec<Binding>,
+    value: f32,
+    previous: f32,
+}
+
+/// Game actions and the inputs bound to them.
+///
+/// Call [`ActionMap::update`] once a frame after the events were handled,
+/// the value of an action is the sum of its bindings, from -1 to 1.
+#[derive(Debug, Clone)]
+pub struct ActionMap {
+    actions: BTreeMap<String, Action>,
+    deadzone: f32,
+}
+
+impl Default for ActionMap {
+    fn default() -> Self {
+        Self {
+            actions: BTreeMap::new(),
+            deadzone: 0.2,
+        }
+    }
+}
+
+impl ActionMap {
+    /// Actions without bindings and a gamepad axis deadzone of 0.2.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse actions from a config, see the [module docs](self).
+    pub fn from_config(config: &str) -> Result<Self, InputError> {
+        let mut map = Self::new();
+        for (index, line) in config.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let config_error = |message: String| InputError::ActionConfig {
+                line: index + 1,
+                message,
+            };
+            let (action, binding) = line
+                .split_once('=')
+                .ok_or_else(|| config_error("expected `action = binding`".to_string()))?;
+            let action = action.trim();
+            if action.is_empty() {
+                return Err(config_error("the action has no name".to_string()));
+            }
+            if binding.trim().is_empty() {
+                map.actions.entry(action.to_string()).or_default();
+                continue;
+            }
+            let binding = binding
+                .parse()
+                .map_err(|error: InputError| config_error(error.to_string()))?;
+            map.bind(action, binding);
+        }
+        Ok(map)
+    }
+
+    /// Read actions from a config file, see [`ActionMap::from_config`].
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, InputError> {
+        let path = path.as_ref();
+        let config = ::std::fs::read_to_string(path).map_err(|source| InputError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::from_config(&config)
+    }
+
+    /// The actions as a config, to save them after rebinding.
+    pub fn to_config(&self) -> String {
+        let mut config = String::new();
+        for (name, action) in &self.actions {
+            if action.bindings.is_empty() {
+                config.push_str(&format!("{name} =\n"));
+            }
+            for binding in &action.bindings {
+                config.push_str(&format!("{name} = {binding}\n"));
+            }
+        }
+        config
+    }
+
+    /// The names of the actions, sorted.
+    pub fn actions(&self) -> impl Iterator<Item = &str> {
+        self.actions.keys().map(String::as_str)
+    }
+
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.actions
+            .get(action)
+            .map_or(&[], |action| action.bindings.as_slice())
+    }
+
+    /// Bind an input to an action, the action is added when it is new.
+    pub fn bind(&mut self, action: &str, binding: Binding) {
+        let bindings = &mut self.actions.entry(action.to_string()).or_default().bindings;
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Remove a binding from an action, `false` when it did not have it.
+    pub fn unbind(&mut self, action: &str, binding: Binding) -> bool {
+        let Some(action) = self.actions.get_mut(action) else {
+            return false;
+        };
+        let len = action.bindings.len();
+        action.bindings.retain(|&other| other != binding);
+        action.bindings.len() != len
+    }
+
+    /// Replace every binding of an action with one, like after asking the player for a key.
+    pub fn rebind(&mut self, action: &str, binding: Binding) {
+        self.clear(action);
+        self.bind(action, binding);
+    }
+
+    /// Remove every binding of an action, the action stays.
+    pub fn clear(&mut self, action: &str) {
+        if let Some(action) = self.actions.get_mut(action) {
+            action.bindings.clear();
+        }
+    }
+
+    pub fn deadzone(&self) -> f32 {
+        self.deadzone
+    }
+
+    /// Ignore gamepad axes closer to the rest position than `deadzone`,
+    /// the rest of their range is stretched back to 0 to 1.
+    pub fn set_deadzone(&mut self, deadzone: f32) {
+        self.deadzone = deadzone.clamp(0.0, 0.99);
+    }
+
+    /// Update the values of the actions from the input of this frame.
+    pub fn update(&mut self, input: &InputState) {
+        let deadzone = self.deadzone;
+        for action in self.actions.values_mut() {
+            action.previous = action.value;
+            action.value = action
+                .bindings
+                .iter()
+                .map(|binding| {
+                    let mut value = input.value(binding.input);
+                    if let Input::GamepadAxis(_) = binding.input {
+                        value = apply_deadzone(value, deadzone);
+                    }
+                    if binding.negate { -value } else { value }
+                })
+                .sum::<f32>()
+                .clamp(-1.0, 1.0);
+        }
+    }
+
+    /// The value of an action from -1 to 1, 0 for an action that does not exist.
+    pub fn value(&self, action: &str) -> f32 {
+        self.actions.get(action).map_or(0.0, |action| action.value)
+    }
+
+    /// Whether any binding pushes the action.
+    pub fn is_held(&self, action: &str) -> bool {
+        self.actions
+            .get(action)
+            .is_some_and(|action| action.value != 0.0)
+    }
+
+    /// Whether the action started being held this frame.
+    pub fn is_pressed(&self, action: &str) -> bool {
+        self.actions
+            .get(action)
+            .is_some_and(|action| action.value != 0.0 && action.previous == 0.0)
+    }
+
+    /// Whether the action stopped being held this frame.
+    pub fn is_released(&self, action: &str) -> bool {
+        self.actions
+            .get(action)
+            .is_some_and(|action| action.value == 0.0 && action.previous != 0.0)
+    }
+}
+
+fn apply_deadzone(value: f32, deadzone: f32) -> f32 {
+    if value.abs() <= deadzone {
+        return 0.0;
+    }
+    value.signum() * (value.abs() - deadzone) / (1.0 - deadzone)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "\
+fire = mouse left
+fire = mouse 6
+idle =
+jump = key Space
+jump = gamepad a
+move_x = axis leftx
+move_x = -key A
+";
+
+    #[test]
+    fn round_trips_configs() -> Result<(), InputError> {
+        let map = ActionMap::from_config(&format!("# comment\n\n{CONFIG}  jump = key Space\n"))?;
+        assert_eq!(
+            map.actions().collect::<Vec<_>>(),
+            ["fire", "idle", "jump", "move_x"]
+        );
+        assert_eq!(
+            map.bindings("move_x"),
+            [
+                Binding::new(Input::GamepadAxis(SDL_GamepadAxis::LEFTX)),
+                Binding::negated(Input::Key(SDL_Scancode::A)),
+            ]
+        );
+        assert_eq!(map.to_config(), CONFIG);
+        assert_eq!(
+            ActionMap::from_config(&map.to_config())?.to_config(),
+            CONFIG
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn reports_config_lines() {
+        let line_of = |config: &str| match ActionMap::from_config(config) {
+            Err(InputError::ActionConfig { line, .. }) => Some(line),
+            _ => None,
+        };
+        assert_eq!(line_of("# actions\n\njump = mouse left\nfire\n"), Some(4));
+        assert_eq!(line_of("jump = mouse left\n = mouse right"), Some(2));
+        assert_eq!(line_of("jump = mouse wheel"), Some(1));
+        assert_eq!(line_of("jump = pedal left"), Some(1));
+    }
+
+    #[test]
+    fn stretches_axes_past_the_deadzone() {
+        assert_eq!(apply_deadzone(0.1, 0.25), 0.0);
+        assert_eq!(apply_deadzone(-0.25, 0.25), 0.0);
+        assert_eq!(apply_deadzone(0.625, 0.25), 0.5);
+        assert_eq!(apply_deadzone(-1.0, 0.25), -1.0);
+        assert_eq!(apply_deadzone(0.5, 0.0), 0.5);
+    }
+
+    #[test]
+    fn tracks_button_transitions() {
+        let mut buttons = Buttons::default();
+        buttons.set(1, true);
+        buttons.set(1, true);
+        assert!(buttons.held.contains(&1) && buttons.pressed.contains(&1));
+
+        buttons.begin_frame();
+        assert!(buttons.pressed.is_empty() && buttons.is_active(&1));
+        buttons.set(1, false);
+        buttons.set(2, false);
+        assert_eq!(buttons.released.iter().collect::<Vec<_>>(), [&1]);
+
+        // pressed and released within a frame still counts once
+        buttons.begin_frame();
+        buttons.set(3, true);
+        buttons.set(3, false);
+        assert!(buttons.is_active(&3) && !buttons.held.contains(&3));
+
+        buttons.set(4, true);
+        buttons.release_all();
+        assert!(buttons.held.is_empty() && buttons.released.contains(&4));
+    }
+
+    #[test]
+    fn merges_gamepads() {
+        let button = |gamepad_id, down| Event::Gamepad {
+            timestamp: ::std::time::Duration::ZERO,
+            gamepad_id,
+            event: GamepadEvent::Button {
+                button: SDL_GamepadButton::SOUTH,
+                down,
+            },
+        };
+        let mut input = InputState::new();
+        input.handle_event(&button(1, true));
+        assert!(input.is_gamepad_pressed(SDL_GamepadButton::SOUTH));
+        input.begin_frame();
+        input.handle_event(&button(2, true));
+        assert!(!input.is_gamepad_pressed(SDL_GamepadButton::SOUTH));
+        assert_eq!(input.pressed_inputs().count(), 0);
+        input.begin_frame();
+
+        input.handle_event(&button(1, false));
+        assert!(input.is_gamepad_held(SDL_GamepadButton::SOUTH));
+        assert!(!input.is_gamepad_released(SDL_GamepadButton::SOUTH));
+
+        input.handle_event(&button(2, false));
+        assert!(!input.is_gamepad_held(SDL_GamepadButton::SOUTH));
+        assert!(input.is_gamepad_released(SDL_GamepadButton::SOUTH));
+    }
+
+    #[test]
+    fn releases_everything_on_focus_loss() {
+        let mut input = InputState::new();
+        input.handle_event(&Event::Gamepad {
+            timestamp: ::std::time::Duration::ZERO,
+            gamepad_id: 1,
+            event: GamepadEvent::Button {
+                button: SDL_GamepadButton::SOUTH,
+                down: true,
+            },
+        });
+        input.begin_frame();
+
+        input.handle_event(&Event::Window {
+            timestamp: ::std::time::Duration::ZERO,
+            window_id: 1,
+            event: WindowEvent::FocusLost,
+        });
+        assert!(!input.is_gamepad_held(SDL_GamepadButton::SOUTH));
+        assert!(input.is_gamepad_released(SDL_GamepadButton::SOUTH));
+        assert_eq!(
+            input.value(Input::GamepadButton(SDL_GamepadButton::SOUTH)),
+            0.0
+        );
+    }
+}
@@ -4,8 +4,13 @@ use ::wscb_type::{
     graph::{Point, Rect},
 };
 
+pub mod clipboard;
+pub mod event;
+pub mod gamepad;
 pub mod graph;
+pub mod input;
 pub mod renderer;
+pub mod sdl;
 pub mod surface;
 pub mod texture;
 pub mod window;
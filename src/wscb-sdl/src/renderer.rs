@@ -130,6 +130,66 @@ impl Renderer {
         }
         Ok(())
     }
+
+    /// Render triangles, textured when a texture is given.
+    ///
+    /// Every three indices form a triangle of `vertices`.
+    pub fn render_geometry(
+        &self,
+        texture: Option<&Texture>,
+        vertices: &[sdl3_sys::render::SDL_Vertex],
+        indices: &[i32],
+    ) -> Result<(), SdlError> {
+        let num_vertices = i32::try_from(vertices.len())
+            .map_err(|_| SdlError::sdl_err("too many vertices to render"))?;
+        let num_indices = i32::try_from(indices.len())
+            .map_err(|_| SdlError::sdl_err("too many indices to render"))?;
+
+        unsafe {
+            if !sdl3_sys::render::SDL_RenderGeometry(
+                self.get_pointer(),
+                texture.map_or(std::ptr::null_mut(), Texture::get_pointer),
+                vertices.as_ptr(),
+                num_vertices,
+                indices.as_ptr(),
+                num_indices,
+            ) {
+                return Err(SdlError::sdl_err("failed to render geometry"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Restrict rendering to a rectangle of the current target, `None` disables clipping.
+    pub fn set_clip_rect(&self, rect: Option<Rect>) -> Result<(), SdlError> {
+        let rect: Option<sdl3_sys::rect::SDL_Rect> = rect.map(Into::into);
+
+        unsafe {
+            if !sdl3_sys::render::SDL_SetRenderClipRect(
+                self.get_pointer(),
+                rect.as_ref()
+                    .map_or(std::ptr::null(), |rect| rect as *const _),
+            ) {
+                return Err(SdlError::sdl_err("failed to set clip rect"));
+            }
+        }
+        Ok(())
+    }
+
+    /// The current clip rectangle, `None` when clipping is disabled.
+    pub fn clip_rect(&self) -> Result<Option<Rect>, SdlError> {
+        unsafe {
+            if !sdl3_sys::render::SDL_RenderClipEnabled(self.get_pointer()) {
+                return Ok(None);
+            }
+
+            let mut rect = sdl3_sys::rect::SDL_Rect::default();
+            if !sdl3_sys::render::SDL_GetRenderClipRect(self.get_pointer(), &mut rect) {
+                return Err(SdlError::sdl_err("failed to get clip rect"));
+            }
+            Ok(Rect::try_from(rect).ok())
+        }
+    }
 }
 
 impl Drop for Renderer {
@@ -0,0 +1,211 @@
+use ::std::{
+    fmt,
+    rc::Rc,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use ::sdl3_sys::init::SDL_InitFlags;
+use ::wscb_type::error::SdlError;
+
+/// Whether an [`Sdl`] context is alive, SDL can only be initialized once at a time.
+static INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+/// Whether this is the thread the process started on.
+///
+/// SDL only knows its main thread once it was initialized, before that
+/// `SDL_IsMainThread` says yes on every thread, so ask the system. On
+/// platforms without such a check the first thread to initialize SDL is
+/// taken as the main one.
+fn is_main_thread() -> bool {
+    cfg_if::cfg_if! {
+        if #[cfg(target_os = "macos")] {
+            unsafe extern "C" {
+                fn pthread_main_np() -> ::std::ffi::c_int;
+            }
+            unsafe { pthread_main_np() != 0 }
+        } else if #[cfg(target_os = "linux")] {
+            unsafe extern "C" {
+                fn gettid() -> i32;
+            }
+            // the main thread has the id of the process
+            u32::try_from(unsafe { gettid() }).is_ok_and(|tid| tid == ::std::process::id())
+        } else {
+            unsafe { sdl3_sys::init::SDL_IsMainThread() }
+        }
+    }
+}
+
+/// Calls `SDL_Quit` when the context and every subsystem token are gone.
+#[derive(Debug, PartialEq, Eq)]
+struct Context;
+
+impl Drop for Context {
+    fn drop(&mut self) {
+        unsafe {
+            sdl3_sys::init::SDL_Quit();
+        }
+        INITIALIZED.store(false, Ordering::Release);
+    }
+}
+
+/// The SDL library, initialized on the main thread.
+///
+/// Subsystems are started by asking for their token, like [`Sdl::video`].
+/// Each token keeps its subsystem running, the subsystem stops when the last
+/// token of it is dropped and SDL shuts down when the context and every
+/// token are dropped. Neither can leave the main thread.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sdl {
+    context: Rc<Context>,
+}
+
+impl Sdl {
+    /// Initialize SDL without any subsystem.
+    ///
+    /// Fails when called from another thread than the one running `main`,
+    /// or while another context is alive. Outside Linux and macOS the thread
+    /// is not checked, the first one to initialize SDL becomes its main thread.
+    pub fn init() -> Result<Self, SdlError> {
+        if !is_main_thread() {
+            return Err(SdlError::NotMainThread);
+        }
+        if INITIALIZED.swap(true, Ordering::AcqRel) {
+            return Err(SdlError::AlreadyInitialized);
+        }
+
+        unsafe {
+            if !sdl3_sys::init::SDL_Init(SDL_InitFlags::default()) {
+                INITIALIZED.store(false, Ordering::Release);
+                return Err(SdlError::sdl_err("failed to initialize SDL"));
+            }
+        }
+
+        Ok(Self {
+            context: Rc::new(Context),
+        })
+    }
+
+    fn subsystem(&self, flags: SDL_InitFlags, what: &str) -> Result<Rc<Subsystem>, SdlError> {
+        unsafe {
+            if !sdl3_sys::init::SDL_InitSubSystem(flags) {
+                return Err(SdlError::sdl_err(&format!(
+                    "failed to initialize the {what} subsystem"
+                )));
+            }
+        }
+        Ok(Rc::new(Subsystem {
+            flags,
+            _context: self.context.clone(),
+        }))
+    }
+
+    /// Start the video subsystem, needed for windows and renderers.
+    pub fn video(&self) -> Result<VideoSubsystem, SdlError> {
+        Ok(VideoSubsystem {
+            _subsystem: self.subsystem(SDL_InitFlags::VIDEO, "video")?,
+        })
+    }
+
+    pub fn audio(&self) -> Result<AudioSubsystem, SdlError> {
+        Ok(AudioSubsystem {
+            _subsystem: self.subsystem(SDL_InitFlags::AUDIO, "audio")?,
+        })
+    }
+
+    /// Start the gamepad subsystem, it also starts the joystick and events subsystems.
+    pub fn gamepad(&self) -> Result<GamepadSubsystem, SdlError> {
+        Ok(GamepadSubsystem {
+            _subsystem: self.subsystem(SDL_InitFlags::GAMEPAD, "gamepad")?,
+        })
+    }
+
+    /// Start the events subsystem, video and gamepad start it on their own.
+    pub fn events(&self) -> Result<EventsSubsystem, SdlError> {
+        Ok(EventsSubsystem {
+            _subsystem: self.subsystem(SDL_InitFlags::EVENTS, "events")?,
+        })
+    }
+
+    /// Whether all the subsystems of `flags` are running.
+    pub fn is_initialized(&self, flags: SDL_InitFlags) -> bool {
+        unsafe { sdl3_sys::init::SDL_WasInit(flags) == flags }
+    }
+}
+
+/// One start of an SDL subsystem, SDL counts them and stops it after the last quit.
+#[derive(PartialEq, Eq)]
+struct Subsystem {
+    flags: SDL_InitFlags,
+    _context: Rc<Context>,
+}
+
+impl fmt::Debug for Subsystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Subsystem")
+            .field("flags", &format_args!("{:#x}", self.flags.0))
+            .finish()
+    }
+}
+
+impl Drop for Subsystem {
+    fn drop(&mut self) {
+        unsafe {
+            sdl3_sys::init::SDL_QuitSubSystem(self.flags);
+        }
+    }
+}
+
+/// Proof that the video subsystem is running, see [`Sdl::video`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VideoSubsystem {
+    _subsystem: Rc<Subsystem>,
+}
+
+/// Proof that the audio subsystem is running, see [`Sdl::audio`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioSubsystem {
+    _subsystem: Rc<Subsystem>,
+}
+
+/// Proof that the gamepad subsystem is running, see [`Sdl::gamepad`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GamepadSubsystem {
+    _subsystem: Rc<Subsystem>,
+}
+
+/// Proof that the events subsystem is running, see [`Sdl::events`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventsSubsystem {
+    _subsystem: Rc<Subsystem>,
+}
+
+impl VideoSubsystem {
+    /// The name of the video driver, like `x11`, `wayland` or `cocoa`.
+    pub fn driver(&self) -> Option<String> {
+        unsafe {
+            let driver = sdl3_sys::video::SDL_GetCurrentVideoDriver();
+            if driver.is_null() {
+                return None;
+            }
+            Some(
+                std::ffi::CStr::from_ptr(driver)
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::std::thread;
+
+    use super::*;
+
+    #[test]
+    fn refuses_other_threads() {
+        let refused = thread::spawn(|| matches!(Sdl::init(), Err(SdlError::NotMainThread)));
+        assert!(matches!(refused.join(), Ok(true)));
+        assert!(!INITIALIZED.load(Ordering::Acquire));
+    }
+}
@@ -18,6 +18,20 @@ impl Surface {
         })
     }
 
+    /// Create a new surface with the given size and pixel format.
+    ///
+    /// The pixels are zero initialized.
+    pub fn new(
+        width: u32,
+        height: u32,
+        format: sdl3_sys::pixels::SDL_PixelFormat,
+    ) -> Result<Self, SdlError> {
+        unsafe {
+            let surface = sdl3_sys::surface::SDL_CreateSurface(width as i32, height as i32, format);
+            Self::from_raw(surface).ok_or_else(|| SdlError::sdl_err("failed to create surface"))
+        }
+    }
+
     pub fn get_pointer(&self) -> *mut sdl3_sys::surface::SDL_Surface {
         self.pointer.as_ptr()
     }
@@ -41,6 +55,24 @@ impl Surface {
         unsafe { (*self.get_pointer()).pitch }
     }
 
+    /// Create a copy of the surface scaled to the given size.
+    pub fn scale(
+        &self,
+        width: u32,
+        height: u32,
+        mode: sdl3_sys::surface::SDL_ScaleMode,
+    ) -> Result<Self, SdlError> {
+        unsafe {
+            let surface = sdl3_sys::surface::SDL_ScaleSurface(
+                self.get_pointer(),
+                width as i32,
+                height as i32,
+                mode,
+            );
+            Self::from_raw(surface).ok_or_else(|| SdlError::sdl_err("failed to scale surface"))
+        }
+    }
+
     pub fn duplicate(&self) -> Option<Self> {
         unsafe {
             Some(Self {
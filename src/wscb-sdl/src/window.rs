@@ -1,55 +1,307 @@
-use ::std::{ops::Deref, ptr::NonNull};
-#[cfg(target_os = "macos")]
-use std::ffi::c_void;
+use ::std::{ffi::c_void, ops::Deref, ptr::NonNull};
 
-use ::wscb_type::{error::SdlError, graph::Size};
-use raw_window_handle::{AppKitWindowHandle, HandleError, RawWindowHandle, WindowHandle};
+use ::wscb_type::{
+    error::SdlError,
+    graph::{Point, Rect, Size},
+};
+#[cfg(target_os = "macos")]
+use raw_window_handle::{AppKitDisplayHandle, AppKitWindowHandle};
+use raw_window_handle::{
+    DisplayHandle, HandleError, RawDisplayHandle, RawWindowHandle, WindowHandle,
+};
+#[cfg(target_os = "linux")]
+use raw_window_handle::{
+    WaylandDisplayHandle, WaylandWindowHandle, XcbDisplayHandle, XcbWindowHandle,
+    XlibDisplayHandle, XlibWindowHandle,
+};
 
-use crate::renderer::Renderer;
+use crate::{renderer::Renderer, sdl::VideoSubsystem};
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct Window {
     pointer: NonNull<sdl3_sys::video::SDL_Window>,
     #[cfg(target_os = "macos")]
     ns_view: NonNull<c_void>,
+    /// Keeps the video subsystem running while the window exists.
+    _video: VideoSubsystem,
 }
 
-impl Window {
-    pub fn new(title: &str, width: i32, height: i32) -> Result<Self, SdlError> {
-        unsafe {
-            cfg_if::cfg_if! {
-                if #[cfg(target_os = "macos")] {
-                    let flags = sdl3_sys::video::SDL_WindowFlags::RESIZABLE | sdl3_sys::video::SDL_WindowFlags::METAL;
-                } else {
-                    let flags = sdl3_sys::video::SDL_WindowFlags::RESIZABLE;
-                }
+/// A display mode a window can be fullscreen in, see [`Window::fullscreen_modes`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DisplayMode {
+    display: u32,
+    pub size: Size,
+    /// Pixels per point, 2.0 for a mode with twice the pixels of its size.
+    pub pixel_density: f32,
+    /// In hertz, 0.0 when unknown.
+    pub refresh_rate: f32,
+}
+
+impl DisplayMode {
+    fn from_raw(mode: &sdl3_sys::video::SDL_DisplayMode) -> Self {
+        Self {
+            display: mode.displayID.0,
+            size: Size::new(mode.w, mode.h),
+            pixel_density: mode.pixel_density,
+            refresh_rate: mode.refresh_rate,
+        }
+    }
+}
+
+/// Options to create a [`Window`] with.
+///
+/// Windows are resizable unless told otherwise and placed where the system
+/// likes, [`WindowBuilder::centered`] and [`WindowBuilder::position`] place them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowBuilder {
+    title: String,
+    size: Size,
+    x: i32,
+    y: i32,
+    min_size: Option<Size>,
+    max_size: Option<Size>,
+    resizable: bool,
+    fullscreen: bool,
+    borderless: bool,
+    hidden: bool,
+    high_pixel_density: bool,
+    always_on_top: bool,
+    transparent: bool,
+}
+
+impl WindowBuilder {
+    pub fn new(title: &str, width: i32, height: i32) -> Self {
+        Self {
+            title: title.to_string(),
+            size: Size::new(width, height),
+            x: sdl3_sys::video::SDL_WINDOWPOS_UNDEFINED,
+            y: sdl3_sys::video::SDL_WINDOWPOS_UNDEFINED,
+            min_size: None,
+            max_size: None,
+            resizable: true,
+            fullscreen: false,
+            borderless: false,
+            hidden: false,
+            high_pixel_density: false,
+            always_on_top: false,
+            transparent: false,
+        }
+    }
+
+    pub fn position(mut self, position: Point) -> Self {
+        self.x = position.x;
+        self.y = position.y;
+        self
+    }
+
+    /// Center the window on the primary display.
+    pub fn centered(mut self) -> Self {
+        self.x = sdl3_sys::video::SDL_WINDOWPOS_CENTERED;
+        self.y = sdl3_sys::video::SDL_WINDOWPOS_CENTERED;
+        self
+    }
+
+    pub fn min_size(mut self, size: Size) -> Self {
+        self.min_size = Some(size);
+        self
+    }
+
+    pub fn max_size(mut self, size: Size) -> Self {
+        self.max_size = Some(size);
+        self
+    }
+
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+
+    /// Start in borderless fullscreen on the desktop mode, see [`Window::set_fullscreen_mode`].
+    pub fn fullscreen(mut self, fullscreen: bool) -> Self {
+        self.fullscreen = fullscreen;
+        self
+    }
+
+    pub fn borderless(mut self, borderless: bool) -> Self {
+        self.borderless = borderless;
+        self
+    }
+
+    /// Create the window without showing it, [`Window::show`] shows it.
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
+    /// Ask for a backbuffer in pixels rather than points on high-DPI displays.
+    ///
+    /// Without it the window is scaled up by the system on those displays.
+    pub fn high_pixel_density(mut self, high_pixel_density: bool) -> Self {
+        self.high_pixel_density = high_pixel_density;
+        self
+    }
+
+    pub fn always_on_top(mut self, always_on_top: bool) -> Self {
+        self.always_on_top = always_on_top;
+        self
+    }
+
+    /// Blend the window with what is behind it by the alpha of its pixels.
+    pub fn transparent(mut self, transparent: bool) -> Self {
+        self.transparent = transparent;
+        self
+    }
+
+    fn flags(&self) -> sdl3_sys::video::SDL_WindowFlags {
+        use sdl3_sys::video::SDL_WindowFlags;
+
+        let mut flags = SDL_WindowFlags::default();
+        for (on, flag) in [
+            (self.resizable, SDL_WindowFlags::RESIZABLE),
+            (self.fullscreen, SDL_WindowFlags::FULLSCREEN),
+            (self.borderless, SDL_WindowFlags::BORDERLESS),
+            (self.hidden, SDL_WindowFlags::HIDDEN),
+            (self.high_pixel_density, SDL_WindowFlags::HIGH_PIXEL_DENSITY),
+            (self.always_on_top, SDL_WindowFlags::ALWAYS_ON_TOP),
+            (self.transparent, SDL_WindowFlags::TRANSPARENT),
+            (cfg!(target_os = "macos"), SDL_WindowFlags::METAL),
+        ] {
+            if on {
+                flags |= flag;
             }
+        }
+        flags
+    }
+
+    pub fn build(&self, video: &VideoSubsystem) -> Result<Window, SdlError> {
+        use sdl3_sys::{properties::*, video::*};
+
+        let c_title = std::ffi::CString::new(self.title.as_str())
+            .map_err(|_| SdlError::sdl_err("invalid title string"))?;
 
-            let c_title = std::ffi::CString::new(title)
-                .map_err(|_| SdlError::sdl_err("invalid title string"))?;
+        unsafe {
+            let properties = SDL_CreateProperties();
+            if properties.0 == 0 {
+                return Err(SdlError::sdl_err("failed to create window properties"));
+            }
+            SDL_SetStringProperty(
+                properties,
+                SDL_PROP_WINDOW_CREATE_TITLE_STRING,
+                c_title.as_ptr(),
+            );
+            SDL_SetNumberProperty(
+                properties,
+                SDL_PROP_WINDOW_CREATE_WIDTH_NUMBER,
+                self.size.width.into(),
+            );
+            SDL_SetNumberProperty(
+                properties,
+                SDL_PROP_WINDOW_CREATE_HEIGHT_NUMBER,
+                self.size.height.into(),
+            );
+            SDL_SetNumberProperty(properties, SDL_PROP_WINDOW_CREATE_X_NUMBER, self.x.into());
+            SDL_SetNumberProperty(properties, SDL_PROP_WINDOW_CREATE_Y_NUMBER, self.y.into());
+            // the flags are a bit set, the sign bit is only reinterpreted
+            SDL_SetNumberProperty(
+                properties,
+                SDL_PROP_WINDOW_CREATE_FLAGS_NUMBER,
+                self.flags().0 as i64,
+            );
 
-            let pointer = sdl3_sys::video::SDL_CreateWindow(c_title.as_ptr(), width, height, flags);
+            let pointer = SDL_CreateWindowWithProperties(properties);
+            SDL_DestroyProperties(properties);
             let pointer = NonNull::new(pointer)
                 .ok_or_else(|| SdlError::sdl_err("failed to create window"))?;
 
             cfg_if::cfg_if! {
                 if #[cfg(target_os = "macos")] {
                     let view = sdl3_sys::metal::SDL_Metal_CreateView(pointer.as_ptr());
-                    let view = NonNull::new(view).ok_or_else(|| SdlError::sdl_err("failed to create NSView"))?;
+                    let Some(view) = NonNull::new(view) else {
+                        SDL_DestroyWindow(pointer.as_ptr());
+                        return Err(SdlError::sdl_err("failed to create NSView"));
+                    };
 
-                    Ok(Self { pointer: pointer, ns_view: view })
-                }
-                else{
-                    unimplemented!()
+                    let window = Window { pointer, ns_view: view, _video: video.clone() };
+                } else {
+                    let window = Window { pointer, _video: video.clone() };
                 }
             }
+
+            if let Some(size) = self.min_size {
+                window.set_min_size(size)?;
+            }
+            if let Some(size) = self.max_size {
+                window.set_max_size(size)?;
+            }
+            Ok(window)
         }
     }
+}
+
+impl Window {
+    /// A resizable window, see [`WindowBuilder`] for other options.
+    pub fn new(
+        video: &VideoSubsystem,
+        title: &str,
+        width: i32,
+        height: i32,
+    ) -> Result<Self, SdlError> {
+        WindowBuilder::new(title, width, height).build(video)
+    }
 
     pub fn get_pointer(&self) -> *mut sdl3_sys::video::SDL_Window {
         self.pointer.as_ptr()
     }
 
+    /// The id events name the window by, see [`Event::window_id`](crate::event::Event::window_id).
+    pub fn id(&self) -> u32 {
+        unsafe { sdl3_sys::video::SDL_GetWindowID(self.get_pointer()).0 }
+    }
+
+    fn pointer_property(&self, name: *const std::ffi::c_char) -> Option<NonNull<c_void>> {
+        unsafe {
+            let properties = sdl3_sys::video::SDL_GetWindowProperties(self.get_pointer());
+            NonNull::new(sdl3_sys::properties::SDL_GetPointerProperty(
+                properties,
+                name,
+                std::ptr::null_mut(),
+            ))
+        }
+    }
+
+    fn number_property(&self, name: *const std::ffi::c_char) -> i64 {
+        unsafe {
+            let properties = sdl3_sys::video::SDL_GetWindowProperties(self.get_pointer());
+            sdl3_sys::properties::SDL_GetNumberProperty(properties, name, 0)
+        }
+    }
+
+    /// The X11 window, when SDL runs on the `x11` video driver.
+    #[cfg(target_os = "linux")]
+    fn x11_window(&self) -> Option<u64> {
+        let window = self.number_property(sdl3_sys::video::SDL_PROP_WINDOW_X11_WINDOW_NUMBER);
+        u64::try_from(window).ok().filter(|&window| window != 0)
+    }
+
+    /// The window as an XCB handle, for crates that take XCB and not Xlib.
+    ///
+    /// SDL creates its X11 windows with Xlib and does not share the XCB
+    /// connection behind its display, the display handle has none.
+    #[cfg(target_os = "linux")]
+    pub fn xcb_handles(&self) -> Result<(XcbDisplayHandle, XcbWindowHandle), HandleError> {
+        let window = self.x11_window().ok_or(HandleError::Unavailable)?;
+        let window = u32::try_from(window)
+            .ok()
+            .and_then(std::num::NonZeroU32::new)
+            .ok_or(HandleError::Unavailable)?;
+        let screen = self.number_property(sdl3_sys::video::SDL_PROP_WINDOW_X11_SCREEN_NUMBER);
+        let screen = i32::try_from(screen).map_err(|_| HandleError::Unavailable)?;
+        Ok((
+            XcbDisplayHandle::new(None, screen),
+            XcbWindowHandle::new(window),
+        ))
+    }
+
     pub fn size(&self) -> Result<Size, SdlError> {
         let mut w = 0;
         let mut h = 0;
@@ -70,6 +322,192 @@ impl Window {
         Ok(())
     }
 
+    pub fn min_size(&self) -> Result<Size, SdlError> {
+        let mut w = 0;
+        let mut h = 0;
+        unsafe {
+            if !sdl3_sys::video::SDL_GetWindowMinimumSize(self.get_pointer(), &mut w, &mut h) {
+                return Err(SdlError::sdl_err("failed to get window minimum size"));
+            }
+        }
+        Ok(Size::new(w, h))
+    }
+
+    /// Keep the window from being resized below `size`, 0 for no limit.
+    pub fn set_min_size(&self, size: Size) -> Result<(), SdlError> {
+        unsafe {
+            if !sdl3_sys::video::SDL_SetWindowMinimumSize(
+                self.get_pointer(),
+                size.width,
+                size.height,
+            ) {
+                return Err(SdlError::sdl_err("failed to set window minimum size"));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn max_size(&self) -> Result<Size, SdlError> {
+        let mut w = 0;
+        let mut h = 0;
+        unsafe {
+            if !sdl3_sys::video::SDL_GetWindowMaximumSize(self.get_pointer(), &mut w, &mut h) {
+                return Err(SdlError::sdl_err("failed to get window maximum size"));
+            }
+        }
+        Ok(Size::new(w, h))
+    }
+
+    /// Keep the window from being resized above `size`, 0 for no limit.
+    pub fn set_max_size(&self, size: Size) -> Result<(), SdlError> {
+        unsafe {
+            if !sdl3_sys::video::SDL_SetWindowMaximumSize(
+                self.get_pointer(),
+                size.width,
+                size.height,
+            ) {
+                return Err(SdlError::sdl_err("failed to set window maximum size"));
+            }
+        }
+        Ok(())
+    }
+
+    /// The position of the top left corner of the client area on the desktop.
+    pub fn position(&self) -> Result<Point, SdlError> {
+        let mut x = 0;
+        let mut y = 0;
+        unsafe {
+            if !sdl3_sys::video::SDL_GetWindowPosition(self.get_pointer(), &mut x, &mut y) {
+                return Err(SdlError::sdl_err("failed to get window position"));
+            }
+        }
+        Ok(Point::new(x, y))
+    }
+
+    /// Move the window, the window system may not allow it, like Wayland.
+    pub fn set_position(&self, position: Point) -> Result<(), SdlError> {
+        unsafe {
+            if !sdl3_sys::video::SDL_SetWindowPosition(self.get_pointer(), position.x, position.y) {
+                return Err(SdlError::sdl_err("failed to set window position"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Center the window on its display.
+    pub fn center(&self) -> Result<(), SdlError> {
+        let centered = sdl3_sys::video::SDL_WINDOWPOS_CENTERED;
+        self.set_position(Point::new(centered, centered))
+    }
+
+    /// The ratio of pixels to points, 2.0 on most high-DPI displays with
+    /// [high pixel density](WindowBuilder::high_pixel_density).
+    pub fn pixel_density(&self) -> Result<f32, SdlError> {
+        let density = unsafe { sdl3_sys::video::SDL_GetWindowPixelDensity(self.get_pointer()) };
+        if density == 0.0 {
+            return Err(SdlError::sdl_err("failed to get window pixel density"));
+        }
+        Ok(density)
+    }
+
+    /// How much to scale content up for the display the window is on, like 1.5 for 150%.
+    ///
+    /// It includes the [pixel density](Window::pixel_density) and the scale
+    /// the user chose for the display.
+    pub fn display_scale(&self) -> Result<f32, SdlError> {
+        let scale = unsafe { sdl3_sys::video::SDL_GetWindowDisplayScale(self.get_pointer()) };
+        if scale == 0.0 {
+            return Err(SdlError::sdl_err("failed to get window display scale"));
+        }
+        Ok(scale)
+    }
+
+    pub fn is_fullscreen(&self) -> bool {
+        unsafe {
+            let flags = sdl3_sys::video::SDL_GetWindowFlags(self.get_pointer());
+            flags.0 & sdl3_sys::video::SDL_WindowFlags::FULLSCREEN.0 != 0
+        }
+    }
+
+    /// Enter or leave fullscreen in the [fullscreen mode](Window::set_fullscreen_mode).
+    pub fn set_fullscreen(&self, fullscreen: bool) -> Result<(), SdlError> {
+        unsafe {
+            if !sdl3_sys::video::SDL_SetWindowFullscreen(self.get_pointer(), fullscreen) {
+                return Err(SdlError::sdl_err("failed to set window fullscreen"));
+            }
+        }
+        Ok(())
+    }
+
+    /// The display mode used in fullscreen, `None` for borderless fullscreen on the desktop.
+    pub fn fullscreen_mode(&self) -> Option<DisplayMode> {
+        unsafe {
+            sdl3_sys::video::SDL_GetWindowFullscreenMode(self.get_pointer())
+                .as_ref()
+                .map(DisplayMode::from_raw)
+        }
+    }
+
+    /// Use the closest mode the display has to `mode` in fullscreen, or
+    /// borderless fullscreen on the desktop with `None`.
+    ///
+    /// A fullscreen window switches to it right away.
+    pub fn set_fullscreen_mode(&self, mode: Option<&DisplayMode>) -> Result<(), SdlError> {
+        unsafe {
+            let Some(mode) = mode else {
+                if !sdl3_sys::video::SDL_SetWindowFullscreenMode(
+                    self.get_pointer(),
+                    std::ptr::null(),
+                ) {
+                    return Err(SdlError::sdl_err("failed to set window fullscreen mode"));
+                }
+                return Ok(());
+            };
+
+            let mut closest = std::mem::MaybeUninit::<sdl3_sys::video::SDL_DisplayMode>::zeroed();
+            if !sdl3_sys::video::SDL_GetClosestFullscreenDisplayMode(
+                sdl3_sys::video::SDL_DisplayID(mode.display),
+                mode.size.width,
+                mode.size.height,
+                mode.refresh_rate,
+                mode.pixel_density > 1.0,
+                closest.as_mut_ptr(),
+            ) {
+                return Err(SdlError::sdl_err(
+                    "no display mode is close to the fullscreen mode",
+                ));
+            }
+            if !sdl3_sys::video::SDL_SetWindowFullscreenMode(self.get_pointer(), closest.as_ptr()) {
+                return Err(SdlError::sdl_err("failed to set window fullscreen mode"));
+            }
+        }
+        Ok(())
+    }
+
+    /// The fullscreen modes of the display the window is on, the largest first.
+    pub fn fullscreen_modes(&self) -> Result<Vec<DisplayMode>, SdlError> {
+        unsafe {
+            let display = sdl3_sys::video::SDL_GetDisplayForWindow(self.get_pointer());
+            if display.0 == 0 {
+                return Err(SdlError::sdl_err("failed to get the display of the window"));
+            }
+            let mut count = 0;
+            let modes = sdl3_sys::video::SDL_GetFullscreenDisplayModes(display, &mut count);
+            if modes.is_null() {
+                return Err(SdlError::sdl_err("failed to get fullscreen display modes"));
+            }
+            // the array is freed before a bad count is reported
+            let result = usize::try_from(count).map(|count| {
+                std::slice::from_raw_parts(modes, count)
+                    .iter()
+                    .filter_map(|mode| mode.as_ref().map(DisplayMode::from_raw))
+                    .collect()
+            });
+            sdl3_sys::stdinc::SDL_free(modes.cast());
+            Ok(result?)
+        }
+    }
+
     pub fn title(&self) -> String {
         unsafe {
             let title_ptr = sdl3_sys::video::SDL_GetWindowTitle(self.get_pointer());
@@ -111,6 +549,44 @@ impl Window {
         Ok(())
     }
 
+    /// Start sending text input and IME composition events for this window.
+    ///
+    /// On some platforms this shows the on-screen keyboard.
+    pub fn start_text_input(&self) -> Result<(), SdlError> {
+        unsafe {
+            if !sdl3_sys::keyboard::SDL_StartTextInput(self.get_pointer()) {
+                return Err(SdlError::sdl_err("failed to start text input"));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn stop_text_input(&self) -> Result<(), SdlError> {
+        unsafe {
+            if !sdl3_sys::keyboard::SDL_StopTextInput(self.get_pointer()) {
+                return Err(SdlError::sdl_err("failed to stop text input"));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_text_input_active(&self) -> bool {
+        unsafe { sdl3_sys::keyboard::SDL_TextInputActive(self.get_pointer()) }
+    }
+
+    /// Tell the IME where the text being edited is, so its candidate list does not cover it.
+    ///
+    /// `cursor` is the offset of the caret from the left of `area`, in pixels.
+    pub fn set_text_input_area(&self, area: Rect, cursor: i32) -> Result<(), SdlError> {
+        let rect: sdl3_sys::rect::SDL_Rect = area.into();
+        unsafe {
+            if !sdl3_sys::keyboard::SDL_SetTextInputArea(self.get_pointer(), &rect, cursor) {
+                return Err(SdlError::sdl_err("failed to set text input area"));
+            }
+        }
+        Ok(())
+    }
+
     pub fn create_renderer(&self) -> Result<Renderer, SdlError> {
         unsafe {
             let renderer =
@@ -146,17 +622,94 @@ impl Deref for Window {
 impl raw_window_handle::HasWindowHandle for Window {
     fn window_handle(&self) -> Result<WindowHandle<'_>, HandleError> {
         cfg_if::cfg_if! {
-            if #[cfg(windows)] {
-                unimplemented!()
-            } else if #[cfg(target_os = "macos")] {
+            if #[cfg(target_os = "macos")] {
                 unsafe{
                     Ok(WindowHandle::borrow_raw(
                         RawWindowHandle::AppKit(AppKitWindowHandle::new(self.ns_view))
                     ))
                 }
+            } else if #[cfg(target_os = "linux")] {
+                let raw = if let Some(surface) = self.pointer_property(sdl3_sys::video::SDL_PROP_WINDOW_WAYLAND_SURFACE_POINTER) {
+                    RawWindowHandle::Wayland(WaylandWindowHandle::new(surface))
+                } else if let Some(window) = self.x11_window() {
+                    RawWindowHandle::Xlib(XlibWindowHandle::new(window))
+                } else {
+                    return Err(HandleError::Unavailable);
+                };
+                // the handles live as long as the SDL window
+                unsafe { Ok(WindowHandle::borrow_raw(raw)) }
+            } else {
+                Err(HandleError::NotSupported)
+            }
+        }
+    }
+}
+
+impl raw_window_handle::HasDisplayHandle for Window {
+    fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+        cfg_if::cfg_if! {
+            if #[cfg(target_os = "macos")] {
+                let raw = RawDisplayHandle::AppKit(AppKitDisplayHandle::new());
+                unsafe { Ok(DisplayHandle::borrow_raw(raw)) }
+            } else if #[cfg(target_os = "linux")] {
+                let raw = if let Some(display) = self.pointer_property(sdl3_sys::video::SDL_PROP_WINDOW_WAYLAND_DISPLAY_POINTER) {
+                    RawDisplayHandle::Wayland(WaylandDisplayHandle::new(display))
+                } else if let Some(display) = self.pointer_property(sdl3_sys::video::SDL_PROP_WINDOW_X11_DISPLAY_POINTER) {
+                    let screen = self.number_property(sdl3_sys::video::SDL_PROP_WINDOW_X11_SCREEN_NUMBER);
+                    let screen = i32::try_from(screen).map_err(|_| HandleError::Unavailable)?;
+                    RawDisplayHandle::Xlib(XlibDisplayHandle::new(Some(display), screen))
+                } else {
+                    return Err(HandleError::Unavailable);
+                };
+                unsafe { Ok(DisplayHandle::borrow_raw(raw)) }
             } else {
-                unimplemented!()
+                Err(HandleError::NotSupported)
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ::sdl3_sys::video::SDL_WindowFlags;
+
+    use super::*;
+
+    #[test]
+    fn maps_options_to_flags() {
+        let platform = if cfg!(target_os = "macos") {
+            SDL_WindowFlags::METAL
+        } else {
+            SDL_WindowFlags::default()
+        };
+
+        let builder = WindowBuilder::new("test", 640, 480);
+        assert_eq!(builder.flags(), SDL_WindowFlags::RESIZABLE | platform);
+
+        let builder = builder
+            .resizable(false)
+            .fullscreen(true)
+            .borderless(true)
+            .hidden(true)
+            .high_pixel_density(true)
+            .always_on_top(true)
+            .transparent(true);
+        assert_eq!(
+            builder.flags(),
+            SDL_WindowFlags::FULLSCREEN
+                | SDL_WindowFlags::BORDERLESS
+                | SDL_WindowFlags::HIDDEN
+                | SDL_WindowFlags::HIGH_PIXEL_DENSITY
+                | SDL_WindowFlags::ALWAYS_ON_TOP
+                | SDL_WindowFlags::TRANSPARENT
+                | platform
+        );
+        assert_eq!(
+            WindowBuilder::new("test", 640, 480)
+                .resizable(false)
+                .hidden(true)
+                .flags(),
+            SDL_WindowFlags::HIDDEN | platform
+        );
+    }
+}
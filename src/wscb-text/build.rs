@@ -0,0 +1,91 @@
+//! Link the native libraries built by `building/Build.cs` (`BuildNative` target).
+//!
+//! The build script installs them under `artifact-{Configuration}/bin` and
+//! `install-{Configuration}/lib` at the repository root. Set `WSCB_NATIVE_ROOT`
+//! to use another root directory.
+
+use ::std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+/// Static libraries in link order, each entry lists the accepted file stems
+/// (CMake appends a `d` to some of them in debug builds).
+const STATIC_LIBRARIES: &[&[&str]] = &[
+    &["wscb-cpp"],
+    &["plutosvg"],
+    &["plutovg"],
+    &["freetyped", "freetype"],
+    &["harfbuzz"],
+    &["png16d", "png16", "libpng16_staticd", "libpng16_static"],
+    &["bz2", "bz2_static"],
+    &["brotlidec", "brotlidec-static"],
+    &["brotlicommon", "brotlicommon-static"],
+    &["zd", "z", "zlibstaticd", "zlibstatic"],
+];
+
+fn main() {
+    println!("cargo::rerun-if-env-changed=WSCB_NATIVE_ROOT");
+    println!("cargo::rerun-if-changed=build.rs");
+
+    let configuration = match env::var("PROFILE").as_deref() {
+        Ok("release") => "Release",
+        _ => "Debug",
+    };
+
+    let root = env::var_os("WSCB_NATIVE_ROOT")
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os("CARGO_MANIFEST_DIR").map(|dir| PathBuf::from(dir).join("..").join(".."))
+        })
+        .unwrap_or_default();
+
+    let search_paths = [
+        root.join(format!("artifact-{configuration}")).join("bin"),
+        root.join(format!("install-{configuration}")).join("lib"),
+        root.join(format!("install-{configuration}")).join("lib64"),
+    ];
+
+    if !search_paths[0].exists() {
+        println!(
+            "cargo::warning=native libraries not found in {}, run `build.sh BuildNative` first",
+            search_paths[0].display()
+        );
+        return;
+    }
+
+    for path in &search_paths {
+        println!("cargo::rustc-link-search=native={}", path.display());
+    }
+
+    for candidates in STATIC_LIBRARIES {
+        match find_library(&search_paths, candidates) {
+            Some(name) => println!("cargo::rustc-link-lib=static={name}"),
+            None => println!(
+                "cargo::warning=none of the static libraries {candidates:?} has been found"
+            ),
+        }
+    }
+
+    match env::var("CARGO_CFG_TARGET_OS").as_deref() {
+        Ok("macos") | Ok("ios") => println!("cargo::rustc-link-lib=dylib=c++"),
+        Ok("windows") => {}
+        _ => {
+            println!("cargo::rustc-link-lib=dylib=stdc++");
+            println!("cargo::rustc-link-lib=dylib=dl");
+        }
+    }
+}
+
+/// Find the first candidate that exists in one of the search paths.
+fn find_library<'a>(search_paths: &[PathBuf], candidates: &[&'a str]) -> Option<&'a str> {
+    candidates.iter().copied().find(|name| {
+        search_paths
+            .iter()
+            .any(|dir| library_file_exists(dir, name))
+    })
+}
+
+fn library_file_exists(dir: &Path, name: &str) -> bool {
+    dir.join(format!("lib{name}.a")).exists() || dir.join(format!("{name}.lib")).exists()
+}
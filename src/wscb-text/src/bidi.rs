@@ -0,0 +1,221 @@
+use ::std::ops::Range;
+
+use ::unicode_bidi::{BidiInfo, Level};
+
+use crate::shape::Direction;
+
+/// A paragraph found by the bidi algorithm.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BidiParagraph {
+    /// The byte range, the paragraph separator included.
+    pub range: Range<usize>,
+    /// The base embedding level, odd for right to left paragraphs.
+    pub level: u8,
+}
+
+impl BidiParagraph {
+    pub fn direction(&self) -> Direction {
+        level_direction(self.level)
+    }
+}
+
+/// A maximal range of text with the same embedding level.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BidiRun {
+    pub range: Range<usize>,
+    pub level: u8,
+}
+
+impl BidiRun {
+    /// The direction the run must be shaped with.
+    pub fn direction(&self) -> Direction {
+        level_direction(self.level)
+    }
+}
+
+/// The direction of text at the given embedding level.
+pub fn level_direction(level: u8) -> Direction {
+    if level % 2 == 0 {
+        Direction::LeftToRight
+    } else {
+        Direction::RightToLeft
+    }
+}
+
+/// The resolved embedding levels of a text (UAX #9).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BidiText {
+    /// The level of every byte of the text.
+    levels: Vec<u8>,
+    paragraphs: Vec<BidiParagraph>,
+}
+
+impl BidiText {
+    /// Resolve the levels of `text`.
+    ///
+    /// `base` forces the direction of every paragraph, `None` takes it from the
+    /// first strong character of each paragraph.
+    pub fn new(text: &str, base: Option<Direction>) -> Self {
+        let base = base.map(|direction| {
+            if direction.is_backward() {
+                Level::rtl()
+            } else {
+                Level::ltr()
+            }
+        });
+        let info = BidiInfo::new(text, base);
+
+        let paragraphs = info
+            .paragraphs
+            .iter()
+            .map(|paragraph| BidiParagraph {
+                range: paragraph.range.clone(),
+                level: paragraph.level.number(),
+            })
+            .collect();
+
+        // levels are resolved for whole paragraphs here, the per line rules
+        // (L1 and L2) are applied by the layout after wrapping
+        let levels = info.levels.iter().map(Level::number).collect();
+
+        Self { levels, paragraphs }
+    }
+
+    pub fn paragraphs(&self) -> &[BidiParagraph] {
+        &self.paragraphs
+    }
+
+    /// The paragraph containing the byte offset.
+    pub fn paragraph_at(&self, offset: usize) -> Option<&BidiParagraph> {
+        self.paragraphs
+            .iter()
+            .find(|paragraph| paragraph.range.contains(&offset))
+            .or_else(|| {
+                self.paragraphs
+                    .last()
+                    .filter(|_| offset == self.levels.len())
+            })
+    }
+
+    /// The embedding level of the byte at `offset`.
+    pub fn level_at(&self, offset: usize) -> u8 {
+        self.levels
+            .get(offset)
+            .copied()
+            .or_else(|| self.paragraphs.last().map(|paragraph| paragraph.level))
+            .unwrap_or(0)
+    }
+
+    /// Whether the whole text is left to right.
+    pub fn is_ltr(&self) -> bool {
+        self.levels.iter().all(|level| level % 2 == 0)
+            && self
+                .paragraphs
+                .iter()
+                .all(|paragraph| paragraph.level % 2 == 0)
+    }
+
+    /// The ranges that must be shaped separately, in logical order.
+    ///
+    /// Runs never cross paragraphs.
+    pub fn runs(&self) -> Vec<BidiRun> {
+        let mut runs = Vec::new();
+
+        for paragraph in &self.paragraphs {
+            let mut start = paragraph.range.start;
+            for offset in paragraph.range.clone() {
+                if self.levels[offset] != self.levels[start] {
+                    runs.push(BidiRun {
+                        range: start..offset,
+                        level: self.levels[start],
+                    });
+                    start = offset;
+                }
+            }
+            if start < paragraph.range.end {
+                runs.push(BidiRun {
+                    range: start..paragraph.range.end,
+                    level: self.levels[start],
+                });
+            }
+        }
+
+        runs
+    }
+}
+
+/// Reorder items of a line from logical to visual order (rule L2).
+///
+/// Returns the logical index of every item, in visual order.
+pub fn reorder(levels: &[u8]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..levels.len()).collect();
+
+    let Some(highest) = levels.iter().copied().max() else {
+        return order;
+    };
+    let lowest_odd = levels
+        .iter()
+        .copied()
+        .filter(|level| level % 2 == 1)
+        .min()
+        .unwrap_or(highest + 1);
+
+    // from the highest level down to the lowest odd one, reverse every
+    // sequence at that level or higher
+    for level in (lowest_odd..=highest).rev() {
+        let mut index = 0;
+        while index < order.len() {
+            if levels[order[index]] < level {
+                index += 1;
+                continue;
+            }
+            let start = index;
+            while index < order.len() && levels[order[index]] >= level {
+                index += 1;
+            }
+            order[start..index].reverse();
+        }
+    }
+
+    order
+}
+
+/// The inverse of an order, maps logical indices to visual ones.
+pub fn invert(order: &[usize]) -> Vec<usize> {
+    let mut inverse = vec![0; order.len()];
+    for (visual, &logical) in order.iter().enumerate() {
+        inverse[logical] = visual;
+    }
+    inverse
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reorders_nested_levels() {
+        assert_eq!(reorder(&[0, 0, 1, 1, 1, 0]), [0, 1, 4, 3, 2, 5]);
+        // numbers inside right to left text keep their order
+        assert_eq!(reorder(&[1, 1, 2, 2, 1]), [4, 2, 3, 1, 0]);
+        assert_eq!(reorder(&[]), Vec::<usize>::new());
+        assert_eq!(invert(&[4, 2, 3, 1, 0]), [4, 3, 1, 2, 0]);
+    }
+
+    #[test]
+    fn splits_runs_by_level() {
+        let text = "abc \u{5d0}\u{5d1} 12 def";
+        let bidi = BidiText::new(text, None);
+        assert_eq!(bidi.paragraphs()[0].level, 0);
+
+        let runs: Vec<_> = bidi
+            .runs()
+            .into_iter()
+            .map(|run| (&text[run.range], run.level))
+            .collect();
+        assert_eq!(
+            runs,
+            [("abc ", 0), ("\u{5d0}\u{5d1} ", 1), ("12", 2), (" def", 0)]
+        );
+    }
+}
@@ -0,0 +1,438 @@
+use ::std::{cell::RefCell, ops::Range};
+
+use ::wscb_type::HashMap;
+
+use crate::{
+    bidi::BidiText,
+    error::TextError,
+    font::Face,
+    shape::{Script, ShapeOptions, ShapedRun},
+};
+
+/// A range of text drawn with one face, in one script.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontRun {
+    pub range: Range<usize>,
+    pub face: Face,
+    /// The script of the run, [`Script::COMMON`] when it only has neutral characters.
+    pub script: Script,
+}
+
+/// An ordered fallback list of faces.
+///
+/// Every character uses the first face that has a glyph for it. Which faces
+/// cover which characters is cached, so itemizing the same text every frame
+/// only asks FreeType once per character.
+#[derive(Debug, Default)]
+pub struct FontCollection {
+    faces: Vec<Face>,
+    /// Whether the face at an index covers a character.
+    coverage: RefCell<HashMap<(usize, char), bool>>,
+    /// The index of the first face covering a character.
+    first: RefCell<HashMap<char, Option<usize>>>,
+}
+
+impl FontCollection {
+    pub fn new(faces: impl IntoIterator<Item = Face>) -> Self {
+        Self {
+            faces: faces.into_iter().collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Append a face to the end of the fallback list.
+    pub fn push(&mut self, face: Face) {
+        self.faces.push(face);
+        // characters nothing covered may be covered now
+        self.first.get_mut().retain(|_, index| index.is_some());
+    }
+
+    pub fn faces(&self) -> &[Face] {
+        &self.faces
+    }
+
+    pub fn len(&self) -> usize {
+        self.faces.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.faces.is_empty()
+    }
+
+    /// Whether the face at `index` has a glyph for the character.
+    pub fn covers(&self, index: usize, ch: char) -> bool {
+        let Some(face) = self.faces.get(index) else {
+            return false;
+        };
+
+        *self
+            .coverage
+            .borrow_mut()
+            .entry((index, ch))
+            .or_insert_with(|| face.glyph_index(ch).is_some())
+    }
+
+    /// The index of the first face covering the character.
+    pub fn face_index_for(&self, ch: char) -> Option<usize> {
+        if let Some(index) = self.first.borrow().get(&ch) {
+            return *index;
+        }
+
+        let index = (0..self.faces.len()).find(|&index| self.covers(index, ch));
+        self.first.borrow_mut().insert(ch, index);
+        index
+    }
+
+    /// The first face covering the character.
+    pub fn face_for(&self, ch: char) -> Option<&Face> {
+        self.face_index_for(ch).map(|index| &self.faces[index])
+    }
+
+    /// Split the text into runs of one face and one script.
+    ///
+    /// Neutral characters (spaces, punctuation, combining marks) stay with the
+    /// face and script of the text before them when that face covers them, so
+    /// marks are not split from their base and spaces do not break runs.
+    /// Characters no face covers use the face before them (or the first face)
+    /// and are drawn as missing glyphs.
+    pub fn itemize(&self, text: &str) -> Vec<FontRun> {
+        struct Current {
+            start: usize,
+            face: usize,
+            script: Script,
+        }
+
+        let mut runs = Vec::new();
+        let mut current: Option<Current> = None;
+
+        for (offset, ch) in text.char_indices() {
+            let script = Script::of(ch);
+            let neutral = script.is_neutral();
+
+            let face = match &current {
+                Some(run) if neutral && self.covers(run.face, ch) => run.face,
+                _ => self
+                    .face_index_for(ch)
+                    .or(current.as_ref().map(|run| run.face))
+                    .unwrap_or(0),
+            };
+
+            match &mut current {
+                Some(run)
+                    if run.face == face
+                        && (neutral || run.script.is_neutral() || run.script == script) =>
+                {
+                    if !neutral {
+                        run.script = script;
+                    }
+                }
+                _ => {
+                    let previous_script = current.as_ref().map(|run| run.script);
+                    if let Some(run) = current.take() {
+                        self.finish_run(&mut runs, run.start..offset, run.face, run.script);
+                    }
+                    current = Some(Current {
+                        start: offset,
+                        face,
+                        script: if neutral {
+                            previous_script.unwrap_or(Script::COMMON)
+                        } else {
+                            script
+                        },
+                    });
+                }
+            }
+        }
+
+        if let Some(run) = current {
+            self.finish_run(&mut runs, run.start..text.len(), run.face, run.script);
+        }
+
+        runs
+    }
+
+    fn finish_run(
+        &self,
+        runs: &mut Vec<FontRun>,
+        range: Range<usize>,
+        face: usize,
+        script: Script,
+    ) {
+        if let Some(face) = self.faces.get(face) {
+            runs.push(FontRun {
+                range,
+                face: face.clone(),
+                script,
+            });
+        }
+    }
+
+    /// Shape the text with fallback, ready for [`Layout::new`](crate::layout::Layout::new).
+    ///
+    /// The text is split by bidi level and by [`FontCollection::itemize`].
+    /// `options.direction` is the base direction of the paragraphs, the
+    /// script is only used where the text itself is neutral.
+    pub fn shape(
+        &self,
+        text: &str,
+        pixel_size: u32,
+        options: &ShapeOptions,
+    ) -> Result<Vec<ShapedRun>, TextError> {
+        let bidi = BidiText::new(text, options.direction);
+        self.shape_range(text, 0..text.len(), &bidi, pixel_size, options)
+    }
+
+    /// Shape a range of the text, like [`FontCollection::shape`].
+    ///
+    /// Used to shape the parts of a paragraph that have different styles, the
+    /// levels of the whole paragraph are resolved once by the caller.
+    pub fn shape_range(
+        &self,
+        text: &str,
+        range: Range<usize>,
+        bidi: &BidiText,
+        pixel_size: u32,
+        options: &ShapeOptions,
+    ) -> Result<Vec<ShapedRun>, TextError> {
+        let Some(slice) = text.get(range.clone()) else {
+            return Err(TextError::InvalidRange {
+                start: range.start,
+                end: range.end,
+                len: text.len(),
+            });
+        };
+        let font_runs: Vec<FontRun> = self
+            .itemize(slice)
+            .into_iter()
+            .map(|run| FontRun {
+                range: run.range.start + range.start..run.range.end + range.start,
+                ..run
+            })
+            .collect();
+
+        let mut runs = Vec::new();
+        let mut font_index = 0;
+
+        for bidi_run in bidi.runs() {
+            let bidi_range =
+                bidi_run.range.start.max(range.start)..bidi_run.range.end.min(range.end);
+            if bidi_range.is_empty() {
+                continue;
+            }
+
+            while font_index < font_runs.len()
+                && font_runs[font_index].range.end <= bidi_range.start
+            {
+                font_index += 1;
+            }
+
+            for font_run in font_runs[font_index..]
+                .iter()
+                .take_while(|run| run.range.start < bidi_range.end)
+            {
+                let run_range = font_run.range.start.max(bidi_range.start)
+                    ..font_run.range.end.min(bidi_range.end);
+
+                let run_options = ShapeOptions {
+                    direction: Some(bidi_run.direction()),
+                    script: if font_run.script.is_neutral() {
+                        options.script
+                    } else {
+                        Some(font_run.script)
+                    },
+                    language: options.language,
+                    features: options.features.clone(),
+                };
+
+                runs.push(
+                    font_run
+                        .face
+                        .shape(text, run_range, pixel_size, &run_options)?,
+                );
+            }
+        }
+
+        Ok(runs)
+    }
+}
+
+/// The collections of the styles of a font family.
+///
+/// Missing styles use the closest one that exists, down to `regular`.
+#[derive(Debug, Default)]
+pub struct FontFamily {
+    pub regular: FontCollection,
+    pub bold: Option<FontCollection>,
+    pub italic: Option<FontCollection>,
+    pub bold_italic: Option<FontCollection>,
+}
+
+impl FontFamily {
+    pub fn new(regular: FontCollection) -> Self {
+        Self {
+            regular,
+            ..Default::default()
+        }
+    }
+
+    /// The collection for a style.
+    pub fn select(&self, bold: bool, italic: bool) -> &FontCollection {
+        let found = match (bold, italic) {
+            (true, true) => self
+                .bold_italic
+                .as_ref()
+                .or(self.bold.as_ref())
+                .or(self.italic.as_ref()),
+            (true, false) => self.bold.as_ref(),
+            (false, true) => self.italic.as_ref(),
+            (false, false) => None,
+        };
+        found.unwrap_or(&self.regular)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_font::{SPACE, SQUARE, TestFont, longs, words};
+
+    /// The test font with `' '` and the given character ranges mapped to the square.
+    fn font(ranges: &[(char, char)]) -> Result<Face, TextError> {
+        let segments = ranges.len() + 2;
+        let mut starts = vec![0x20];
+        let mut ends = vec![0x20];
+        let mut deltas = vec![SPACE as i32 - 0x20];
+        let mut offsets = vec![0];
+        let mut glyphs = Vec::new();
+        for (index, &(first, last)) in ranges.iter().enumerate() {
+            starts.push(first as i32);
+            ends.push(last as i32);
+            deltas.push(0);
+            // from the offset itself to the first glyph of the range
+            offsets.push(((segments - 1 - index + glyphs.len()) * 2) as i32);
+            glyphs.extend((first..=last).map(|_| SQUARE as i32));
+        }
+        starts.push(0xFFFF);
+        ends.push(0xFFFF);
+        deltas.push(1);
+        offsets.push(0);
+
+        let segments = segments as i32;
+        let selector = segments.ilog2() as i32;
+        let mut subtable = Vec::new();
+        words(
+            &mut subtable,
+            &[
+                4,
+                16 + segments * 8 + glyphs.len() as i32 * 2,
+                0,
+                segments * 2,
+            ],
+        );
+        words(
+            &mut subtable,
+            &[2 << selector, selector, segments * 2 - (2 << selector)],
+        );
+        words(&mut subtable, &ends);
+        words(&mut subtable, &[0]);
+        words(&mut subtable, &starts);
+        words(&mut subtable, &deltas);
+        words(&mut subtable, &offsets);
+        words(&mut subtable, &glyphs);
+
+        let mut cmap = Vec::new();
+        words(&mut cmap, &[0, 1, 3, 1]);
+        longs(&mut cmap, &[12]);
+        cmap.extend(subtable);
+
+        TestFont::new().with_table(b"cmap", cmap).face()
+    }
+
+    fn runs(fonts: &FontCollection, text: &str) -> Vec<(Range<usize>, usize, Script)> {
+        (fonts.itemize(text).into_iter())
+            .map(|run| {
+                let face = (fonts.faces().iter())
+                    .position(|face| *face == run.face)
+                    .unwrap_or(usize::MAX);
+                (run.range, face, run.script)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn itemizes_by_coverage() -> Result<(), TextError> {
+        let fonts = FontCollection::new([font(&[('a', 'z')])?, font(&[('α', 'ω')])?]);
+
+        // spaces stay with the face before them, `αβ` is four bytes
+        assert_eq!(
+            runs(&fonts, "ab αβ c"),
+            [
+                (0..3, 0, Script::LATIN),
+                (3..8, 1, Script::GREEK),
+                (8..9, 0, Script::LATIN),
+            ]
+        );
+        // a space no run covers yet goes to the first face that covers it
+        assert_eq!(
+            runs(&fonts, " αβ"),
+            [(0..1, 0, Script::COMMON), (1..5, 1, Script::GREEK)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn splits_one_face_by_script() -> Result<(), TextError> {
+        let fonts = FontCollection::new([font(&[('a', 'z'), ('α', 'ω')])?]);
+
+        // the neutral space joins the run before it, the script is taken
+        // from the first character that has one
+        assert_eq!(
+            runs(&fonts, " ab αβ"),
+            [(0..4, 0, Script::LATIN), (4..8, 0, Script::GREEK)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn falls_back_in_order() -> Result<(), TextError> {
+        let latin = font(&[('a', 'z')])?;
+        let both = font(&[('a', 'z'), ('α', 'ω')])?;
+
+        let fonts = FontCollection::new([latin.clone(), both.clone()]);
+        assert_eq!(fonts.face_index_for('a'), Some(0));
+        assert_eq!(fonts.face_index_for('α'), Some(1));
+        assert_eq!(fonts.face_for('α'), Some(&both));
+        assert_eq!(fonts.face_index_for('日'), None);
+
+        let fonts = FontCollection::new([both, latin]);
+        assert_eq!(fonts.face_index_for('a'), Some(0));
+
+        // characters nothing covers stay with the face before them
+        let fonts = FontCollection::new([font(&[('α', 'ω')])?, font(&[('a', 'z')])?]);
+        assert_eq!(
+            runs(&fonts, "a日"),
+            [(0..1, 1, Script::LATIN), (1..4, 1, Script::HAN)]
+        );
+        assert_eq!(runs(&fonts, "日"), [(0..3, 0, Script::HAN)]);
+        Ok(())
+    }
+
+    #[test]
+    fn caches_coverage() -> Result<(), TextError> {
+        let mut fonts = FontCollection::new([font(&[('a', 'z')])?]);
+        fonts.itemize("abba");
+        assert_eq!(fonts.coverage.borrow().len(), 2);
+        assert_eq!(fonts.first.borrow().len(), 2);
+
+        assert_eq!(fonts.face_index_for('α'), None);
+        assert_eq!(fonts.first.borrow().get(&'α'), Some(&None));
+
+        // a new face may cover what nothing did, the rest is kept
+        fonts.push(font(&[('α', 'ω')])?);
+        assert_eq!(fonts.first.borrow().len(), 2);
+        assert_eq!(fonts.face_index_for('α'), Some(1));
+        assert_eq!(fonts.face_index_for('a'), Some(0));
+        assert!(!fonts.covers(1, 'a') && !fonts.covers(2, 'a'));
+        Ok(())
+    }
+}
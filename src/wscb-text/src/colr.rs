@@ -0,0 +1,1171 @@
+//! COLRv1 color glyphs.
+//!
+//! FreeType parses the paint graph of COLRv1 glyphs but leaves the drawing to
+//! its caller. The graph is walked here and composed in software: fills and
+//! gradients are clipped by glyph outlines rasterized with FreeType, layers
+//! are painted source over and composite paints blend two isolated layers.
+
+use ::std::f64::consts::PI;
+
+use ::wscb_sdl::graph::Surface;
+use ::wscb_type::graph::Point;
+
+use crate::{
+    error::TextError,
+    ffi::freetype::{
+        FT_Bitmap, FT_COLOR_NO_ROOT_TRANSFORM, FT_COLR_COMPOSITE_CLEAR,
+        FT_COLR_COMPOSITE_COLOR_BURN, FT_COLR_COMPOSITE_COLOR_DODGE, FT_COLR_COMPOSITE_DARKEN,
+        FT_COLR_COMPOSITE_DEST, FT_COLR_COMPOSITE_DEST_ATOP, FT_COLR_COMPOSITE_DEST_IN,
+        FT_COLR_COMPOSITE_DEST_OUT, FT_COLR_COMPOSITE_DEST_OVER, FT_COLR_COMPOSITE_DIFFERENCE,
+        FT_COLR_COMPOSITE_EXCLUSION, FT_COLR_COMPOSITE_HARD_LIGHT, FT_COLR_COMPOSITE_HSL_COLOR,
+        FT_COLR_COMPOSITE_HSL_HUE, FT_COLR_COMPOSITE_HSL_LUMINOSITY,
+        FT_COLR_COMPOSITE_HSL_SATURATION, FT_COLR_COMPOSITE_LIGHTEN, FT_COLR_COMPOSITE_MULTIPLY,
+        FT_COLR_COMPOSITE_OVERLAY, FT_COLR_COMPOSITE_PLUS, FT_COLR_COMPOSITE_SCREEN,
+        FT_COLR_COMPOSITE_SOFT_LIGHT, FT_COLR_COMPOSITE_SRC, FT_COLR_COMPOSITE_SRC_ATOP,
+        FT_COLR_COMPOSITE_SRC_IN, FT_COLR_COMPOSITE_SRC_OUT, FT_COLR_COMPOSITE_SRC_OVER,
+        FT_COLR_COMPOSITE_XOR, FT_COLR_PAINT_EXTEND_REFLECT, FT_COLR_PAINT_EXTEND_REPEAT,
+        FT_COLR_PAINTFORMAT_COLR_GLYPH, FT_COLR_PAINTFORMAT_COLR_LAYERS,
+        FT_COLR_PAINTFORMAT_COMPOSITE, FT_COLR_PAINTFORMAT_GLYPH,
+        FT_COLR_PAINTFORMAT_LINEAR_GRADIENT, FT_COLR_PAINTFORMAT_RADIAL_GRADIENT,
+        FT_COLR_PAINTFORMAT_ROTATE, FT_COLR_PAINTFORMAT_SCALE, FT_COLR_PAINTFORMAT_SKEW,
+        FT_COLR_PAINTFORMAT_SOLID, FT_COLR_PAINTFORMAT_SWEEP_GRADIENT,
+        FT_COLR_PAINTFORMAT_TRANSFORM, FT_COLR_PAINTFORMAT_TRANSLATE, FT_COLR_Paint, FT_ClipBox,
+        FT_Color, FT_ColorIndex, FT_ColorLine, FT_ColorStop, FT_Composite_Mode, FT_Face, FT_Fixed,
+        FT_GLYPH_FORMAT_OUTLINE, FT_Get_Color_Glyph_ClipBox, FT_Get_Color_Glyph_Paint,
+        FT_Get_Colorline_Stops, FT_Get_Paint, FT_Get_Paint_Layers, FT_LOAD_IGNORE_TRANSFORM,
+        FT_LOAD_NO_SCALE, FT_Load_Glyph, FT_OpaquePaint, FT_Outline, FT_Outline_Get_Bitmap,
+        FT_PALETTE_FOR_DARK_BACKGROUND, FT_PIXEL_MODE_GRAY, FT_Palette_Data, FT_Palette_Data_Get,
+        FT_Palette_Select, FT_Pos, FT_Vector,
+    },
+    font::Face,
+    raster::GLYPH_PIXEL_FORMAT,
+};
+
+/// Paint graphs nested deeper than this are cut off, they are broken or hostile.
+const MAX_DEPTH: u32 = 64;
+
+/// Glyph images larger than this many pixels on a side are not rendered.
+const MAX_EXTENT: i32 = 4096;
+
+/// The palette index that stands for the text color.
+const FOREGROUND_INDEX: u16 = 0xFFFF;
+
+/// A premultiplied RGBA color with channels in `0.0..=1.0`.
+type Rgba = [f32; 4];
+
+const TRANSPARENT: Rgba = [0.0; 4];
+
+/// A rendered COLRv1 glyph.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ColorImage {
+    pub width: usize,
+    pub height: usize,
+    /// Straight alpha RGBA rows, top to bottom.
+    pub data: Vec<u8>,
+    /// The offset from the pen position to the top left corner, y goes down.
+    pub bearing: Point,
+}
+
+impl ColorImage {
+    /// The image as a [`GLYPH_PIXEL_FORMAT`] surface, `None` if it is empty.
+    pub fn to_surface(&self) -> Result<Option<Surface>, TextError> {
+        if self.width == 0 || self.height == 0 {
+            return Ok(None);
+        }
+
+        let surface = Surface::new(self.width as u32, self.height as u32, GLYPH_PIXEL_FORMAT)?;
+        let pitch = surface.pitch() as usize;
+        let pixels = surface.pixels();
+
+        for (y, source) in self.data.chunks_exact(self.width * 4).enumerate() {
+            let row =
+                unsafe { std::slice::from_raw_parts_mut(pixels.add(y * pitch), self.width * 4) };
+            row.copy_from_slice(source);
+        }
+
+        Ok(Some(surface))
+    }
+}
+
+impl Face {
+    /// Render the COLRv1 paint graph of a glyph at the current pixel size,
+    /// shifted right by `subpixel_offset` pixels.
+    ///
+    /// Returns `None` if the glyph has no paint graph, so it is a COLRv0,
+    /// bitmap or plain outline glyph. Palette 0 is used and the foreground
+    /// color is black, white when that palette is meant for dark backgrounds.
+    pub(crate) fn render_colr_glyph(
+        &self,
+        glyph_id: u32,
+        subpixel_offset: f32,
+    ) -> Option<ColorImage> {
+        let face = self.get_pointer();
+
+        let mut root = FT_OpaquePaint {
+            p: std::ptr::null_mut(),
+            insert_root_transform: 0,
+        };
+        if unsafe {
+            FT_Get_Color_Glyph_Paint(face, glyph_id, FT_COLOR_NO_ROOT_TRANSFORM, &mut root)
+        } == 0
+        {
+            return None;
+        }
+
+        // font units to pixels, y goes up
+        let raw = unsafe { &*face };
+        let (x_scale, y_scale) = if raw.size.is_null() {
+            (0.0, 0.0)
+        } else {
+            let metrics = unsafe { (*raw.size).metrics };
+            (fixed(metrics.x_scale) / 64.0, fixed(metrics.y_scale) / 64.0)
+        };
+        let transform = Affine {
+            dx: subpixel_offset as f64,
+            ..Affine::scale(x_scale, y_scale)
+        };
+
+        let canvas = Canvas::for_glyph(face, glyph_id, transform, subpixel_offset as f64)?;
+        let (palette, foreground) = palette(face);
+
+        let painter = Painter {
+            face: self,
+            canvas,
+            palette,
+            foreground,
+        };
+
+        let clip = vec![1.0; canvas.len()];
+        let mut target = vec![TRANSPARENT; canvas.len()];
+        painter.paint(root, transform, &clip, &mut target, 0);
+
+        Some(canvas.crop(&target))
+    }
+}
+
+/// The pixels a glyph is painted on, `left`/`top` are the pixel coordinates of
+/// the top left corner with y going up.
+#[derive(Debug, Clone, Copy)]
+struct Canvas {
+    left: i32,
+    top: i32,
+    width: usize,
+    height: usize,
+}
+
+impl Canvas {
+    /// The area covered by the clip box of the glyph, or by the bounding box
+    /// of the whole face if the font does not give one.
+    fn for_glyph(
+        face: FT_Face,
+        glyph_id: u32,
+        transform: Affine,
+        subpixel_offset: f64,
+    ) -> Option<Self> {
+        let mut clip_box = FT_ClipBox::default();
+        let corners = if unsafe { FT_Get_Color_Glyph_ClipBox(face, glyph_id, &mut clip_box) } != 0 {
+            [
+                clip_box.bottom_left,
+                clip_box.top_left,
+                clip_box.top_right,
+                clip_box.bottom_right,
+            ]
+            .map(|corner| {
+                (
+                    corner.x as f64 / 64.0 + subpixel_offset,
+                    corner.y as f64 / 64.0,
+                )
+            })
+        } else {
+            let bbox = unsafe { (*face).bbox };
+            [
+                (bbox.xMin, bbox.yMin),
+                (bbox.xMin, bbox.yMax),
+                (bbox.xMax, bbox.yMax),
+                (bbox.xMax, bbox.yMin),
+            ]
+            .map(|(x, y)| transform.apply(x as f64, y as f64))
+        };
+
+        let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+        let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for (x, y) in corners {
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+        }
+
+        let width = max_x.ceil() - min_x.floor();
+        let height = max_y.ceil() - min_y.floor();
+        if !(width >= 0.0 && height >= 0.0)
+            || width > MAX_EXTENT as f64
+            || height > MAX_EXTENT as f64
+        {
+            return None;
+        }
+
+        Some(Self {
+            left: min_x.floor() as i32,
+            top: max_y.ceil() as i32,
+            width: width as usize,
+            height: height as usize,
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.width * self.height
+    }
+
+    /// The center of every pixel in pixel coordinates, row by row from the top.
+    fn centers(&self) -> impl Iterator<Item = (f64, f64)> {
+        let (left, top, width) = (self.left as f64, self.top as f64, self.width);
+        (0..self.len()).map(move |index| {
+            (
+                left + (index % width) as f64 + 0.5,
+                top - (index / width) as f64 - 0.5,
+            )
+        })
+    }
+
+    /// Crop the painted pixels and convert them to straight alpha.
+    fn crop(self, pixels: &[Rgba]) -> ColorImage {
+        let covered = |index: usize| pixels[index][3] * 255.0 >= 0.5;
+
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (usize::MAX, usize::MAX, 0, 0);
+        for index in (0..self.len()).filter(|&index| covered(index)) {
+            let (x, y) = (index % self.width, index / self.width);
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x + 1);
+            max_y = max_y.max(y + 1);
+        }
+
+        if min_x == usize::MAX {
+            return ColorImage {
+                width: 0,
+                height: 0,
+                data: Vec::new(),
+                bearing: Point::new(self.left, -self.top),
+            };
+        }
+
+        let (width, height) = (max_x - min_x, max_y - min_y);
+        let mut data = Vec::with_capacity(width * height * 4);
+        for y in min_y..max_y {
+            for pixel in &pixels[y * self.width + min_x..y * self.width + max_x] {
+                data.extend(unpremultiply(*pixel));
+            }
+        }
+
+        ColorImage {
+            width,
+            height,
+            data,
+            bearing: Point::new(self.left + min_x as i32, min_y as i32 - self.top),
+        }
+    }
+}
+
+/// Walks a paint graph, every paint draws into a target clipped by a coverage mask.
+struct Painter<'a> {
+    face: &'a Face,
+    canvas: Canvas,
+    palette: Vec<Rgba>,
+    foreground: Rgba,
+}
+
+impl Painter<'_> {
+    /// Draw a paint source over `target`.
+    ///
+    /// `transform` maps the paint's coordinates (font units) to pixels and
+    /// `clip` is the coverage of every canvas pixel. Paints FreeType cannot
+    /// read are skipped.
+    fn paint(
+        &self,
+        paint: FT_OpaquePaint,
+        transform: Affine,
+        clip: &[f32],
+        target: &mut [Rgba],
+        depth: u32,
+    ) {
+        if depth > MAX_DEPTH {
+            return;
+        }
+
+        let face = self.face.get_pointer();
+        let mut read: FT_COLR_Paint = unsafe { std::mem::zeroed() };
+        if unsafe { FT_Get_Paint(face, paint, &mut read) } == 0 {
+            return;
+        }
+
+        let depth = depth + 1;
+
+        unsafe {
+            match read.format {
+                FT_COLR_PAINTFORMAT_COLR_LAYERS => {
+                    let mut iterator = read.u.colr_layers.layer_iterator;
+                    let mut layer: FT_OpaquePaint = std::mem::zeroed();
+                    while FT_Get_Paint_Layers(face, &mut iterator, &mut layer) != 0 {
+                        self.paint(layer, transform, clip, target, depth);
+                    }
+                }
+                FT_COLR_PAINTFORMAT_SOLID => {
+                    let color = self.color(read.u.solid.color);
+                    self.fill(clip, target, |_, _| color);
+                }
+                FT_COLR_PAINTFORMAT_LINEAR_GRADIENT => {
+                    let gradient = read.u.linear_gradient;
+                    let line = self.color_line(gradient.colorline);
+                    let p0 = vector(gradient.p0);
+                    let p1 = vector(gradient.p1);
+                    let p2 = vector(gradient.p2);
+                    self.fill_gradient(clip, target, transform, |x, y| {
+                        line.at(linear_position(p0, p1, p2, (x, y))?)
+                    });
+                }
+                FT_COLR_PAINTFORMAT_RADIAL_GRADIENT => {
+                    let gradient = read.u.radial_gradient;
+                    let line = self.color_line(gradient.colorline);
+                    let c0 = vector(gradient.c0);
+                    let c1 = vector(gradient.c1);
+                    let (r0, r1) = (fixed(gradient.r0), fixed(gradient.r1));
+                    self.fill_gradient(clip, target, transform, |x, y| {
+                        line.at(radial_position(c0, r0, c1, r1, (x, y))?)
+                    });
+                }
+                FT_COLR_PAINTFORMAT_SWEEP_GRADIENT => {
+                    let gradient = read.u.sweep_gradient;
+                    let line = self.color_line(gradient.colorline);
+                    let center = vector(gradient.center);
+                    // angles are in half turns, 1.0 is 180 degrees
+                    let start = fixed(gradient.start_angle) * 180.0;
+                    let end = fixed(gradient.end_angle) * 180.0;
+                    self.fill_gradient(clip, target, transform, |x, y| {
+                        let angle = (y - center.1).atan2(x - center.0).to_degrees();
+                        let angle = angle.rem_euclid(360.0);
+                        if (end - start).abs() < f64::EPSILON {
+                            // a zero sweep pads with the first or the last stop
+                            line.edge(angle >= start)
+                        } else {
+                            line.at((angle - start) / (end - start))
+                        }
+                    });
+                }
+                FT_COLR_PAINTFORMAT_GLYPH => {
+                    let glyph = read.u.glyph;
+                    let Some(mask) = self.glyph_mask(glyph.glyphID, transform) else {
+                        return;
+                    };
+                    let clip: Vec<f32> = clip.iter().zip(mask).map(|(a, b)| a * b).collect();
+                    if clip.iter().any(|&coverage| coverage > 0.0) {
+                        self.paint(glyph.paint, transform, &clip, target, depth);
+                    }
+                }
+                FT_COLR_PAINTFORMAT_COLR_GLYPH => {
+                    let mut root: FT_OpaquePaint = std::mem::zeroed();
+                    if FT_Get_Color_Glyph_Paint(
+                        face,
+                        read.u.colr_glyph.glyphID,
+                        FT_COLOR_NO_ROOT_TRANSFORM,
+                        &mut root,
+                    ) != 0
+                    {
+                        self.paint(root, transform, clip, target, depth);
+                    }
+                }
+                FT_COLR_PAINTFORMAT_TRANSFORM => {
+                    let paint = read.u.transform;
+                    let affine = paint.affine;
+                    let inner = Affine {
+                        xx: fixed(affine.xx),
+                        xy: fixed(affine.xy),
+                        dx: fixed(affine.dx),
+                        yx: fixed(affine.yx),
+                        yy: fixed(affine.yy),
+                        dy: fixed(affine.dy),
+                    };
+                    self.paint(paint.paint, transform.then(inner), clip, target, depth);
+                }
+                FT_COLR_PAINTFORMAT_TRANSLATE => {
+                    let paint = read.u.translate;
+                    let inner = Affine::translate(fixed(paint.dx), fixed(paint.dy));
+                    self.paint(paint.paint, transform.then(inner), clip, target, depth);
+                }
+                FT_COLR_PAINTFORMAT_SCALE => {
+                    let paint = read.u.scale;
+                    let inner = Affine::scale(fixed(paint.scale_x), fixed(paint.scale_y))
+                        .around(fixed(paint.center_x), fixed(paint.center_y));
+                    self.paint(paint.paint, transform.then(inner), clip, target, depth);
+                }
+                FT_COLR_PAINTFORMAT_ROTATE => {
+                    let paint = read.u.rotate;
+                    let (sin, cos) = (fixed(paint.angle) * PI).sin_cos();
+                    let inner = Affine {
+                        xx: cos,
+                        xy: -sin,
+                        yx: sin,
+                        yy: cos,
+                        ..Affine::IDENTITY
+                    }
+                    .around(fixed(paint.center_x), fixed(paint.center_y));
+                    self.paint(paint.paint, transform.then(inner), clip, target, depth);
+                }
+                FT_COLR_PAINTFORMAT_SKEW => {
+                    let paint = read.u.skew;
+                    let inner = Affine {
+                        xy: (-fixed(paint.x_skew_angle) * PI).tan(),
+                        yx: (fixed(paint.y_skew_angle) * PI).tan(),
+                        ..Affine::IDENTITY
+                    }
+                    .around(fixed(paint.center_x), fixed(paint.center_y));
+                    self.paint(paint.paint, transform.then(inner), clip, target, depth);
+                }
+                FT_COLR_PAINTFORMAT_COMPOSITE => {
+                    let paint = read.u.composite;
+                    let mut backdrop = vec![TRANSPARENT; target.len()];
+                    self.paint(paint.backdrop_paint, transform, clip, &mut backdrop, depth);
+                    let mut source = vec![TRANSPARENT; target.len()];
+                    self.paint(paint.source_paint, transform, clip, &mut source, depth);
+
+                    for ((pixel, source), backdrop) in target.iter_mut().zip(source).zip(backdrop) {
+                        let composed = composite(paint.composite_mode, source, backdrop);
+                        *pixel = source_over(composed, *pixel);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Draw a color computed at every pixel center (in pixels), scaled by the clip.
+    fn fill(&self, clip: &[f32], target: &mut [Rgba], color: impl Fn(f64, f64) -> Rgba) {
+        for ((pixel, &coverage), (x, y)) in target.iter_mut().zip(clip).zip(self.canvas.centers()) {
+            if coverage <= 0.0 {
+                continue;
+            }
+            let color = color(x, y).map(|channel| channel * coverage);
+            *pixel = source_over(color, *pixel);
+        }
+    }
+
+    /// Draw a gradient given in the coordinates `transform` maps to pixels.
+    fn fill_gradient(
+        &self,
+        clip: &[f32],
+        target: &mut [Rgba],
+        transform: Affine,
+        color: impl Fn(f64, f64) -> Option<Rgba>,
+    ) {
+        // a degenerate transform squashes the gradient into nothing
+        let Some(inverse) = transform.invert() else {
+            return;
+        };
+        self.fill(clip, target, |x, y| {
+            let (x, y) = inverse.apply(x, y);
+            color(x, y).unwrap_or(TRANSPARENT)
+        });
+    }
+
+    /// The coverage of an outline glyph on the canvas.
+    fn glyph_mask(&self, glyph_id: u32, transform: Affine) -> Option<Vec<f32>> {
+        let face = self.face.get_pointer();
+        let canvas = self.canvas;
+
+        unsafe {
+            if FT_Load_Glyph(face, glyph_id, FT_LOAD_NO_SCALE | FT_LOAD_IGNORE_TRANSFORM) != 0 {
+                return None;
+            }
+
+            let slot = &*(*face).glyph;
+            if slot.format != FT_GLYPH_FORMAT_OUTLINE {
+                return None;
+            }
+
+            let outline = slot.outline;
+            let points = if outline.n_points == 0 {
+                &[]
+            } else {
+                std::slice::from_raw_parts(outline.points, outline.n_points as usize)
+            };
+
+            // the bitmap's origin is the bottom left corner of the canvas, in 26.6
+            let bottom = (canvas.top - canvas.height as i32) as f64;
+            let mut moved: Vec<FT_Vector> = points
+                .iter()
+                .map(|point| {
+                    let (x, y) = transform.apply(point.x as f64, point.y as f64);
+                    FT_Vector {
+                        x: ((x - canvas.left as f64) * 64.0).round() as FT_Pos,
+                        y: ((y - bottom) * 64.0).round() as FT_Pos,
+                    }
+                })
+                .collect();
+            let moved_outline = FT_Outline {
+                points: moved.as_mut_ptr(),
+                ..outline
+            };
+
+            let mut buffer = vec![0u8; canvas.len()];
+            let bitmap = FT_Bitmap {
+                rows: canvas.height as u32,
+                width: canvas.width as u32,
+                pitch: canvas.width as i32,
+                buffer: buffer.as_mut_ptr(),
+                num_grays: 256,
+                pixel_mode: FT_PIXEL_MODE_GRAY,
+                palette_mode: 0,
+                palette: std::ptr::null_mut(),
+            };
+
+            if FT_Outline_Get_Bitmap(self.face.library().get_pointer(), &moved_outline, &bitmap)
+                != 0
+            {
+                return None;
+            }
+
+            Some(
+                buffer
+                    .iter()
+                    .map(|&coverage| coverage as f32 / 255.0)
+                    .collect(),
+            )
+        }
+    }
+
+    /// A palette entry scaled by the paint's alpha.
+    fn color(&self, index: FT_ColorIndex) -> Rgba {
+        let color = if index.palette_index == FOREGROUND_INDEX {
+            self.foreground
+        } else {
+            self.palette
+                .get(index.palette_index as usize)
+                .copied()
+                .unwrap_or(TRANSPARENT)
+        };
+        let alpha = (index.alpha as f32 / 16384.0).clamp(0.0, 1.0);
+        color.map(|channel| channel * alpha)
+    }
+
+    fn color_line(&self, line: FT_ColorLine) -> ColorLine {
+        let face = self.face.get_pointer();
+        let mut iterator = line.color_stop_iterator;
+        let mut stops = Vec::new();
+
+        unsafe {
+            let mut stop: FT_ColorStop = std::mem::zeroed();
+            while FT_Get_Colorline_Stops(face, &mut stop, &mut iterator) != 0 {
+                stops.push((fixed(stop.stop_offset), self.color(stop.color)));
+            }
+        }
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        ColorLine {
+            extend: line.extend,
+            stops,
+        }
+    }
+}
+
+/// The colors of palette 0 and the foreground color.
+fn palette(face: FT_Face) -> (Vec<Rgba>, Rgba) {
+    const BLACK: Rgba = [0.0, 0.0, 0.0, 1.0];
+    const WHITE: Rgba = [1.0; 4];
+
+    unsafe {
+        let mut data: FT_Palette_Data = std::mem::zeroed();
+        if FT_Palette_Data_Get(face, &mut data) != 0 || data.num_palettes == 0 {
+            return (Vec::new(), BLACK);
+        }
+
+        let foreground = if !data.palette_flags.is_null()
+            && *data.palette_flags & FT_PALETTE_FOR_DARK_BACKGROUND != 0
+        {
+            WHITE
+        } else {
+            BLACK
+        };
+
+        let mut colors: *mut FT_Color = std::ptr::null_mut();
+        if FT_Palette_Select(face, 0, &mut colors) != 0 || colors.is_null() {
+            return (Vec::new(), foreground);
+        }
+
+        let colors = std::slice::from_raw_parts(colors, data.num_palette_entries as usize);
+        let palette = colors
+            .iter()
+            .map(|color| {
+                let alpha = color.alpha as f32 / 255.0;
+                [
+                    color.red as f32 / 255.0 * alpha,
+                    color.green as f32 / 255.0 * alpha,
+                    color.blue as f32 / 255.0 * alpha,
+                    alpha,
+                ]
+            })
+            .collect();
+
+        (palette, foreground)
+    }
+}
+
+/// The colors of a gradient along its line.
+struct ColorLine {
+    extend: u32,
+    /// Offsets and colors, sorted by offset.
+    stops: Vec<(f64, Rgba)>,
+}
+
+impl ColorLine {
+    /// The color of the first stop, or the last one if `end`.
+    fn edge(&self, end: bool) -> Option<Rgba> {
+        let stop = if end {
+            self.stops.last()
+        } else {
+            self.stops.first()
+        };
+        stop.map(|stop| stop.1)
+    }
+
+    /// The color at a position on the line.
+    fn at(&self, position: f64) -> Option<Rgba> {
+        let (first, last) = (self.stops.first()?, self.stops.last()?);
+        let (start, end) = (first.0, last.0);
+        let length = end - start;
+
+        if length <= f64::EPSILON {
+            return Some(if position < start { first.1 } else { last.1 });
+        }
+
+        let position = match self.extend {
+            FT_COLR_PAINT_EXTEND_REPEAT => start + (position - start).rem_euclid(length),
+            FT_COLR_PAINT_EXTEND_REFLECT => {
+                let offset = (position - start).rem_euclid(length * 2.0);
+                start
+                    + if offset > length {
+                        length * 2.0 - offset
+                    } else {
+                        offset
+                    }
+            }
+            _ => position.clamp(start, end),
+        };
+
+        let next = self
+            .stops
+            .iter()
+            .position(|stop| stop.0 >= position)
+            .unwrap_or(self.stops.len() - 1);
+        if next == 0 {
+            return Some(first.1);
+        }
+
+        let (from, to) = (self.stops[next - 1], self.stops[next]);
+        let span = to.0 - from.0;
+        let t = if span <= f64::EPSILON {
+            1.0
+        } else {
+            ((position - from.0) / span) as f32
+        };
+        Some(std::array::from_fn(|channel| {
+            from.1[channel] + (to.1[channel] - from.1[channel]) * t
+        }))
+    }
+}
+
+/// The position of a point on a linear gradient from `p0` to `p1`, with the
+/// color bands parallel to `p0`..`p2`.
+fn linear_position(p0: (f64, f64), p1: (f64, f64), p2: (f64, f64), p: (f64, f64)) -> Option<f64> {
+    let (dx, dy) = (p1.0 - p0.0, p1.1 - p0.1);
+    let (nx, ny) = (p2.1 - p0.1, -(p2.0 - p0.0));
+    let normal = nx * nx + ny * ny;
+
+    // project p0..p1 onto the normal of p0..p2 to get the direction of change
+    let (gx, gy) = if normal <= f64::EPSILON {
+        (dx, dy)
+    } else {
+        let scale = (dx * nx + dy * ny) / normal;
+        (nx * scale, ny * scale)
+    };
+
+    let length = gx * gx + gy * gy;
+    (length > f64::EPSILON).then(|| ((p.0 - p0.0) * gx + (p.1 - p0.1) * gy) / length)
+}
+
+/// The largest position of a two point conical gradient whose circle passes
+/// through a point and has a radius of at least zero.
+fn radial_position(c0: (f64, f64), r0: f64, c1: (f64, f64), r1: f64, p: (f64, f64)) -> Option<f64> {
+    let (cx, cy) = (c1.0 - c0.0, c1.1 - c0.1);
+    let (px, py) = (p.0 - c0.0, p.1 - c0.1);
+    let dr = r1 - r0;
+
+    // |p - c(t)| = r(t) as a t² - 2 b t + c = 0
+    let a = cx * cx + cy * cy - dr * dr;
+    let b = px * cx + py * cy + r0 * dr;
+    let c = px * px + py * py - r0 * r0;
+    let radius = |t: f64| r0 + t * dr;
+
+    if a.abs() <= f64::EPSILON {
+        if b.abs() <= f64::EPSILON {
+            return None;
+        }
+        let t = c / (2.0 * b);
+        return (radius(t) >= 0.0).then_some(t);
+    }
+
+    let discriminant = b * b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let root = discriminant.sqrt();
+    let (t1, t2) = ((b + root) / a, (b - root) / a);
+    let (larger, smaller) = (t1.max(t2), t1.min(t2));
+
+    if radius(larger) >= 0.0 {
+        Some(larger)
+    } else if radius(smaller) >= 0.0 {
+        Some(smaller)
+    } else {
+        None
+    }
+}
+
+/// Compose a source over a backdrop, both premultiplied.
+fn composite(mode: FT_Composite_Mode, source: Rgba, backdrop: Rgba) -> Rgba {
+    let (sa, da) = (source[3], backdrop[3]);
+
+    // Porter-Duff operators weigh source and backdrop as a whole
+    let porter_duff = |fs: f32, fd: f32| -> Rgba {
+        std::array::from_fn(|channel| (source[channel] * fs + backdrop[channel] * fd).min(1.0))
+    };
+
+    match mode {
+        FT_COLR_COMPOSITE_CLEAR => TRANSPARENT,
+        FT_COLR_COMPOSITE_SRC => source,
+        FT_COLR_COMPOSITE_DEST => backdrop,
+        FT_COLR_COMPOSITE_SRC_OVER => porter_duff(1.0, 1.0 - sa),
+        FT_COLR_COMPOSITE_DEST_OVER => porter_duff(1.0 - da, 1.0),
+        FT_COLR_COMPOSITE_SRC_IN => porter_duff(da, 0.0),
+        FT_COLR_COMPOSITE_DEST_IN => porter_duff(0.0, sa),
+        FT_COLR_COMPOSITE_SRC_OUT => porter_duff(1.0 - da, 0.0),
+        FT_COLR_COMPOSITE_DEST_OUT => porter_duff(0.0, 1.0 - sa),
+        FT_COLR_COMPOSITE_SRC_ATOP => porter_duff(da, 1.0 - sa),
+        FT_COLR_COMPOSITE_DEST_ATOP => porter_duff(1.0 - da, sa),
+        FT_COLR_COMPOSITE_XOR => porter_duff(1.0 - da, 1.0 - sa),
+        FT_COLR_COMPOSITE_PLUS => porter_duff(1.0, 1.0),
+        _ => blend(mode, source, backdrop),
+    }
+}
+
+/// The blend modes of the W3C compositing spec, on premultiplied colors.
+fn blend(mode: FT_Composite_Mode, source: Rgba, backdrop: Rgba) -> Rgba {
+    let (sa, da) = (source[3], backdrop[3]);
+    let cs = rgb(unpremultiplied(source));
+    let cd = rgb(unpremultiplied(backdrop));
+
+    let separable = |function: fn(f32, f32) -> f32| -> [f32; 3] {
+        std::array::from_fn(|channel| function(cs[channel], cd[channel]))
+    };
+
+    let mixed = match mode {
+        FT_COLR_COMPOSITE_SCREEN => separable(screen),
+        FT_COLR_COMPOSITE_OVERLAY => separable(|s, d| hard_light(d, s)),
+        FT_COLR_COMPOSITE_DARKEN => separable(f32::min),
+        FT_COLR_COMPOSITE_LIGHTEN => separable(f32::max),
+        FT_COLR_COMPOSITE_COLOR_DODGE => separable(color_dodge),
+        FT_COLR_COMPOSITE_COLOR_BURN => separable(color_burn),
+        FT_COLR_COMPOSITE_HARD_LIGHT => separable(hard_light),
+        FT_COLR_COMPOSITE_SOFT_LIGHT => separable(soft_light),
+        FT_COLR_COMPOSITE_DIFFERENCE => separable(|s, d| (s - d).abs()),
+        FT_COLR_COMPOSITE_EXCLUSION => separable(|s, d| s + d - 2.0 * s * d),
+        FT_COLR_COMPOSITE_MULTIPLY => separable(|s, d| s * d),
+        FT_COLR_COMPOSITE_HSL_HUE => {
+            set_luminosity(set_saturation(cs, saturation(cd)), luminosity(cd))
+        }
+        FT_COLR_COMPOSITE_HSL_SATURATION => {
+            set_luminosity(set_saturation(cd, saturation(cs)), luminosity(cd))
+        }
+        FT_COLR_COMPOSITE_HSL_COLOR => set_luminosity(cs, luminosity(cd)),
+        FT_COLR_COMPOSITE_HSL_LUMINOSITY => set_luminosity(cd, luminosity(cs)),
+        // unknown modes draw like source over
+        _ => cs,
+    };
+
+    let alpha = sa + da - sa * da;
+    let mut result = [0.0, 0.0, 0.0, alpha];
+    for channel in 0..3 {
+        result[channel] = (1.0 - da) * source[channel]
+            + (1.0 - sa) * backdrop[channel]
+            + sa * da * mixed[channel];
+    }
+    result
+}
+
+fn screen(s: f32, d: f32) -> f32 {
+    s + d - s * d
+}
+
+fn hard_light(s: f32, d: f32) -> f32 {
+    if s <= 0.5 {
+        d * 2.0 * s
+    } else {
+        screen(d, 2.0 * s - 1.0)
+    }
+}
+
+fn soft_light(s: f32, d: f32) -> f32 {
+    if s <= 0.5 {
+        d - (1.0 - 2.0 * s) * d * (1.0 - d)
+    } else {
+        let darkened = if d <= 0.25 {
+            ((16.0 * d - 12.0) * d + 4.0) * d
+        } else {
+            d.sqrt()
+        };
+        d + (2.0 * s - 1.0) * (darkened - d)
+    }
+}
+
+fn color_dodge(s: f32, d: f32) -> f32 {
+    if d <= 0.0 {
+        0.0
+    } else if s >= 1.0 {
+        1.0
+    } else {
+        (d / (1.0 - s)).min(1.0)
+    }
+}
+
+fn color_burn(s: f32, d: f32) -> f32 {
+    if d >= 1.0 {
+        1.0
+    } else if s <= 0.0 {
+        0.0
+    } else {
+        1.0 - ((1.0 - d) / s).min(1.0)
+    }
+}
+
+fn luminosity(color: [f32; 3]) -> f32 {
+    0.3 * color[0] + 0.59 * color[1] + 0.11 * color[2]
+}
+
+fn set_luminosity(color: [f32; 3], target: f32) -> [f32; 3] {
+    let delta = target - luminosity(color);
+    let color = color.map(|channel| channel + delta);
+
+    // bring the channels back into range keeping the luminosity
+    let l = luminosity(color);
+    let min = color[0].min(color[1]).min(color[2]);
+    let max = color[0].max(color[1]).max(color[2]);
+    color.map(|channel| {
+        let mut channel = channel;
+        if min < 0.0 && l - min > f32::EPSILON {
+            channel = l + (channel - l) * l / (l - min);
+        }
+        if max > 1.0 && max - l > f32::EPSILON {
+            channel = l + (channel - l) * (1.0 - l) / (max - l);
+        }
+        channel
+    })
+}
+
+fn saturation(color: [f32; 3]) -> f32 {
+    color[0].max(color[1]).max(color[2]) - color[0].min(color[1]).min(color[2])
+}
+
+fn set_saturation(color: [f32; 3], target: f32) -> [f32; 3] {
+    let min = color[0].min(color[1]).min(color[2]);
+    let max = color[0].max(color[1]).max(color[2]);
+    if max - min <= f32::EPSILON {
+        return [0.0; 3];
+    }
+    color.map(|channel| (channel - min) * target / (max - min))
+}
+
+fn rgb(color: Rgba) -> [f32; 3] {
+    [color[0], color[1], color[2]]
+}
+
+fn source_over(source: Rgba, backdrop: Rgba) -> Rgba {
+    std::array::from_fn(|channel| source[channel] + backdrop[channel] * (1.0 - source[3]))
+}
+
+fn unpremultiplied(color: Rgba) -> Rgba {
+    if color[3] <= 0.0 {
+        return TRANSPARENT;
+    }
+    [
+        (color[0] / color[3]).min(1.0),
+        (color[1] / color[3]).min(1.0),
+        (color[2] / color[3]).min(1.0),
+        color[3].min(1.0),
+    ]
+}
+
+/// A premultiplied color as straight alpha bytes.
+fn unpremultiply(color: Rgba) -> [u8; 4] {
+    unpremultiplied(color).map(|channel| (channel * 255.0).round().clamp(0.0, 255.0) as u8)
+}
+
+fn fixed(value: FT_Fixed) -> f64 {
+    value as f64 / 65536.0
+}
+
+fn vector(vector: FT_Vector) -> (f64, f64) {
+    (fixed(vector.x), fixed(vector.y))
+}
+
+/// A 2D affine transform, `x' = xx x + xy y + dx` and `y' = yx x + yy y + dy`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Affine {
+    xx: f64,
+    xy: f64,
+    dx: f64,
+    yx: f64,
+    yy: f64,
+    dy: f64,
+}
+
+impl Affine {
+    const IDENTITY: Self = Self {
+        xx: 1.0,
+        xy: 0.0,
+        dx: 0.0,
+        yx: 0.0,
+        yy: 1.0,
+        dy: 0.0,
+    };
+
+    fn scale(x: f64, y: f64) -> Self {
+        Self {
+            xx: x,
+            yy: y,
+            ..Self::IDENTITY
+        }
+    }
+
+    fn translate(x: f64, y: f64) -> Self {
+        Self {
+            dx: x,
+            dy: y,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// The transform applied with `(x, y)` as its fixed point.
+    fn around(self, x: f64, y: f64) -> Self {
+        Self::translate(x, y)
+            .then(self)
+            .then(Self::translate(-x, -y))
+    }
+
+    /// `inner` followed by `self`.
+    fn then(self, inner: Self) -> Self {
+        Self {
+            xx: self.xx * inner.xx + self.xy * inner.yx,
+            xy: self.xx * inner.xy + self.xy * inner.yy,
+            dx: self.xx * inner.dx + self.xy * inner.dy + self.dx,
+            yx: self.yx * inner.xx + self.yy * inner.yx,
+            yy: self.yx * inner.xy + self.yy * inner.yy,
+            dy: self.yx * inner.dx + self.yy * inner.dy + self.dy,
+        }
+    }
+
+    fn invert(self) -> Option<Self> {
+        let determinant = self.xx * self.yy - self.xy * self.yx;
+        if determinant.abs() <= f64::EPSILON {
+            return None;
+        }
+
+        let (xx, xy) = (self.yy / determinant, -self.xy / determinant);
+        let (yx, yy) = (-self.yx / determinant, self.xx / determinant);
+        Some(Self {
+            xx,
+            xy,
+            dx: -(xx * self.dx + xy * self.dy),
+            yx,
+            yy,
+            dy: -(yx * self.dx + yy * self.dy),
+        })
+    }
+
+    fn apply(self, x: f64, y: f64) -> (f64, f64) {
+        (
+            self.xx * x + self.xy * y + self.dx,
+            self.yx * x + self.yy * y + self.dy,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_font::{BLANK, SPACE, SQUARE, TestFont, longs, words};
+
+    /// The test font with two COLRv1 glyphs: the square filled with solid red
+    /// and [`BLANK`] filling the square with a red to blue gradient from left
+    /// to right.
+    fn font() -> TestFont {
+        let mut colr = Vec::new();
+        words(&mut colr, &[1, 0]);
+        longs(&mut colr, &[0, 0]);
+        words(&mut colr, &[0]);
+        longs(&mut colr, &[34, 0, 0, 0, 0]);
+        // BaseGlyphList, paint offsets are from its start
+        longs(&mut colr, &[2]);
+        words(&mut colr, &[SQUARE as i32, 0, 16, BLANK as i32, 0, 27]);
+        // PaintGlyph(square) -> PaintSolid(red)
+        colr.extend([10, 0, 0, 6]);
+        words(&mut colr, &[SQUARE as i32]);
+        colr.push(2);
+        words(&mut colr, &[0, 0x4000]);
+        // PaintGlyph(square) -> PaintLinearGradient from x 0 to 1000
+        colr.extend([10, 0, 0, 6]);
+        words(&mut colr, &[SQUARE as i32]);
+        colr.extend([4, 0, 0, 16]);
+        words(&mut colr, &[0, 0, 1000, 0, 0, 1000]);
+        colr.push(0);
+        words(&mut colr, &[2, 0, 0, 0x4000, 0x4000, 1, 0x4000]);
+
+        let mut cpal = Vec::new();
+        words(&mut cpal, &[0, 2, 1, 2]);
+        longs(&mut cpal, &[14]);
+        words(&mut cpal, &[0]);
+        // BGRA red and blue
+        cpal.extend([0, 0, 255, 255, 255, 0, 0, 255]);
+
+        TestFont::new()
+            .with_table(b"COLR", colr)
+            .with_table(b"CPAL", cpal)
+    }
+
+    fn render(glyph_id: u32) -> Result<Option<ColorImage>, TextError> {
+        let face = font().face()?;
+        assert!(face.has_color());
+        face.set_pixel_size(10)?;
+        Ok(face.render_colr_glyph(glyph_id, 0.0))
+    }
+
+    #[test]
+    fn fills_glyph_with_solid_paint() -> Result<(), TextError> {
+        let image = render(SQUARE)?;
+        let size = image
+            .as_ref()
+            .map(|image| (image.width, image.height, image.bearing));
+        assert_eq!(size, Some((10, 10, Point::new(0, -8))));
+
+        let data = image.map(|image| image.data).unwrap_or_default();
+        assert!(
+            data.as_chunks::<4>()
+                .0
+                .iter()
+                .all(|&pixel| pixel == [255, 0, 0, 255])
+        );
+
+        // glyphs without a base glyph record have no paint graph
+        assert_eq!(render(SPACE)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn fills_glyph_with_linear_gradient() -> Result<(), TextError> {
+        let data = render(BLANK)?.map(|image| image.data).unwrap_or_default();
+        assert_eq!(data.len(), 10 * 10 * 4);
+
+        let pixel = |x: usize, y: usize| &data[(y * 10 + x) * 4..(y * 10 + x) * 4 + 4];
+        // pixel centers sit at 5% and 95% of the way
+        assert_eq!(pixel(0, 0), [242, 0, 13, 255]);
+        assert_eq!(pixel(9, 9), [13, 0, 242, 255]);
+        assert_eq!(pixel(4, 0), pixel(4, 9));
+        Ok(())
+    }
+
+    #[test]
+    fn extends_color_lines() {
+        let red = [1.0, 0.0, 0.0, 1.0];
+        let blue = [0.0, 0.0, 1.0, 1.0];
+        let line = |extend| ColorLine {
+            extend,
+            stops: vec![(0.0, red), (1.0, blue)],
+        };
+
+        let pad = line(0);
+        assert_eq!(pad.at(-0.5), Some(red));
+        assert_eq!(pad.at(1.5), Some(blue));
+        assert_eq!(pad.at(0.5), Some([0.5, 0.0, 0.5, 1.0]));
+
+        assert_eq!(
+            line(FT_COLR_PAINT_EXTEND_REPEAT).at(1.25),
+            Some([0.75, 0.0, 0.25, 1.0])
+        );
+        assert_eq!(
+            line(FT_COLR_PAINT_EXTEND_REFLECT).at(1.25),
+            Some([0.25, 0.0, 0.75, 1.0])
+        );
+
+        let empty = ColorLine {
+            extend: 0,
+            stops: Vec::new(),
+        };
+        assert_eq!(empty.at(0.5), None);
+    }
+
+    #[test]
+    fn positions_on_gradients() {
+        let linear = |p2, p| linear_position((0.0, 0.0), (10.0, 0.0), p2, p);
+        assert_eq!(linear((0.0, 10.0), (5.0, 3.0)), Some(0.5));
+        // slanted color bands move the end of the gradient onto their normal
+        assert_eq!(linear((10.0, 10.0), (5.0, -5.0)), Some(1.0));
+        assert_eq!(linear((0.0, 10.0), (-5.0, 0.0)), Some(-0.5));
+
+        let radial = |p| radial_position((0.0, 0.0), 0.0, (0.0, 0.0), 10.0, p);
+        assert_eq!(radial((5.0, 0.0)), Some(0.5));
+        assert_eq!(radial((0.0, 20.0)), Some(2.0));
+        // the circles of a cone towards a point never reach behind its tip
+        assert_eq!(
+            radial_position((0.0, 0.0), 0.0, (10.0, 0.0), 0.0, (0.0, 5.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn composes_layers() {
+        let red = [1.0, 0.0, 0.0, 1.0];
+        let white = [1.0; 4];
+        let half = [0.0, 0.0, 0.5, 0.5];
+
+        assert_eq!(
+            composite(FT_COLR_COMPOSITE_SRC_IN, red, half),
+            [0.5, 0.0, 0.0, 0.5]
+        );
+        assert_eq!(
+            composite(FT_COLR_COMPOSITE_DEST_OUT, half, red),
+            [0.5, 0.0, 0.0, 0.5]
+        );
+        assert_eq!(composite(FT_COLR_COMPOSITE_MULTIPLY, red, white), red);
+        assert_eq!(
+            composite(FT_COLR_COMPOSITE_SCREEN, red, [0.0, 0.0, 0.0, 1.0]),
+            red
+        );
+        assert_eq!(source_over(half, red), [0.5, 0.0, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn composes_affine_transforms() {
+        let turn = Affine {
+            xx: 0.0,
+            xy: -1.0,
+            yx: 1.0,
+            yy: 0.0,
+            ..Affine::IDENTITY
+        }
+        .around(1.0, 1.0);
+        assert_eq!(turn.apply(2.0, 1.0), (1.0, 2.0));
+
+        let inverse = turn.invert().map(|inverse| inverse.apply(1.0, 2.0));
+        assert_eq!(inverse, Some((2.0, 1.0)));
+        assert_eq!(Affine::scale(0.0, 1.0).invert(), None);
+    }
+}
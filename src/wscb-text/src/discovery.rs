@@ -0,0 +1,611 @@
+//! Finding fonts in directories.
+//!
+//! A [`FontIndex`] scans directories once, reads the names, style and
+//! character coverage of every face and then answers queries without touching
+//! the files again. Only the chosen faces are loaded.
+
+use ::std::{
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
+
+use ::wscb_type::HashSet;
+
+use crate::{
+    error::TextError,
+    ffi::freetype::{FT_Get_First_Char, FT_Get_Next_Char, FT_UInt},
+    font::{Face, Library},
+};
+
+/// The file extensions scanned for fonts, compared without case.
+pub const FONT_EXTENSIONS: &[&str] = &["ttf", "otf", "ttc", "otc"];
+
+/// The characters a face has glyphs for, as sorted ranges of code points.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Coverage {
+    ranges: Vec<RangeInclusive<u32>>,
+}
+
+impl Coverage {
+    /// Build the coverage from characters in ascending order.
+    pub fn from_sorted(chars: impl IntoIterator<Item = char>) -> Self {
+        let mut ranges: Vec<RangeInclusive<u32>> = Vec::new();
+
+        for ch in chars {
+            let code = ch as u32;
+            match ranges.last_mut() {
+                Some(last) if *last.end() + 1 == code => *last = *last.start()..=code,
+                Some(last) if last.contains(&code) => {}
+                _ => ranges.push(code..=code),
+            }
+        }
+
+        Self { ranges }
+    }
+
+    /// The characters of the character map of a face.
+    pub fn of_face(face: &Face) -> Self {
+        let mut glyph: FT_UInt = 0;
+        let mut code = unsafe { FT_Get_First_Char(face.get_pointer(), &mut glyph) };
+
+        // FreeType walks the character map in ascending order
+        let chars = std::iter::from_fn(|| {
+            while glyph != 0 {
+                let current = code;
+                code = unsafe { FT_Get_Next_Char(face.get_pointer(), code, &mut glyph) };
+                if let Some(ch) = u32::try_from(current).ok().and_then(char::from_u32) {
+                    return Some(ch);
+                }
+            }
+            None
+        });
+
+        Self::from_sorted(chars)
+    }
+
+    pub fn contains(&self, ch: char) -> bool {
+        let code = ch as u32;
+        let index = self.ranges.partition_point(|range| *range.end() < code);
+        self.ranges
+            .get(index)
+            .is_some_and(|range| range.contains(&code))
+    }
+
+    /// Whether every character of the text is covered.
+    ///
+    /// Control characters and whitespace are not drawn, they are skipped.
+    pub fn covers(&self, text: &str) -> bool {
+        text.chars()
+            .filter(|ch| !ch.is_control() && !ch.is_whitespace())
+            .all(|ch| self.contains(ch))
+    }
+
+    /// The number of covered characters.
+    pub fn len(&self) -> usize {
+        self.ranges
+            .iter()
+            .map(|range| (range.end() - range.start()) as usize + 1)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn ranges(&self) -> &[RangeInclusive<u32>] {
+        &self.ranges
+    }
+}
+
+/// A face found by a [`FontIndex`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontEntry {
+    pub path: PathBuf,
+    /// The index of the face inside the file, for collections.
+    pub face_index: u32,
+    pub family: String,
+    pub style: String,
+    /// The weight class, `400` is regular and `700` bold.
+    pub weight: u16,
+    /// The width class, `5` is normal and smaller is narrower.
+    pub width: u16,
+    pub italic: bool,
+    pub monospace: bool,
+    pub color: bool,
+    pub coverage: Coverage,
+}
+
+impl FontEntry {
+    /// Describe a loaded face.
+    pub fn from_face(face: &Face, path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            face_index: face.face_index(),
+            family: face.family_name().unwrap_or_default(),
+            style: face.style_name().unwrap_or_default(),
+            weight: face.weight(),
+            width: face.width_class(),
+            italic: face.is_italic(),
+            monospace: face.is_monospace(),
+            color: face.has_color(),
+            coverage: Coverage::of_face(face),
+        }
+    }
+
+    /// Load the face this entry describes.
+    pub fn load(&self, library: &Library) -> Result<Face, TextError> {
+        library.load_face_from_file(&self.path, self.face_index)
+    }
+}
+
+/// What a [`FontIndex::query`] looks for.
+///
+/// Styles are matched like CSS does: the closest width comes first, then
+/// the italic style when asked for, then the closest weight.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FontQuery {
+    /// The family name, compared without case. `None` accepts any family.
+    pub family: Option<String>,
+    pub weight: u16,
+    /// The width class, `5` is normal.
+    pub width: u16,
+    pub italic: bool,
+    /// Text the face must have glyphs for.
+    pub text: String,
+}
+
+impl Default for FontQuery {
+    fn default() -> Self {
+        Self {
+            family: None,
+            weight: 400,
+            width: 5,
+            italic: false,
+            text: String::new(),
+        }
+    }
+}
+
+impl FontQuery {
+    pub fn family(family: impl Into<String>) -> Self {
+        Self {
+            family: Some(family.into()),
+            ..Default::default()
+        }
+    }
+
+    #[must_use]
+    pub fn bold(self) -> Self {
+        Self {
+            weight: 700,
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn italic(self) -> Self {
+        Self {
+            italic: true,
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn with_weight(self, weight: u16) -> Self {
+        Self { weight, ..self }
+    }
+
+    #[must_use]
+    pub fn with_width(self, width: u16) -> Self {
+        Self { width, ..self }
+    }
+
+    #[must_use]
+    pub fn covering(self, text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..self
+        }
+    }
+
+    fn accepts(&self, entry: &FontEntry) -> bool {
+        self.family
+            .as_ref()
+            .is_none_or(|family| entry.family.eq_ignore_ascii_case(family))
+            && entry.coverage.covers(&self.text)
+    }
+
+    /// The sort key of an accepted entry, smaller is better.
+    fn rank(&self, entry: &FontEntry) -> (u8, u16, bool, u8, u16) {
+        let (width_tier, width_distance) = width_preference(self.width, entry.width);
+        let (tier, distance) = weight_preference(self.weight, entry.weight);
+        (
+            width_tier,
+            width_distance,
+            entry.italic != self.italic,
+            tier,
+            distance,
+        )
+    }
+}
+
+/// How well an available weight serves a desired one, smaller is better.
+///
+/// This is the CSS font matching order: weights between 400 and 500 first
+/// look up to 500, then down, then above 500; lighter weights look down
+/// first and bolder weights look up first.
+fn weight_preference(desired: u16, available: u16) -> (u8, u16) {
+    let distance = desired.abs_diff(available);
+
+    match desired {
+        400..=500 if (desired..=500).contains(&available) => (0, distance),
+        400..=500 if available < desired => (1, distance),
+        400..=500 => (2, distance),
+        _ if available == desired => (0, 0),
+        0..400 if available < desired => (0, distance),
+        0..400 => (1, distance),
+        _ if available > desired => (0, distance),
+        _ => (1, distance),
+    }
+}
+
+/// How well an available width serves a desired one, smaller is better.
+///
+/// Normal and narrower widths look for narrower faces first, wider ones for
+/// wider faces first.
+fn width_preference(desired: u16, available: u16) -> (u8, u16) {
+    let distance = desired.abs_diff(available);
+    let preferred_side = if desired <= 5 {
+        available <= desired
+    } else {
+        available >= desired
+    };
+    (u8::from(!preferred_side), distance)
+}
+
+/// A file that could not be indexed.
+#[derive(Debug)]
+pub struct ScanFailure {
+    pub path: PathBuf,
+    pub error: TextError,
+}
+
+impl ScanFailure {
+    fn io(path: PathBuf, source: ::std::io::Error) -> Self {
+        Self {
+            error: TextError::Io {
+                path: path.clone(),
+                source,
+            },
+            path,
+        }
+    }
+}
+
+/// The faces found in a set of directories.
+///
+/// Directories added later take priority on equal matches, so mod folders
+/// scanned after the game data can replace its fonts.
+#[derive(Debug, Default)]
+pub struct FontIndex {
+    entries: Vec<FontEntry>,
+}
+
+impl FontIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn entries(&self) -> &[FontEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Add an entry found some other way, like a font bundled in memory.
+    pub fn push(&mut self, entry: FontEntry) {
+        self.entries.push(entry);
+    }
+
+    /// Index every font file under a directory, subdirectories included.
+    ///
+    /// Files are visited in name order. Files that are not valid fonts and
+    /// directories that can not be read are skipped and returned. Symbolic
+    /// links are followed, but every directory is scanned once, so links
+    /// back up the tree do not loop.
+    pub fn add_dir(&mut self, library: &Library, dir: impl AsRef<Path>) -> Vec<ScanFailure> {
+        let mut failures = Vec::new();
+        let mut pending = vec![dir.as_ref().to_path_buf()];
+        let mut visited = HashSet::default();
+
+        while let Some(dir) = pending.pop() {
+            match std::fs::canonicalize(&dir) {
+                Ok(canonical) => {
+                    if !visited.insert(canonical) {
+                        continue;
+                    }
+                }
+                Err(source) => {
+                    failures.push(ScanFailure::io(dir, source));
+                    continue;
+                }
+            }
+
+            let children = std::fs::read_dir(&dir).and_then(|entries| {
+                entries
+                    .map(|entry| entry.map(|entry| entry.path()))
+                    .collect::<Result<Vec<_>, _>>()
+            });
+            let mut children = match children {
+                Ok(children) => children,
+                Err(source) => {
+                    failures.push(ScanFailure::io(dir, source));
+                    continue;
+                }
+            };
+            children.sort();
+
+            let mut subdirs = Vec::new();
+            for path in children {
+                if path.is_dir() {
+                    subdirs.push(path);
+                } else if is_font_file(&path)
+                    && let Err(error) = self.add_file(library, &path)
+                {
+                    failures.push(ScanFailure { path, error });
+                }
+            }
+            // visited in name order from the stack
+            pending.extend(subdirs.into_iter().rev());
+        }
+
+        failures
+    }
+
+    /// Index every face of a font file, all faces of a collection.
+    ///
+    /// Returns the number of faces added. Nothing is added when one face of
+    /// a collection fails to load.
+    pub fn add_file(
+        &mut self,
+        library: &Library,
+        path: impl AsRef<Path>,
+    ) -> Result<usize, TextError> {
+        let path = path.as_ref();
+        let data: std::rc::Rc<[u8]> = std::fs::read(path)
+            .map_err(|source| TextError::Io {
+                path: path.to_path_buf(),
+                source,
+            })?
+            .into();
+
+        let first = library.load_face_from_memory(data.clone(), 0)?;
+        let mut entries = vec![FontEntry::from_face(&first, path)];
+
+        for index in 1..first.num_faces() {
+            let face = library.load_face_from_memory(data.clone(), index)?;
+            entries.push(FontEntry::from_face(&face, path));
+        }
+
+        let count = entries.len();
+        self.entries.extend(entries);
+        Ok(count)
+    }
+
+    /// The names of the indexed families, sorted and without duplicates.
+    pub fn families(&self) -> Vec<&str> {
+        let mut families: Vec<&str> = self
+            .entries
+            .iter()
+            .map(|entry| entry.family.as_str())
+            .collect();
+        families.sort_unstable();
+        families.dedup();
+        families
+    }
+
+    /// The best face for the query.
+    pub fn query(&self, query: &FontQuery) -> Option<&FontEntry> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| query.accepts(entry))
+            // on a tie the later entry wins
+            .min_by_key(|(index, entry)| (query.rank(entry), usize::MAX - index))
+            .map(|(_, entry)| entry)
+    }
+
+    /// Every face accepted by the query, best first.
+    pub fn query_all(&self, query: &FontQuery) -> Vec<&FontEntry> {
+        let mut found: Vec<(usize, &FontEntry)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| query.accepts(entry))
+            .collect();
+        found.sort_by_key(|(index, entry)| (query.rank(entry), usize::MAX - index));
+        found.into_iter().map(|(_, entry)| entry).collect()
+    }
+}
+
+fn is_font_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            FONT_EXTENSIONS
+                .iter()
+                .any(|known| extension.eq_ignore_ascii_case(known))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_font::{TestFont, longs};
+
+    fn entry(family: &str, weight: u16, italic: bool, chars: &str) -> FontEntry {
+        let mut chars: Vec<char> = chars.chars().collect();
+        chars.sort_unstable();
+        FontEntry {
+            path: PathBuf::from(format!("{family}-{weight}.ttf")),
+            face_index: 0,
+            family: family.to_string(),
+            style: String::new(),
+            weight,
+            width: 5,
+            italic,
+            monospace: false,
+            color: false,
+            coverage: Coverage::from_sorted(chars),
+        }
+    }
+
+    #[test]
+    fn coverage_merges_ranges() {
+        let coverage = Coverage::from_sorted("abcxyz".chars());
+        assert_eq!(coverage.ranges(), [97..=99, 120..=122]);
+        assert_eq!(coverage.len(), 6);
+        assert!(coverage.contains('b') && !coverage.contains('d'));
+        assert!(coverage.covers("cab\nzyx "));
+        assert!(!coverage.covers("abd"));
+    }
+
+    #[test]
+    fn queries_match_style_and_coverage() {
+        let mut index = FontIndex::new();
+        index.push(entry("Sans", 400, false, "abc"));
+        index.push(entry("Sans", 700, false, "abc"));
+        index.push(entry("Sans", 300, true, "abc"));
+        index.push(entry("Serif", 400, false, "abcé"));
+
+        let found = |query: FontQuery| {
+            index
+                .query(&query)
+                .map(|entry| (entry.family.as_str(), entry.weight))
+        };
+
+        assert_eq!(found(FontQuery::family("sans").bold()), Some(("Sans", 700)));
+        assert_eq!(
+            found(FontQuery::family("Sans").with_weight(500)),
+            Some(("Sans", 400))
+        );
+        assert_eq!(
+            found(FontQuery::family("Sans").italic()),
+            Some(("Sans", 300))
+        );
+        assert_eq!(
+            found(FontQuery::default().covering("é")),
+            Some(("Serif", 400))
+        );
+        assert_eq!(found(FontQuery::family("Sans").covering("é")), None);
+
+        // later entries replace earlier ones
+        index.push(entry("Sans", 700, false, "abc"));
+        let best = index.query(&FontQuery::family("Sans").bold());
+        assert!(std::ptr::eq(
+            best.unwrap_or(&index.entries()[0]),
+            &index.entries()[4]
+        ));
+    }
+
+    #[test]
+    fn weights_follow_css_order() {
+        let pick = |desired: u16| {
+            [300, 400, 600, 900]
+                .into_iter()
+                .min_by_key(|&weight| weight_preference(desired, weight))
+        };
+        assert_eq!(pick(500), Some(400));
+        assert_eq!(pick(700), Some(900));
+        assert_eq!(pick(350), Some(300));
+        assert_eq!(pick(200), Some(300));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn follows_symlinks_once() -> Result<(), TextError> {
+        let dir = std::env::temp_dir().join(format!("wscb-discovery-{}", std::process::id()));
+        let io_error = |source| TextError::Io {
+            path: dir.clone(),
+            source,
+        };
+        std::fs::create_dir_all(dir.join("fonts")).map_err(io_error)?;
+        std::fs::write(dir.join("fonts/test.ttf"), TestFont::new().build()).map_err(io_error)?;
+        // a link back up the tree and a second way into the same directory
+        std::os::unix::fs::symlink("..", dir.join("fonts/up")).map_err(io_error)?;
+        std::os::unix::fs::symlink("fonts", dir.join("more")).map_err(io_error)?;
+
+        let mut index = FontIndex::new();
+        let scanned = index.add_dir(&Library::new()?, &dir);
+        std::fs::remove_dir_all(&dir).map_err(io_error)?;
+
+        assert_eq!(scanned.len(), 0);
+        assert_eq!(index.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn adds_collections_whole_or_not_at_all() -> Result<(), TextError> {
+        // a collection of the test font and a face past the end of the file
+        let font = TestFont::new().build();
+        let mut collection = Vec::new();
+        collection.extend(b"ttcf");
+        longs(&mut collection, &[0x0001_0000, 2, 20, 0x00FF_FFFF]);
+        collection.extend(&font[..12]);
+        for record in font[12..].chunks(16).take(usize::from(font[5])) {
+            collection.extend(&record[..8]);
+            let offset = u32::from_be_bytes([record[8], record[9], record[10], record[11]]);
+            longs(&mut collection, &[offset + 20]);
+            collection.extend(&record[12..]);
+        }
+        collection.extend(&font[12 + 16 * usize::from(font[5])..]);
+
+        let dir = std::env::temp_dir().join(format!("wscb-collection-{}", std::process::id()));
+        let io_error = |source| TextError::Io {
+            path: dir.clone(),
+            source,
+        };
+        std::fs::create_dir_all(&dir).map_err(io_error)?;
+        std::fs::write(dir.join("broken.ttc"), collection).map_err(io_error)?;
+        std::fs::write(dir.join("test.ttf"), font).map_err(io_error)?;
+
+        let library = Library::new()?;
+        let mut index = FontIndex::new();
+        // the first face is fine, the second one fails
+        let first = library.load_face_from_file(dir.join("broken.ttc"), 0);
+        let added = index.add_file(&library, dir.join("broken.ttc"));
+        let failures = index.add_dir(&library, &dir);
+        std::fs::remove_dir_all(&dir).map_err(io_error)?;
+
+        assert_eq!(first?.num_faces(), 2);
+        assert!(added.is_err());
+        assert_eq!(index.len(), 1);
+        assert_eq!(
+            failures
+                .iter()
+                .map(|failure| failure.path.clone())
+                .collect::<Vec<_>>(),
+            [dir.join("broken.ttc")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn reports_unreadable_dirs() -> Result<(), TextError> {
+        let dir = std::env::temp_dir().join(format!("wscb-missing-{}", std::process::id()));
+        let mut index = FontIndex::new();
+        let failures = index.add_dir(&Library::new()?, &dir);
+
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].path, dir);
+        assert!(matches!(failures[0].error, TextError::Io { .. }));
+        assert!(index.is_empty());
+        Ok(())
+    }
+}
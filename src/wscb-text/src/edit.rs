@@ -0,0 +1,805 @@
+//! Editable text, for text boxes like name entry, chat and the console.
+//!
+//! A [`TextBuffer`] holds the text, a caret and a selection, the undo history
+//! and the composition of an input method. Carets always stand on grapheme
+//! boundaries, so a user perceived character is never split. Motions that
+//! depend on how the text is shown, like moving to the line above, take the
+//! [`Layout`] of [`TextBuffer::display_text`].
+
+use ::std::{borrow::Cow, ops::Range};
+
+use ::wscb_sdl::clipboard;
+use ::wscb_type::{graph, graph_f};
+
+use crate::{
+    error::TextError,
+    hit::Affinity,
+    layout::Layout,
+    rich::{AttributedString, TextStyle},
+    segment::{
+        self, grapheme_floor, is_line_separator, next_grapheme_boundary, next_word_end,
+        previous_grapheme_boundary, previous_word_start,
+    },
+};
+
+/// The number of edits [`TextBuffer::undo`] can go back.
+pub const UNDO_LIMIT: usize = 100;
+
+/// A caret motion that does not depend on the layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Motion {
+    PreviousGrapheme,
+    NextGrapheme,
+    /// To the start of the word before the caret.
+    PreviousWord,
+    /// To the end of the word after the caret.
+    NextWord,
+    Start,
+    End,
+}
+
+/// Text being composed by an input method, not part of the text yet.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Preedit {
+    pub text: String,
+    /// The byte range of the clause the input method is working on, if it said so.
+    pub cursor: Option<Range<usize>>,
+}
+
+/// How an edit was made, consecutive edits of one kind are undone together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EditKind {
+    Typing,
+    Backspace,
+    Delete,
+    Other,
+}
+
+/// A replacement in the text with the selections around it.
+#[derive(Debug, Clone)]
+struct Edit {
+    start: usize,
+    removed: String,
+    inserted: String,
+    /// The anchor and caret before and after the edit.
+    before: (usize, usize),
+    after: (usize, usize),
+    kind: EditKind,
+}
+
+impl Edit {
+    /// Merge the next edit into this one, if both belong to one undo step.
+    ///
+    /// Typing is grouped by word, deleting by the run of key presses.
+    fn absorb(&mut self, next: &Self) -> bool {
+        if self.kind != next.kind {
+            return false;
+        }
+
+        let merged = match next.kind {
+            EditKind::Typing => {
+                let new_word = next.inserted.starts_with(char::is_whitespace)
+                    && !self.inserted.ends_with(char::is_whitespace);
+                if next.removed.is_empty()
+                    && self.start + self.inserted.len() == next.start
+                    && !new_word
+                {
+                    self.inserted.push_str(&next.inserted);
+                    true
+                } else {
+                    false
+                }
+            }
+            EditKind::Backspace => {
+                if self.inserted.is_empty() && next.start + next.removed.len() == self.start {
+                    self.start = next.start;
+                    self.removed.insert_str(0, &next.removed);
+                    true
+                } else {
+                    false
+                }
+            }
+            EditKind::Delete => {
+                if self.inserted.is_empty() && next.start == self.start {
+                    self.removed.push_str(&next.removed);
+                    true
+                } else {
+                    false
+                }
+            }
+            EditKind::Other => false,
+        };
+
+        if merged {
+            self.after = next.after;
+        }
+        merged
+    }
+}
+
+/// Turn line separators into spaces, a CR LF pair into one space.
+fn to_single_line(text: &str) -> Cow<'_, str> {
+    if !text.contains(is_line_separator) {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(text.replace("\r\n", " ").replace(is_line_separator, " "))
+}
+
+/// A text being edited.
+#[derive(Debug, Clone, Default)]
+pub struct TextBuffer {
+    text: String,
+    /// The byte offset of the caret, the moving end of the selection.
+    caret: usize,
+    /// The fixed end of the selection, the caret when nothing is selected.
+    anchor: usize,
+    /// Which side of a line break or bidi boundary the caret is drawn on.
+    affinity: Affinity,
+    preedit: Option<Preedit>,
+    single_line: bool,
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    /// Whether the next edit may join the last undo step.
+    merge: bool,
+    /// The position along the line that moving between lines keeps to.
+    goal: Option<f32>,
+}
+
+impl TextBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A buffer with some text and the caret at its end.
+    pub fn with_text(text: &str) -> Self {
+        let mut buffer = Self::new();
+        buffer.set_text(text);
+        buffer
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replace the whole text, move the caret to its end and forget the undo history.
+    pub fn set_text(&mut self, text: &str) {
+        self.text = self.normalize(text).into_owned();
+        self.caret = self.text.len();
+        self.anchor = self.caret;
+        self.preedit = None;
+        self.undo.clear();
+        self.redo.clear();
+        self.merge = false;
+        self.goal = None;
+    }
+
+    pub fn is_single_line(&self) -> bool {
+        self.single_line
+    }
+
+    /// Keep the text on one line, line separators typed or pasted become spaces.
+    pub fn set_single_line(&mut self, single_line: bool) {
+        self.single_line = single_line;
+        if single_line {
+            let text = to_single_line(&self.text).into_owned();
+            if text != self.text {
+                self.replace(0..self.text.len(), &text, EditKind::Other);
+            }
+        }
+    }
+
+    fn normalize<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if self.single_line {
+            to_single_line(text)
+        } else {
+            Cow::Borrowed(text)
+        }
+    }
+
+    /// The byte offset of the caret.
+    pub fn caret(&self) -> usize {
+        self.caret
+    }
+
+    /// Where the caret is drawn when its offset can be shown at two places.
+    pub fn affinity(&self) -> Affinity {
+        self.affinity
+    }
+
+    /// The byte offset of the fixed end of the selection.
+    pub fn anchor(&self) -> usize {
+        self.anchor
+    }
+
+    /// The selected byte range, empty at the caret when nothing is selected.
+    pub fn selection(&self) -> Range<usize> {
+        self.caret.min(self.anchor)..self.caret.max(self.anchor)
+    }
+
+    pub fn has_selection(&self) -> bool {
+        self.caret != self.anchor
+    }
+
+    pub fn selected_text(&self) -> &str {
+        &self.text[self.selection()]
+    }
+
+    /// Move the caret to the grapheme boundary at or before a byte offset.
+    ///
+    /// With `select` the anchor stays and the selection grows or shrinks,
+    /// otherwise the selection is dropped.
+    pub fn set_caret(&mut self, offset: usize, select: bool) {
+        self.caret = grapheme_floor(&self.text, offset);
+        if !select {
+            self.anchor = self.caret;
+        }
+        self.affinity = Affinity::Downstream;
+        self.merge = false;
+        self.goal = None;
+    }
+
+    /// Select a byte range, the caret goes to its end.
+    pub fn select(&mut self, range: Range<usize>) {
+        self.set_caret(range.start, false);
+        self.set_caret(range.end, true);
+    }
+
+    pub fn select_all(&mut self) {
+        self.select(0..self.text.len());
+    }
+
+    /// Select the word, or the run of spaces or punctuation, at a byte offset.
+    pub fn select_word(&mut self, offset: usize) {
+        if let Some(word) = segment::word_at(&self.text, offset) {
+            self.select(word.range);
+        }
+    }
+
+    /// Move the caret.
+    ///
+    /// Without `select` a selection collapses to its start or end when moving
+    /// by graphemes, instead of moving the caret.
+    pub fn move_caret(&mut self, motion: Motion, select: bool) {
+        let selection = self.selection();
+        let offset = match motion {
+            Motion::PreviousGrapheme if !select && !selection.is_empty() => selection.start,
+            Motion::NextGrapheme if !select && !selection.is_empty() => selection.end,
+            Motion::PreviousGrapheme => previous_grapheme_boundary(&self.text, self.caret),
+            Motion::NextGrapheme => next_grapheme_boundary(&self.text, self.caret),
+            Motion::PreviousWord => previous_word_start(&self.text, self.caret),
+            Motion::NextWord => next_word_end(&self.text, self.caret),
+            Motion::Start => 0,
+            Motion::End => self.text.len(),
+        };
+        self.set_caret(offset, select);
+    }
+
+    /// Move the caret one grapheme to the left or right on screen.
+    ///
+    /// In bidi text this follows the visual order of the line, at the end of a
+    /// line it goes on to the next or previous line.
+    pub fn move_visually(&mut self, layout: &Layout, right: bool, select: bool) {
+        if self.preedit.is_some() {
+            return;
+        }
+        if !select && self.has_selection() {
+            let selection = self.selection();
+            let rtl = self.line_level(layout) % 2 == 1;
+            self.set_caret(
+                if right != rtl {
+                    selection.end
+                } else {
+                    selection.start
+                },
+                false,
+            );
+            return;
+        }
+
+        let mut offset = self.caret;
+        loop {
+            match layout.move_visually(offset, right) {
+                Some(next) => {
+                    offset = next;
+                    if segment::is_grapheme_boundary(&self.text, offset) {
+                        break;
+                    }
+                }
+                None => {
+                    let forward = right != (self.line_level(layout) % 2 == 1);
+                    offset = if forward {
+                        next_grapheme_boundary(&self.text, self.caret)
+                    } else {
+                        previous_grapheme_boundary(&self.text, self.caret)
+                    };
+                    break;
+                }
+            }
+        }
+        self.set_caret(offset, select);
+    }
+
+    /// The bidi level of the paragraph of the line with the caret.
+    fn line_level(&self, layout: &Layout) -> u8 {
+        layout
+            .line_at_caret(self.caret, self.affinity)
+            .map_or(0, |index| layout.lines()[index].level)
+    }
+
+    /// Move the caret to the line after or before the one it is on.
+    ///
+    /// The caret keeps its position along the lines while it moves through
+    /// shorter lines. From the first or last line it goes to the start or end
+    /// of the text.
+    pub fn move_line(&mut self, layout: &Layout, next: bool, select: bool) {
+        if self.preedit.is_some() {
+            return;
+        }
+        let vertical = layout.writing_mode().is_vertical();
+        let Some((index, caret)) = layout
+            .line_at_caret(self.caret, self.affinity)
+            .zip(layout.caret_rect(self.caret, self.affinity))
+        else {
+            return;
+        };
+        let goal = self.goal.unwrap_or(if vertical {
+            caret.position.y
+        } else {
+            caret.position.x
+        });
+
+        let target = if next {
+            Some(index + 1).filter(|&target| target < layout.lines().len())
+        } else {
+            index.checked_sub(1)
+        };
+        let Some(target) = target else {
+            self.set_caret(if next { self.text.len() } else { 0 }, select);
+            return;
+        };
+
+        let rect = layout.lines()[target].rect;
+        let point = if vertical {
+            graph_f::Point::new(rect.position.x + rect.size.width / 2.0, goal)
+        } else {
+            graph_f::Point::new(goal, rect.position.y + rect.size.height / 2.0)
+        };
+        self.move_to_point(layout, point, select);
+        self.goal = Some(goal);
+    }
+
+    /// Move the caret to the start or end of the line it is on.
+    ///
+    /// The end of a wrapped line is before the spaces it was wrapped at.
+    pub fn move_to_line_edge(&mut self, layout: &Layout, end: bool, select: bool) {
+        if self.preedit.is_some() {
+            return;
+        }
+        let Some(line) = layout
+            .line_at_caret(self.caret, self.affinity)
+            .map(|index| &layout.lines()[index])
+        else {
+            return;
+        };
+        let offset = if end {
+            let text = &self.text[line.range.clone()];
+            let trimmed = if line.hard_break {
+                text.trim_end_matches(is_line_separator)
+            } else {
+                text.trim_end()
+            };
+            line.range.start + trimmed.len()
+        } else {
+            line.range.start
+        };
+        self.set_caret(offset, select);
+        if end {
+            // the end of a line wrapped inside a word is the start of the next one
+            self.affinity = Affinity::Upstream;
+        }
+    }
+
+    /// Move the caret to the position nearest to a point of the layout, as for a click.
+    pub fn move_to_point(&mut self, layout: &Layout, point: graph_f::Point, select: bool) {
+        if self.preedit.is_some() {
+            return;
+        }
+        if let Some(hit) = layout.hit_test(&self.text, point) {
+            self.set_caret(hit.offset, select);
+            self.affinity = hit.affinity;
+        }
+    }
+
+    /// Replace the selection with text, as typed.
+    pub fn insert(&mut self, text: &str) {
+        let text = self.normalize(text).into_owned();
+        self.replace(self.selection(), &text, EditKind::Typing);
+    }
+
+    /// Delete the selection, or the grapheme before the caret.
+    pub fn backspace(&mut self) {
+        if self.has_selection() {
+            self.replace(self.selection(), "", EditKind::Other);
+        } else {
+            let start = previous_grapheme_boundary(&self.text, self.caret);
+            self.replace(start..self.caret, "", EditKind::Backspace);
+        }
+    }
+
+    /// Delete the selection, or the grapheme after the caret.
+    pub fn delete(&mut self) {
+        if self.has_selection() {
+            self.replace(self.selection(), "", EditKind::Other);
+        } else {
+            let end = next_grapheme_boundary(&self.text, self.caret);
+            self.replace(self.caret..end, "", EditKind::Delete);
+        }
+    }
+
+    /// Delete the selection, or back to the start of the word before the caret.
+    pub fn delete_word_backward(&mut self) {
+        if self.has_selection() {
+            self.replace(self.selection(), "", EditKind::Other);
+        } else {
+            let start = previous_word_start(&self.text, self.caret);
+            self.replace(start..self.caret, "", EditKind::Other);
+        }
+    }
+
+    /// Delete the selection, or up to the end of the word after the caret.
+    pub fn delete_word_forward(&mut self) {
+        if self.has_selection() {
+            self.replace(self.selection(), "", EditKind::Other);
+        } else {
+            let end = next_word_end(&self.text, self.caret);
+            self.replace(self.caret..end, "", EditKind::Other);
+        }
+    }
+
+    /// Replace a byte range with text and record it for undo.
+    fn replace(&mut self, range: Range<usize>, text: &str, kind: EditKind) {
+        let removed = self.text[range.clone()].to_string();
+        if removed.is_empty() && text.is_empty() {
+            return;
+        }
+
+        let before = (self.anchor, self.caret);
+        self.text.replace_range(range.clone(), text);
+        self.caret = range.start + text.len();
+        self.anchor = self.caret;
+        self.goal = None;
+
+        let edit = Edit {
+            start: range.start,
+            removed,
+            inserted: text.to_string(),
+            before,
+            after: (self.anchor, self.caret),
+            kind,
+        };
+        self.redo.clear();
+        let merged = self.merge && self.undo.last_mut().is_some_and(|last| last.absorb(&edit));
+        if !merged {
+            self.undo.push(edit);
+            if self.undo.len() > UNDO_LIMIT {
+                self.undo.remove(0);
+            }
+        }
+        self.merge = true;
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Revert the last edit, returns whether there was one.
+    pub fn undo(&mut self) -> bool {
+        let Some(edit) = self.undo.pop() else {
+            return false;
+        };
+        self.text
+            .replace_range(edit.start..edit.start + edit.inserted.len(), &edit.removed);
+        (self.anchor, self.caret) = edit.before;
+        self.redo.push(edit);
+        self.merge = false;
+        self.goal = None;
+        true
+    }
+
+    /// Make the last undone edit again, returns whether there was one.
+    pub fn redo(&mut self) -> bool {
+        let Some(edit) = self.redo.pop() else {
+            return false;
+        };
+        self.text
+            .replace_range(edit.start..edit.start + edit.removed.len(), &edit.inserted);
+        (self.anchor, self.caret) = edit.after;
+        self.undo.push(edit);
+        self.merge = false;
+        self.goal = None;
+        true
+    }
+
+    /// Put the selection on the clipboard.
+    ///
+    /// Does nothing when nothing is selected.
+    pub fn copy(&self) -> Result<(), TextError> {
+        if self.has_selection() {
+            clipboard::set_clipboard_text(self.selected_text())?;
+        }
+        Ok(())
+    }
+
+    /// Put the selection on the clipboard and delete it.
+    pub fn cut(&mut self) -> Result<(), TextError> {
+        if self.has_selection() {
+            self.copy()?;
+            self.replace(self.selection(), "", EditKind::Other);
+        }
+        Ok(())
+    }
+
+    /// Replace the selection with the text on the clipboard.
+    ///
+    /// A paste is undone on its own, never together with typing around it.
+    pub fn paste(&mut self) -> Result<(), TextError> {
+        let text = clipboard::clipboard_text()?;
+        let text = self.normalize(&text).into_owned();
+        self.replace(self.selection(), &text, EditKind::Other);
+        self.merge = false;
+        Ok(())
+    }
+
+    /// The composition of the input method, if there is one.
+    pub fn preedit(&self) -> Option<&Preedit> {
+        self.preedit.as_ref()
+    }
+
+    /// Update the composition, from an SDL text editing event.
+    ///
+    /// `cursor` is the byte range of the clause being worked on in `text`, the
+    /// `cursor` of the event. A range that does not fall on characters of the
+    /// text is dropped. Empty text ends the composition. A selection is
+    /// replaced by the composition once it starts.
+    pub fn set_preedit(&mut self, text: &str, cursor: Option<Range<usize>>) {
+        if text.is_empty() {
+            self.preedit = None;
+            return;
+        }
+        if self.preedit.is_none() && self.has_selection() {
+            self.replace(self.selection(), "", EditKind::Other);
+        }
+
+        self.preedit = Some(Preedit {
+            text: text.to_string(),
+            cursor: cursor.filter(|cursor| {
+                cursor.start <= cursor.end
+                    && text.is_char_boundary(cursor.start)
+                    && text.is_char_boundary(cursor.end)
+            }),
+        });
+    }
+
+    /// End the composition with the text the input method settled on, from an SDL text input event.
+    pub fn commit(&mut self, text: &str) {
+        self.preedit = None;
+        self.insert(text);
+    }
+
+    /// The text to lay out and draw, with the composition at the caret.
+    pub fn display_text(&self) -> Cow<'_, str> {
+        match &self.preedit {
+            None => Cow::Borrowed(&self.text),
+            Some(preedit) => {
+                let mut text = String::with_capacity(self.text.len() + preedit.text.len());
+                text.push_str(&self.text[..self.caret]);
+                text.push_str(&preedit.text);
+                text.push_str(&self.text[self.caret..]);
+                Cow::Owned(text)
+            }
+        }
+    }
+
+    /// The byte range of the composition in the display text.
+    pub fn preedit_range(&self) -> Option<Range<usize>> {
+        self.preedit
+            .as_ref()
+            .map(|preedit| self.caret..self.caret + preedit.text.len())
+    }
+
+    /// The byte offset of the caret in the display text.
+    ///
+    /// While composing the caret stands at the clause being worked on, or at
+    /// the end of the composition.
+    pub fn display_caret(&self) -> usize {
+        match &self.preedit {
+            None => self.caret,
+            Some(preedit) => {
+                self.caret
+                    + preedit
+                        .cursor
+                        .as_ref()
+                        .map_or(preedit.text.len(), |cursor| cursor.start)
+            }
+        }
+    }
+
+    /// The display text in a style, with the composition underlined.
+    pub fn attributed(&self, style: TextStyle) -> AttributedString {
+        let text = self.display_text();
+        let Some(range) = self.preedit_range() else {
+            return AttributedString::new(text.into_owned(), style);
+        };
+
+        let mut string = AttributedString::default();
+        let underlined = TextStyle {
+            underline: true,
+            ..style
+        };
+        string.push_str(&text[..range.start], style);
+        string.push_str(&text[range.clone()], underlined);
+        string.push_str(&text[range.end..], style);
+        string
+    }
+
+    /// The rectangle of the caret in the layout of the display text.
+    pub fn caret_rect(&self, layout: &Layout) -> Option<graph_f::Rect> {
+        layout.caret_rect(self.display_caret(), self.display_affinity())
+    }
+
+    /// The affinity of the display caret, a caret in a composition sticks to it.
+    fn display_affinity(&self) -> Affinity {
+        if self.preedit.is_some() {
+            Affinity::Downstream
+        } else {
+            self.affinity
+        }
+    }
+
+    /// The rectangles of the selection in the layout of the display text.
+    ///
+    /// A composition replaces the selection, so there is none while composing.
+    pub fn selection_rects(&self, layout: &Layout) -> Vec<graph_f::Rect> {
+        if self.preedit.is_some() {
+            return Vec::new();
+        }
+        layout.selection_rects(self.selection())
+    }
+
+    /// The area to pass to [`Window::set_text_input_area`](wscb_sdl::window::Window::set_text_input_area),
+    /// for a layout drawn at `origin` in window coordinates.
+    ///
+    /// It is the line with the caret, so the candidate list of the input
+    /// method opens next to the composition instead of over it.
+    pub fn text_input_area(
+        &self,
+        layout: &Layout,
+        origin: graph_f::Point,
+    ) -> Option<(graph::Rect, i32)> {
+        let caret = self.caret_rect(layout)?;
+        let line = layout.lines()
+            [layout.line_at_caret(self.display_caret(), self.display_affinity())?]
+        .rect;
+
+        let area = graph::Rect::new(
+            (origin.x + line.position.x).floor() as i32,
+            (origin.y + line.position.y).floor() as i32,
+            line.size.width.ceil() as i32,
+            line.size.height.ceil() as i32,
+        );
+        let cursor = if layout.writing_mode().is_vertical() {
+            caret.position.y - line.position.y
+        } else {
+            caret.position.x - line.position.x
+        };
+        Some((area, cursor.round() as i32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::std::time::Duration;
+
+    use ::wscb_sdl::event::Event;
+
+    use super::*;
+
+    #[test]
+    fn moves_by_graphemes_and_words() {
+        let mut buffer = TextBuffer::with_text("he\u{301}llo, world");
+        buffer.set_caret(2, false);
+        assert_eq!(
+            buffer.caret(),
+            1,
+            "a caret inside a grapheme is moved before it"
+        );
+
+        buffer.move_caret(Motion::NextGrapheme, false);
+        assert_eq!(buffer.caret(), 4);
+        buffer.move_caret(Motion::NextWord, false);
+        assert_eq!(buffer.caret(), 7);
+        buffer.move_caret(Motion::NextWord, true);
+        assert_eq!(buffer.selected_text(), ", world");
+        buffer.move_caret(Motion::PreviousWord, false);
+        assert_eq!(buffer.caret(), 9);
+
+        buffer.select_word(10);
+        assert_eq!(buffer.selected_text(), "world");
+    }
+
+    #[test]
+    fn undoes_words_and_deletions_together() {
+        let mut buffer = TextBuffer::new();
+        for ch in ["a", "b", " ", "c", "d"] {
+            buffer.insert(ch);
+        }
+        buffer.backspace();
+        buffer.backspace();
+        assert_eq!(buffer.text(), "ab ");
+
+        assert!(buffer.undo());
+        assert_eq!(buffer.text(), "ab cd");
+        assert!(buffer.undo());
+        assert_eq!(buffer.text(), "ab");
+        assert!(buffer.redo());
+        assert_eq!(buffer.text(), "ab cd");
+        assert_eq!(buffer.caret(), 5);
+
+        buffer.move_caret(Motion::Start, false);
+        buffer.insert("x");
+        assert!(!buffer.can_redo());
+        assert!(buffer.undo());
+        assert_eq!(buffer.text(), "ab cd");
+    }
+
+    #[test]
+    fn shows_the_composition_at_the_caret() {
+        let mut buffer = TextBuffer::with_text("ab");
+        buffer.select(0..1);
+        buffer.set_preedit("にほん", Some(3..6));
+        assert_eq!(buffer.display_text(), "にほんb");
+        assert_eq!(buffer.preedit_range(), Some(0..9));
+        assert_eq!(buffer.display_caret(), 3);
+
+        buffer.commit("日本");
+        assert_eq!(buffer.text(), "日本b");
+        assert!(buffer.preedit().is_none());
+    }
+
+    #[test]
+    fn takes_the_clause_of_editing_events() {
+        let mut buffer = TextBuffer::with_text("x");
+        // "ほん" selected, as SDL reports characters 1..3 of "にほんご"
+        let event = Event::TextEditing {
+            timestamp: Duration::ZERO,
+            window_id: 1,
+            text: "にほんご".to_string(),
+            cursor: Some(3..9),
+        };
+        if let Event::TextEditing { text, cursor, .. } = &event {
+            buffer.set_preedit(text, cursor.clone());
+        }
+        assert_eq!(buffer.display_text(), "xにほんご");
+        assert_eq!(buffer.display_caret(), 4);
+        assert_eq!(
+            buffer.preedit().and_then(|preedit| preedit.cursor.clone()),
+            Some(3..9)
+        );
+
+        // not on characters of the composition
+        buffer.set_preedit("にほんご", Some(1..2));
+        assert_eq!(buffer.display_caret(), 13);
+    }
+
+    #[test]
+    fn single_line_turns_separators_into_spaces() {
+        let mut buffer = TextBuffer::new();
+        buffer.set_single_line(true);
+        buffer.insert("a\r\nb\nc");
+        assert_eq!(buffer.text(), "a b c");
+    }
+}
@@ -0,0 +1,497 @@
+//! Outlines, drop shadows and glows.
+//!
+//! Effects are drawn as extra layers behind the text. Each layer is a
+//! silhouette of the glyphs, grown by the FreeType stroker and softened by a
+//! gaussian blur, then tinted with one color like any coverage glyph.
+
+use ::wscb_sdl::graph::Surface;
+use ::wscb_type::{color::Color, graph::Point, graph_f};
+
+use crate::{
+    error::TextError,
+    ffi::freetype::{
+        FT_BitmapGlyph, FT_Done_Glyph, FT_Fixed, FT_GLYPH_FORMAT_OUTLINE, FT_Get_Glyph, FT_Glyph,
+        FT_Glyph_StrokeBorder, FT_Glyph_To_Bitmap, FT_LOAD_NO_BITMAP, FT_Load_Glyph, FT_Pos,
+        FT_RENDER_MODE_NORMAL, FT_STROKER_LINECAP_ROUND, FT_STROKER_LINEJOIN_BEVEL,
+        FT_STROKER_LINEJOIN_MITER_VARIABLE, FT_STROKER_LINEJOIN_ROUND, FT_Set_Transform,
+        FT_Stroker, FT_Stroker_Done, FT_Stroker_LineJoin, FT_Stroker_New, FT_Stroker_Set,
+        FT_Vector,
+    },
+    font::Face,
+    raster::{
+        GLYPH_PIXEL_FORMAT, GlyphBitmap, GlyphFormat, Hinting, RasterOptions, RenderMode,
+        bitmap_to_surface,
+    },
+};
+
+/// How the corners of an outline are joined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum LineJoin {
+    #[default]
+    Round,
+    Bevel,
+    Miter,
+}
+
+impl LineJoin {
+    fn freetype_line_join(self) -> FT_Stroker_LineJoin {
+        match self {
+            LineJoin::Round => FT_STROKER_LINEJOIN_ROUND,
+            LineJoin::Bevel => FT_STROKER_LINEJOIN_BEVEL,
+            LineJoin::Miter => FT_STROKER_LINEJOIN_MITER_VARIABLE,
+        }
+    }
+}
+
+/// How the silhouette of a glyph is rasterized for an effect layer.
+///
+/// This is the part of an effect that is baked into the glyph atlas, so it
+/// only holds integers to be usable as a cache key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct GlyphEffect {
+    /// How far the silhouette is grown, in 1/64 pixels.
+    pub outline: u32,
+    pub join: LineJoin,
+    /// The blur radius in pixels, `0` keeps hard edges.
+    pub blur: u32,
+}
+
+impl GlyphEffect {
+    /// The silhouette grown by `width` pixels.
+    pub fn outline(width: f32) -> Self {
+        Self {
+            outline: (width.max(0.0) * 64.0).round() as u32,
+            ..Default::default()
+        }
+    }
+
+    /// The silhouette blurred by `radius` pixels.
+    pub fn blur(radius: u32) -> Self {
+        Self {
+            blur: radius,
+            ..Default::default()
+        }
+    }
+
+    #[must_use]
+    pub fn with_join(self, join: LineJoin) -> Self {
+        Self { join, ..self }
+    }
+
+    #[must_use]
+    pub fn with_blur(self, radius: u32) -> Self {
+        Self {
+            blur: radius,
+            ..self
+        }
+    }
+
+    /// Whether the silhouette is the plain glyph shape.
+    pub fn is_none(&self) -> bool {
+        self.outline == 0 && self.blur == 0
+    }
+}
+
+/// A layer drawn behind text.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextEffect {
+    pub effect: GlyphEffect,
+    pub color: Color,
+    /// Where the layer is drawn relative to the text.
+    pub offset: graph_f::Point,
+}
+
+impl TextEffect {
+    /// An outline of `width` pixels around the glyphs.
+    pub fn outline(width: f32, color: Color) -> Self {
+        Self {
+            effect: GlyphEffect::outline(width),
+            color,
+            offset: graph_f::Point::new(0.0, 0.0),
+        }
+    }
+
+    /// A drop shadow moved by `offset` and blurred by `blur` pixels.
+    pub fn shadow(offset: graph_f::Point, blur: u32, color: Color) -> Self {
+        Self {
+            effect: GlyphEffect::blur(blur),
+            color,
+            offset,
+        }
+    }
+
+    /// A glow around the glyphs, grown by `width` and blurred by `blur` pixels.
+    pub fn glow(width: f32, blur: u32, color: Color) -> Self {
+        Self {
+            effect: GlyphEffect::outline(width).with_blur(blur),
+            color,
+            offset: graph_f::Point::new(0.0, 0.0),
+        }
+    }
+}
+
+/// Owns an `FT_Glyph`, which the glyph functions replace in place.
+struct OwnedGlyph(FT_Glyph);
+
+impl Drop for OwnedGlyph {
+    fn drop(&mut self) {
+        if !self.0.is_null() {
+            unsafe {
+                FT_Done_Glyph(self.0);
+            }
+        }
+    }
+}
+
+struct OwnedStroker(FT_Stroker);
+
+impl Drop for OwnedStroker {
+    fn drop(&mut self) {
+        if !self.0.is_null() {
+            unsafe {
+                FT_Stroker_Done(self.0);
+            }
+        }
+    }
+}
+
+impl Face {
+    /// Rasterize the silhouette of a glyph for an effect layer.
+    ///
+    /// The result is always a [`GlyphFormat::Mask`], color glyphs contribute
+    /// their alpha. Outlines use the FreeType stroker; glyphs without an
+    /// outline (bitmap and color fonts) are grown from their bitmap instead.
+    pub fn rasterize_glyph_effect(
+        &self,
+        glyph_id: u32,
+        pixel_size: u32,
+        subpixel_offset: f32,
+        hinting: Hinting,
+        effect: GlyphEffect,
+    ) -> Result<GlyphBitmap, TextError> {
+        let stroked = if effect.outline > 0 {
+            self.stroke_glyph(glyph_id, pixel_size, subpixel_offset, hinting, effect)?
+        } else {
+            None
+        };
+        // what the stroker did not grow is grown from the bitmap
+        let grow = if stroked.is_some() {
+            0.0
+        } else {
+            effect.outline as f32 / 64.0
+        };
+
+        let mut bitmap = match stroked {
+            Some(bitmap) => bitmap,
+            None => self.rasterize_glyph_at(
+                glyph_id,
+                pixel_size,
+                RasterOptions {
+                    mode: RenderMode::Grayscale,
+                    hinting,
+                    subpixel_positioning: true,
+                },
+                subpixel_offset,
+            )?,
+        };
+
+        if grow == 0.0 && effect.blur == 0 && bitmap.format == GlyphFormat::Mask {
+            return Ok(bitmap);
+        }
+
+        if let Some(surface) = &bitmap.surface {
+            let mut mask = AlphaMask::from_surface(surface)?;
+            let mut margin = 0;
+
+            if grow > 0.0 {
+                let (grown, grown_margin) = mask.dilate(grow);
+                mask = grown;
+                margin += grown_margin;
+            }
+            if effect.blur > 0 {
+                mask = mask.blur(effect.blur);
+                margin += effect.blur as i32;
+            }
+
+            bitmap.surface = mask.to_surface()?;
+            bitmap.bearing = Point::new(bitmap.bearing.x - margin, bitmap.bearing.y - margin);
+        }
+        bitmap.format = GlyphFormat::Mask;
+
+        Ok(bitmap)
+    }
+
+    /// Rasterize the glyph grown by the FreeType stroker, `None` if it has no outline.
+    fn stroke_glyph(
+        &self,
+        glyph_id: u32,
+        pixel_size: u32,
+        subpixel_offset: f32,
+        hinting: Hinting,
+        effect: GlyphEffect,
+    ) -> Result<Option<GlyphBitmap>, TextError> {
+        self.set_pixel_size(pixel_size)?;
+        if self.bitmap_scale() != 1.0 {
+            return Ok(None);
+        }
+
+        unsafe {
+            let delta = FT_Vector {
+                x: (subpixel_offset * 64.0).round() as FT_Pos,
+                y: 0,
+            };
+            FT_Set_Transform(self.get_pointer(), std::ptr::null(), &delta);
+
+            let loaded = FT_Load_Glyph(
+                self.get_pointer(),
+                glyph_id,
+                FT_LOAD_NO_BITMAP | hinting.load_flags(FT_RENDER_MODE_NORMAL),
+            );
+
+            FT_Set_Transform(self.get_pointer(), std::ptr::null(), std::ptr::null());
+
+            TextError::check_freetype(loaded, "loading glyph")?;
+
+            let slot = (*self.get_pointer()).glyph;
+            if (*slot).format != FT_GLYPH_FORMAT_OUTLINE {
+                return Ok(None);
+            }
+            let advance = graph_f::Point::new(
+                (*slot).advance.x as f32 / 64.0,
+                (*slot).advance.y as f32 / 64.0,
+            );
+
+            let mut glyph = OwnedGlyph(std::ptr::null_mut());
+            TextError::check_freetype(FT_Get_Glyph(slot, &mut glyph.0), "copying glyph")?;
+
+            let mut stroker = OwnedStroker(std::ptr::null_mut());
+            TextError::check_freetype(
+                FT_Stroker_New(self.library().get_pointer(), &mut stroker.0),
+                "creating stroker",
+            )?;
+            FT_Stroker_Set(
+                stroker.0,
+                effect.outline as FT_Fixed,
+                FT_STROKER_LINECAP_ROUND,
+                effect.join.freetype_line_join(),
+                // a miter limit of 4.0 in 16.16
+                4 << 16,
+            );
+
+            // the outer border is the glyph grown by the radius, inside included
+            TextError::check_freetype(
+                FT_Glyph_StrokeBorder(&mut glyph.0, stroker.0, 0, 1),
+                "stroking glyph",
+            )?;
+            TextError::check_freetype(
+                FT_Glyph_To_Bitmap(&mut glyph.0, FT_RENDER_MODE_NORMAL, std::ptr::null(), 1),
+                "rendering stroked glyph",
+            )?;
+
+            let rendered = &*(glyph.0 as FT_BitmapGlyph);
+
+            Ok(Some(GlyphBitmap {
+                surface: bitmap_to_surface(&rendered.bitmap)?,
+                format: GlyphFormat::Mask,
+                bearing: Point::new(rendered.left, -rendered.top),
+                advance,
+            }))
+        }
+    }
+}
+
+/// An 8-bit coverage image.
+#[derive(Debug, Clone, PartialEq)]
+struct AlphaMask {
+    width: usize,
+    height: usize,
+    data: Vec<u8>,
+}
+
+impl AlphaMask {
+    /// The alpha channel of a [`GLYPH_PIXEL_FORMAT`] surface.
+    fn from_surface(surface: &Surface) -> Result<Self, TextError> {
+        let size = surface.size()?;
+        let (width, height) = (size.width as usize, size.height as usize);
+        let pitch = surface.pitch() as usize;
+        let pixels = surface.pixels();
+
+        let mut data = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let row = unsafe { std::slice::from_raw_parts(pixels.add(y * pitch), width * 4) };
+            data.extend(row.as_chunks::<4>().0.iter().map(|pixel| pixel[3]));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            data,
+        })
+    }
+
+    /// A white surface with the mask as alpha, `None` if nothing is covered.
+    fn to_surface(&self) -> Result<Option<Surface>, TextError> {
+        if self.width == 0 || self.height == 0 || self.data.iter().all(|&alpha| alpha == 0) {
+            return Ok(None);
+        }
+
+        let surface = Surface::new(self.width as u32, self.height as u32, GLYPH_PIXEL_FORMAT)?;
+        let pitch = surface.pitch() as usize;
+        let pixels = surface.pixels();
+
+        for (y, source) in self.data.chunks_exact(self.width).enumerate() {
+            let row =
+                unsafe { std::slice::from_raw_parts_mut(pixels.add(y * pitch), self.width * 4) };
+            for (pixel, &alpha) in row.as_chunks_mut::<4>().0.iter_mut().zip(source) {
+                *pixel = [255, 255, 255, alpha];
+            }
+        }
+
+        Ok(Some(surface))
+    }
+
+    /// Grow the covered area by `radius` pixels with a round pen.
+    ///
+    /// Returns the grown mask and the margin added on every side.
+    fn dilate(&self, radius: f32) -> (Self, i32) {
+        let margin = radius.ceil() as usize;
+        let width = self.width + margin * 2;
+        let height = self.height + margin * 2;
+        let mut data = vec![0; width * height];
+
+        // the weight of every pen offset, antialiased at the rim
+        let reach = margin as isize;
+        let mut pen = Vec::new();
+        for dy in -reach..=reach {
+            for dx in -reach..=reach {
+                let distance = ((dx * dx + dy * dy) as f32).sqrt();
+                // a pixel is covered once the pen reaches its near edge
+                let weight = (radius + 1.0 - distance).clamp(0.0, 1.0);
+                if weight > 0.0 {
+                    pen.push((dx, dy, weight));
+                }
+            }
+        }
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let alpha = self.data[y * self.width + x];
+                if alpha == 0 {
+                    continue;
+                }
+                for &(dx, dy, weight) in &pen {
+                    let tx = (x + margin).wrapping_add_signed(dx);
+                    let ty = (y + margin).wrapping_add_signed(dy);
+                    let target = &mut data[ty * width + tx];
+                    *target = (*target).max((alpha as f32 * weight).round() as u8);
+                }
+            }
+        }
+
+        (
+            Self {
+                width,
+                height,
+                data,
+            },
+            margin as i32,
+        )
+    }
+
+    /// A gaussian blur reaching `radius` pixels, the mask grows by `radius` on every side.
+    fn blur(&self, radius: u32) -> Self {
+        let radius = radius as usize;
+        let sigma = radius as f32 / 2.0;
+        let mut kernel: Vec<f32> = (0..=radius * 2)
+            .map(|index| {
+                let distance = index as f32 - radius as f32;
+                (-(distance * distance) / (2.0 * sigma * sigma)).exp()
+            })
+            .collect();
+        let sum: f32 = kernel.iter().sum();
+        kernel.iter_mut().for_each(|weight| *weight /= sum);
+
+        let width = self.width + radius * 2;
+        let height = self.height + radius * 2;
+
+        // horizontal pass into the padded width, source rows only
+        let mut horizontal = vec![0.0f32; width * self.height];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let alpha = self.data[y * self.width + x] as f32;
+                if alpha == 0.0 {
+                    continue;
+                }
+                let row = &mut horizontal[y * width + x..y * width + x + kernel.len()];
+                for (target, weight) in row.iter_mut().zip(&kernel) {
+                    *target += alpha * weight;
+                }
+            }
+        }
+
+        // vertical pass into the padded height
+        let mut vertical = vec![0.0f32; width * height];
+        for y in 0..self.height {
+            for x in 0..width {
+                let value = horizontal[y * width + x];
+                if value == 0.0 {
+                    continue;
+                }
+                for (offset, weight) in kernel.iter().enumerate() {
+                    vertical[(y + offset) * width + x] += value * weight;
+                }
+            }
+        }
+
+        Self {
+            width,
+            height,
+            data: vertical
+                .into_iter()
+                .map(|value| value.round().clamp(0.0, 255.0) as u8)
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dot() -> AlphaMask {
+        AlphaMask {
+            width: 1,
+            height: 1,
+            data: vec![255],
+        }
+    }
+
+    #[test]
+    fn dilates_with_a_round_pen() {
+        let (grown, margin) = dot().dilate(2.0);
+        assert_eq!(margin, 2);
+        assert_eq!((grown.width, grown.height), (5, 5));
+
+        let at = |x: usize, y: usize| grown.data[y * grown.width + x];
+        assert_eq!(at(2, 2), 255);
+        assert_eq!(at(0, 2), 255);
+        assert_eq!(at(2, 4), 255);
+        // the corners are only grazed by the pen
+        assert!(at(0, 0) < 64);
+    }
+
+    #[test]
+    fn blur_spreads_and_keeps_coverage() {
+        let blurred = dot().blur(3);
+        assert_eq!((blurred.width, blurred.height), (7, 7));
+
+        let at = |x: usize, y: usize| blurred.data[y * blurred.width + x];
+        let center = at(3, 3);
+        assert!(center < 255 && center > at(2, 3) && at(2, 3) > at(1, 3));
+        assert_eq!(at(2, 3), at(4, 3));
+        assert_eq!(at(3, 2), at(2, 3));
+
+        let total: u32 = blurred.data.iter().map(|&alpha| u32::from(alpha)).sum();
+        assert!(total.abs_diff(255) < 16);
+    }
+}
@@ -0,0 +1,108 @@
+use ::std::path::PathBuf;
+
+use ::thiserror::Error;
+use ::wscb_type::error::SdlError;
+
+use crate::ffi::freetype::{FT_Error, FT_Error_String};
+
+#[derive(Debug, Error)]
+pub enum TextError {
+    #[error("FreeType error {code:#04x} ({}) when {context}", freetype_error_message(*.code))]
+    FreeType {
+        code: FT_Error,
+        context: &'static str,
+    },
+    #[error("failed to read font file {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: ::std::io::Error,
+    },
+    #[error("font data is too large: {0} bytes")]
+    FontTooLarge(usize),
+    #[error("failed to install the PlutoSVG hooks into FreeType")]
+    SvgHooks,
+    #[error("HarfBuzz failed when {0}")]
+    HarfBuzz(&'static str),
+    #[error("range {start}..{end} is not a valid run of a text with {len} bytes")]
+    InvalidRange {
+        start: usize,
+        end: usize,
+        len: usize,
+    },
+    #[error("there is no named instance {index}, the face has {count}")]
+    NoNamedInstance { index: u32, count: u32 },
+    #[error("unsupported bitmap pixel mode {0} when copying glyph")]
+    UnsupportedPixelMode(u8),
+    #[error(transparent)]
+    Sdl(#[from] SdlError),
+}
+
+/// An error in rich text markup, offsets are bytes in the markup.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum MarkupError {
+    #[error("the tag at {offset} is not closed by `]`")]
+    UnterminatedTag { offset: usize },
+    #[error("unknown tag `{tag}` at {offset}")]
+    UnknownTag { tag: String, offset: usize },
+    #[error("invalid value `{value}` for tag `{tag}` at {offset}")]
+    InvalidValue {
+        tag: String,
+        value: String,
+        offset: usize,
+    },
+    #[error("closing tag `{tag}` at {offset} was never opened")]
+    UnexpectedClose { tag: String, offset: usize },
+    #[error("closing tag `{found}` at {offset} does not match the open tag `{expected}`")]
+    MismatchedClose {
+        expected: String,
+        found: String,
+        offset: usize,
+    },
+}
+
+impl TextError {
+    /// Turn a FreeType return code into a `Result`.
+    pub fn check_freetype(code: FT_Error, context: &'static str) -> Result<(), Self> {
+        if code == 0 {
+            Ok(())
+        } else {
+            Err(Self::FreeType { code, context })
+        }
+    }
+}
+
+/// Get a human readable message of the FreeType error code.
+///
+/// FreeType only carries its own strings when built with `FT_CONFIG_OPTION_ERROR_STRINGS`,
+/// so the most common codes are spelled out here.
+pub fn freetype_error_message(code: FT_Error) -> String {
+    unsafe {
+        let message = FT_Error_String(code);
+        if !message.is_null() {
+            return std::ffi::CStr::from_ptr(message)
+                .to_string_lossy()
+                .into_owned();
+        }
+    }
+
+    match code {
+        0x01 => "cannot open resource",
+        0x02 => "unknown file format",
+        0x03 => "broken file",
+        0x06 => "invalid argument",
+        0x07 => "unimplemented feature",
+        0x10 => "invalid glyph index",
+        0x11 => "invalid character code",
+        0x12 => "unsupported glyph image format",
+        0x13 => "cannot render this glyph format",
+        0x17 => "invalid pixel size",
+        0x18 => "invalid SVG document",
+        0x23 => "invalid face handle",
+        0x40 => "out of memory",
+        0x9D => "missing bitmap in strike",
+        0x9E => "SVG hooks have not been set",
+        _ => "unknown error",
+    }
+    .to_string()
+}
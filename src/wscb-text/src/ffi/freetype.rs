@@ -0,0 +1,712 @@
+//! Hand written bindings for the parts of FreeType that wscb uses.
+//!
+//! The layouts follow `freetype/freetype.h`, `freetype/ftimage.h`,
+//! `freetype/ftglyph.h`, `freetype/ftstroke.h`, `freetype/ftmm.h`,
+//! `freetype/ftsnames.h`, `freetype/ftoutln.h`, `freetype/ftcolor.h` and
+//! `freetype/tttables.h`.
+//! Records that FreeType allocates itself only declare their public leading
+//! fields, they are always accessed through pointers.
+
+#![allow(non_camel_case_types, non_snake_case)]
+
+use ::std::ffi::{c_char, c_int, c_long, c_short, c_uchar, c_uint, c_ushort, c_void};
+
+pub type FT_Error = c_int;
+pub type FT_Int = c_int;
+pub type FT_UInt = c_uint;
+pub type FT_Int32 = i32;
+pub type FT_ULong = std::ffi::c_ulong;
+pub type FT_Long = c_long;
+pub type FT_Short = c_short;
+pub type FT_UShort = c_ushort;
+pub type FT_Pos = c_long;
+pub type FT_Fixed = c_long;
+pub type FT_F26Dot6 = c_long;
+pub type FT_String = c_char;
+pub type FT_Byte = c_uchar;
+pub type FT_Bool = c_uchar;
+pub type FT_F2Dot14 = c_short;
+
+pub type FT_Library = *mut c_void;
+pub type FT_Face = *mut FT_FaceRec;
+pub type FT_GlyphSlot = *mut FT_GlyphSlotRec;
+pub type FT_Size = *mut FT_SizeRec;
+pub type FT_CharMap = *mut c_void;
+pub type FT_Glyph = *mut FT_GlyphRec;
+pub type FT_BitmapGlyph = *mut FT_BitmapGlyphRec;
+pub type FT_Stroker = *mut c_void;
+
+pub type FT_Render_Mode = c_uint;
+pub const FT_RENDER_MODE_NORMAL: FT_Render_Mode = 0;
+pub const FT_RENDER_MODE_LIGHT: FT_Render_Mode = 1;
+pub const FT_RENDER_MODE_MONO: FT_Render_Mode = 2;
+pub const FT_RENDER_MODE_LCD: FT_Render_Mode = 3;
+
+pub const FT_PIXEL_MODE_MONO: c_uchar = 1;
+pub const FT_PIXEL_MODE_GRAY: c_uchar = 2;
+pub const FT_PIXEL_MODE_LCD: c_uchar = 5;
+pub const FT_PIXEL_MODE_BGRA: c_uchar = 7;
+
+pub const FT_LOAD_DEFAULT: FT_Int32 = 0;
+pub const FT_LOAD_NO_SCALE: FT_Int32 = 1 << 0;
+pub const FT_LOAD_NO_HINTING: FT_Int32 = 1 << 1;
+pub const FT_LOAD_RENDER: FT_Int32 = 1 << 2;
+pub const FT_LOAD_NO_BITMAP: FT_Int32 = 1 << 3;
+pub const FT_LOAD_IGNORE_TRANSFORM: FT_Int32 = 1 << 11;
+pub const FT_LOAD_COLOR: FT_Int32 = 1 << 20;
+
+/// Equivalent of the `FT_LOAD_TARGET_` macro.
+pub const fn FT_LOAD_TARGET_(mode: FT_Render_Mode) -> FT_Int32 {
+    ((mode & 15) as FT_Int32) << 16
+}
+
+pub type FT_Glyph_Format = c_uint;
+pub const FT_GLYPH_FORMAT_OUTLINE: FT_Glyph_Format = u32::from_be_bytes(*b"outl");
+
+pub type FT_Stroker_LineCap = c_uint;
+pub const FT_STROKER_LINECAP_ROUND: FT_Stroker_LineCap = 1;
+
+pub type FT_Stroker_LineJoin = c_uint;
+pub const FT_STROKER_LINEJOIN_ROUND: FT_Stroker_LineJoin = 0;
+pub const FT_STROKER_LINEJOIN_BEVEL: FT_Stroker_LineJoin = 1;
+pub const FT_STROKER_LINEJOIN_MITER_VARIABLE: FT_Stroker_LineJoin = 2;
+
+pub type FT_LcdFilter = c_uint;
+pub const FT_LCD_FILTER_DEFAULT: FT_LcdFilter = 1;
+
+pub const FT_FACE_FLAG_SCALABLE: FT_Long = 1 << 0;
+pub const FT_FACE_FLAG_FIXED_SIZES: FT_Long = 1 << 1;
+pub const FT_FACE_FLAG_FIXED_WIDTH: FT_Long = 1 << 2;
+pub const FT_FACE_FLAG_VERTICAL: FT_Long = 1 << 5;
+pub const FT_FACE_FLAG_MULTIPLE_MASTERS: FT_Long = 1 << 8;
+pub const FT_FACE_FLAG_COLOR: FT_Long = 1 << 14;
+
+pub const FT_VAR_AXIS_FLAG_HIDDEN: FT_UInt = 1;
+
+pub const FT_STYLE_FLAG_ITALIC: FT_Long = 1 << 0;
+pub const FT_STYLE_FLAG_BOLD: FT_Long = 1 << 1;
+
+pub type FT_Sfnt_Tag = c_uint;
+pub const FT_SFNT_VHEA: FT_Sfnt_Tag = 4;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_Generic {
+    pub data: *mut c_void,
+    pub finalizer: Option<unsafe extern "C" fn(object: *mut c_void)>,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FT_Vector {
+    pub x: FT_Pos,
+    pub y: FT_Pos,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_Matrix {
+    pub xx: FT_Fixed,
+    pub xy: FT_Fixed,
+    pub yx: FT_Fixed,
+    pub yy: FT_Fixed,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_BBox {
+    pub xMin: FT_Pos,
+    pub yMin: FT_Pos,
+    pub xMax: FT_Pos,
+    pub yMax: FT_Pos,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_Bitmap_Size {
+    pub height: FT_Short,
+    pub width: FT_Short,
+    pub size: FT_Pos,
+    pub x_ppem: FT_Pos,
+    pub y_ppem: FT_Pos,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_Bitmap {
+    pub rows: c_uint,
+    pub width: c_uint,
+    pub pitch: c_int,
+    pub buffer: *mut c_uchar,
+    pub num_grays: c_ushort,
+    pub pixel_mode: c_uchar,
+    pub palette_mode: c_uchar,
+    pub palette: *mut c_void,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_Outline {
+    pub n_contours: c_ushort,
+    pub n_points: c_ushort,
+    pub points: *mut FT_Vector,
+    pub tags: *mut c_uchar,
+    pub contours: *mut c_ushort,
+    pub flags: c_int,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_Glyph_Metrics {
+    pub width: FT_Pos,
+    pub height: FT_Pos,
+    pub horiBearingX: FT_Pos,
+    pub horiBearingY: FT_Pos,
+    pub horiAdvance: FT_Pos,
+    pub vertBearingX: FT_Pos,
+    pub vertBearingY: FT_Pos,
+    pub vertAdvance: FT_Pos,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_Size_Metrics {
+    pub x_ppem: FT_UShort,
+    pub y_ppem: FT_UShort,
+    pub x_scale: FT_Fixed,
+    pub y_scale: FT_Fixed,
+    pub ascender: FT_Pos,
+    pub descender: FT_Pos,
+    pub height: FT_Pos,
+    pub max_advance: FT_Pos,
+}
+
+#[repr(C)]
+pub struct FT_SizeRec {
+    pub face: FT_Face,
+    pub generic: FT_Generic,
+    pub metrics: FT_Size_Metrics,
+    // private fields follow
+}
+
+#[repr(C)]
+pub struct FT_GlyphSlotRec {
+    pub library: FT_Library,
+    pub face: FT_Face,
+    pub next: FT_GlyphSlot,
+    pub glyph_index: FT_UInt,
+    pub generic: FT_Generic,
+    pub metrics: FT_Glyph_Metrics,
+    pub linearHoriAdvance: FT_Fixed,
+    pub linearVertAdvance: FT_Fixed,
+    pub advance: FT_Vector,
+    pub format: c_uint,
+    pub bitmap: FT_Bitmap,
+    pub bitmap_left: FT_Int,
+    pub bitmap_top: FT_Int,
+    pub outline: FT_Outline,
+    // private fields follow
+}
+
+#[repr(C)]
+pub struct FT_GlyphRec {
+    pub library: FT_Library,
+    pub clazz: *const c_void,
+    pub format: FT_Glyph_Format,
+    pub advance: FT_Vector,
+}
+
+#[repr(C)]
+pub struct FT_BitmapGlyphRec {
+    pub root: FT_GlyphRec,
+    pub left: FT_Int,
+    pub top: FT_Int,
+    pub bitmap: FT_Bitmap,
+}
+
+#[repr(C)]
+pub struct FT_Var_Axis {
+    pub name: *mut FT_String,
+    pub minimum: FT_Fixed,
+    pub def: FT_Fixed,
+    pub maximum: FT_Fixed,
+    pub tag: FT_ULong,
+    pub strid: FT_UInt,
+}
+
+#[repr(C)]
+pub struct FT_Var_Named_Style {
+    pub coords: *mut FT_Fixed,
+    pub strid: FT_UInt,
+    pub psid: FT_UInt,
+}
+
+#[repr(C)]
+pub struct FT_MM_Var {
+    pub num_axis: FT_UInt,
+    pub num_designs: FT_UInt,
+    pub num_namedstyles: FT_UInt,
+    pub axis: *mut FT_Var_Axis,
+    pub namedstyle: *mut FT_Var_Named_Style,
+}
+
+#[repr(C)]
+pub struct FT_SfntName {
+    pub platform_id: FT_UShort,
+    pub encoding_id: FT_UShort,
+    pub language_id: FT_UShort,
+    pub name_id: FT_UShort,
+    pub string: *mut FT_Byte,
+    pub string_len: FT_UInt,
+}
+
+#[repr(C)]
+pub struct FT_FaceRec {
+    pub num_faces: FT_Long,
+    pub face_index: FT_Long,
+    pub face_flags: FT_Long,
+    pub style_flags: FT_Long,
+    pub num_glyphs: FT_Long,
+    pub family_name: *mut FT_String,
+    pub style_name: *mut FT_String,
+    pub num_fixed_sizes: FT_Int,
+    pub available_sizes: *mut FT_Bitmap_Size,
+    pub num_charmaps: FT_Int,
+    pub charmaps: *mut FT_CharMap,
+    pub generic: FT_Generic,
+    pub bbox: FT_BBox,
+    pub units_per_EM: FT_UShort,
+    pub ascender: FT_Short,
+    pub descender: FT_Short,
+    pub height: FT_Short,
+    pub max_advance_width: FT_Short,
+    pub max_advance_height: FT_Short,
+    pub underline_position: FT_Short,
+    pub underline_thickness: FT_Short,
+    pub glyph: FT_GlyphSlot,
+    pub size: FT_Size,
+    pub charmap: FT_CharMap,
+    // private fields follow
+}
+
+pub const FT_PALETTE_FOR_DARK_BACKGROUND: FT_UShort = 0x02;
+
+pub type FT_PaintFormat = c_uint;
+pub const FT_COLR_PAINTFORMAT_COLR_LAYERS: FT_PaintFormat = 1;
+pub const FT_COLR_PAINTFORMAT_SOLID: FT_PaintFormat = 2;
+pub const FT_COLR_PAINTFORMAT_LINEAR_GRADIENT: FT_PaintFormat = 4;
+pub const FT_COLR_PAINTFORMAT_RADIAL_GRADIENT: FT_PaintFormat = 6;
+pub const FT_COLR_PAINTFORMAT_SWEEP_GRADIENT: FT_PaintFormat = 8;
+pub const FT_COLR_PAINTFORMAT_GLYPH: FT_PaintFormat = 10;
+pub const FT_COLR_PAINTFORMAT_COLR_GLYPH: FT_PaintFormat = 11;
+pub const FT_COLR_PAINTFORMAT_TRANSFORM: FT_PaintFormat = 12;
+pub const FT_COLR_PAINTFORMAT_TRANSLATE: FT_PaintFormat = 14;
+pub const FT_COLR_PAINTFORMAT_SCALE: FT_PaintFormat = 16;
+pub const FT_COLR_PAINTFORMAT_ROTATE: FT_PaintFormat = 24;
+pub const FT_COLR_PAINTFORMAT_SKEW: FT_PaintFormat = 28;
+pub const FT_COLR_PAINTFORMAT_COMPOSITE: FT_PaintFormat = 32;
+
+pub type FT_PaintExtend = c_uint;
+pub const FT_COLR_PAINT_EXTEND_PAD: FT_PaintExtend = 0;
+pub const FT_COLR_PAINT_EXTEND_REPEAT: FT_PaintExtend = 1;
+pub const FT_COLR_PAINT_EXTEND_REFLECT: FT_PaintExtend = 2;
+
+pub type FT_Composite_Mode = c_uint;
+pub const FT_COLR_COMPOSITE_CLEAR: FT_Composite_Mode = 0;
+pub const FT_COLR_COMPOSITE_SRC: FT_Composite_Mode = 1;
+pub const FT_COLR_COMPOSITE_DEST: FT_Composite_Mode = 2;
+pub const FT_COLR_COMPOSITE_SRC_OVER: FT_Composite_Mode = 3;
+pub const FT_COLR_COMPOSITE_DEST_OVER: FT_Composite_Mode = 4;
+pub const FT_COLR_COMPOSITE_SRC_IN: FT_Composite_Mode = 5;
+pub const FT_COLR_COMPOSITE_DEST_IN: FT_Composite_Mode = 6;
+pub const FT_COLR_COMPOSITE_SRC_OUT: FT_Composite_Mode = 7;
+pub const FT_COLR_COMPOSITE_DEST_OUT: FT_Composite_Mode = 8;
+pub const FT_COLR_COMPOSITE_SRC_ATOP: FT_Composite_Mode = 9;
+pub const FT_COLR_COMPOSITE_DEST_ATOP: FT_Composite_Mode = 10;
+pub const FT_COLR_COMPOSITE_XOR: FT_Composite_Mode = 11;
+pub const FT_COLR_COMPOSITE_PLUS: FT_Composite_Mode = 12;
+pub const FT_COLR_COMPOSITE_SCREEN: FT_Composite_Mode = 13;
+pub const FT_COLR_COMPOSITE_OVERLAY: FT_Composite_Mode = 14;
+pub const FT_COLR_COMPOSITE_DARKEN: FT_Composite_Mode = 15;
+pub const FT_COLR_COMPOSITE_LIGHTEN: FT_Composite_Mode = 16;
+pub const FT_COLR_COMPOSITE_COLOR_DODGE: FT_Composite_Mode = 17;
+pub const FT_COLR_COMPOSITE_COLOR_BURN: FT_Composite_Mode = 18;
+pub const FT_COLR_COMPOSITE_HARD_LIGHT: FT_Composite_Mode = 19;
+pub const FT_COLR_COMPOSITE_SOFT_LIGHT: FT_Composite_Mode = 20;
+pub const FT_COLR_COMPOSITE_DIFFERENCE: FT_Composite_Mode = 21;
+pub const FT_COLR_COMPOSITE_EXCLUSION: FT_Composite_Mode = 22;
+pub const FT_COLR_COMPOSITE_MULTIPLY: FT_Composite_Mode = 23;
+pub const FT_COLR_COMPOSITE_HSL_HUE: FT_Composite_Mode = 24;
+pub const FT_COLR_COMPOSITE_HSL_SATURATION: FT_Composite_Mode = 25;
+pub const FT_COLR_COMPOSITE_HSL_COLOR: FT_Composite_Mode = 26;
+pub const FT_COLR_COMPOSITE_HSL_LUMINOSITY: FT_Composite_Mode = 27;
+
+pub type FT_Color_Root_Transform = c_uint;
+pub const FT_COLOR_INCLUDE_ROOT_TRANSFORM: FT_Color_Root_Transform = 0;
+pub const FT_COLOR_NO_ROOT_TRANSFORM: FT_Color_Root_Transform = 1;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_Color {
+    pub blue: FT_Byte,
+    pub green: FT_Byte,
+    pub red: FT_Byte,
+    pub alpha: FT_Byte,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_Palette_Data {
+    pub num_palettes: FT_UShort,
+    pub palette_name_ids: *const FT_UShort,
+    pub palette_flags: *const FT_UShort,
+    pub num_palette_entries: FT_UShort,
+    pub palette_entry_name_ids: *const FT_UShort,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_LayerIterator {
+    pub num_layers: FT_UInt,
+    pub layer: FT_UInt,
+    pub p: *mut FT_Byte,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_ColorStopIterator {
+    pub num_color_stops: FT_UInt,
+    pub current_color_stop: FT_UInt,
+    pub p: *mut FT_Byte,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_ColorIndex {
+    pub palette_index: u16,
+    pub alpha: FT_F2Dot14,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_ColorStop {
+    /// 16.16 fixed point since FreeType 2.13, `FT_F2Dot14` before.
+    pub stop_offset: FT_Fixed,
+    pub color: FT_ColorIndex,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_ColorLine {
+    pub extend: FT_PaintExtend,
+    pub color_stop_iterator: FT_ColorStopIterator,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_Affine23 {
+    pub xx: FT_Fixed,
+    pub xy: FT_Fixed,
+    pub dx: FT_Fixed,
+    pub yx: FT_Fixed,
+    pub yy: FT_Fixed,
+    pub dy: FT_Fixed,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_OpaquePaint {
+    pub p: *mut FT_Byte,
+    pub insert_root_transform: FT_Bool,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_PaintColrLayers {
+    pub layer_iterator: FT_LayerIterator,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_PaintSolid {
+    pub color: FT_ColorIndex,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_PaintLinearGradient {
+    pub colorline: FT_ColorLine,
+    pub p0: FT_Vector,
+    pub p1: FT_Vector,
+    pub p2: FT_Vector,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_PaintRadialGradient {
+    pub colorline: FT_ColorLine,
+    pub c0: FT_Vector,
+    pub r0: FT_Pos,
+    pub c1: FT_Vector,
+    pub r1: FT_Pos,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_PaintSweepGradient {
+    pub colorline: FT_ColorLine,
+    pub center: FT_Vector,
+    pub start_angle: FT_Fixed,
+    pub end_angle: FT_Fixed,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_PaintGlyph {
+    pub paint: FT_OpaquePaint,
+    pub glyphID: FT_UInt,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_PaintColrGlyph {
+    pub glyphID: FT_UInt,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_PaintTransform {
+    pub paint: FT_OpaquePaint,
+    pub affine: FT_Affine23,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_PaintTranslate {
+    pub paint: FT_OpaquePaint,
+    pub dx: FT_Fixed,
+    pub dy: FT_Fixed,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_PaintScale {
+    pub paint: FT_OpaquePaint,
+    pub scale_x: FT_Fixed,
+    pub scale_y: FT_Fixed,
+    pub center_x: FT_Fixed,
+    pub center_y: FT_Fixed,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_PaintRotate {
+    pub paint: FT_OpaquePaint,
+    pub angle: FT_Fixed,
+    pub center_x: FT_Fixed,
+    pub center_y: FT_Fixed,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_PaintSkew {
+    pub paint: FT_OpaquePaint,
+    pub x_skew_angle: FT_Fixed,
+    pub y_skew_angle: FT_Fixed,
+    pub center_x: FT_Fixed,
+    pub center_y: FT_Fixed,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FT_PaintComposite {
+    pub source_paint: FT_OpaquePaint,
+    pub composite_mode: FT_Composite_Mode,
+    pub backdrop_paint: FT_OpaquePaint,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union FT_COLR_Paint_Union {
+    pub colr_layers: FT_PaintColrLayers,
+    pub glyph: FT_PaintGlyph,
+    pub solid: FT_PaintSolid,
+    pub linear_gradient: FT_PaintLinearGradient,
+    pub radial_gradient: FT_PaintRadialGradient,
+    pub sweep_gradient: FT_PaintSweepGradient,
+    pub transform: FT_PaintTransform,
+    pub translate: FT_PaintTranslate,
+    pub scale: FT_PaintScale,
+    pub rotate: FT_PaintRotate,
+    pub skew: FT_PaintSkew,
+    pub composite: FT_PaintComposite,
+    pub colr_glyph: FT_PaintColrGlyph,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct FT_COLR_Paint {
+    pub format: FT_PaintFormat,
+    pub u: FT_COLR_Paint_Union,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FT_ClipBox {
+    pub bottom_left: FT_Vector,
+    pub top_left: FT_Vector,
+    pub top_right: FT_Vector,
+    pub bottom_right: FT_Vector,
+}
+
+/// The `vhea` table, as returned by `FT_Get_Sfnt_Table`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct TT_VertHeader {
+    pub Version: FT_Fixed,
+    pub Ascender: FT_Short,
+    pub Descender: FT_Short,
+    pub Line_Gap: FT_Short,
+    pub advance_Height_Max: FT_UShort,
+    pub min_Top_Side_Bearing: FT_Short,
+    pub min_Bottom_Side_Bearing: FT_Short,
+    pub yMax_Extent: FT_Short,
+    pub caret_Slope_Rise: FT_Short,
+    pub caret_Slope_Run: FT_Short,
+    pub caret_Offset: FT_Short,
+    pub Reserved: [FT_Short; 4],
+    pub metric_Data_Format: FT_Short,
+    pub number_Of_VMetrics: FT_UShort,
+    pub long_metrics: *mut c_void,
+    pub short_metrics: *mut c_void,
+}
+
+unsafe extern "C" {
+    pub fn FT_Init_FreeType(alibrary: *mut FT_Library) -> FT_Error;
+    pub fn FT_Done_FreeType(library: FT_Library) -> FT_Error;
+    pub fn FT_Error_String(error_code: FT_Error) -> *const c_char;
+    pub fn FT_Library_SetLcdFilter(library: FT_Library, filter: FT_LcdFilter) -> FT_Error;
+
+    pub fn FT_New_Memory_Face(
+        library: FT_Library,
+        file_base: *const FT_Byte,
+        file_size: FT_Long,
+        face_index: FT_Long,
+        aface: *mut FT_Face,
+    ) -> FT_Error;
+    pub fn FT_Done_Face(face: FT_Face) -> FT_Error;
+
+    pub fn FT_Set_Pixel_Sizes(
+        face: FT_Face,
+        pixel_width: FT_UInt,
+        pixel_height: FT_UInt,
+    ) -> FT_Error;
+    pub fn FT_Select_Size(face: FT_Face, strike_index: FT_Int) -> FT_Error;
+    pub fn FT_Set_Transform(face: FT_Face, matrix: *const FT_Matrix, delta: *const FT_Vector);
+    pub fn FT_Get_Char_Index(face: FT_Face, charcode: FT_ULong) -> FT_UInt;
+    pub fn FT_Get_First_Char(face: FT_Face, agindex: *mut FT_UInt) -> FT_ULong;
+    pub fn FT_Get_Next_Char(face: FT_Face, char_code: FT_ULong, agindex: *mut FT_UInt) -> FT_ULong;
+    pub fn FT_Load_Sfnt_Table(
+        face: FT_Face,
+        tag: FT_ULong,
+        offset: FT_Long,
+        buffer: *mut FT_Byte,
+        length: *mut FT_ULong,
+    ) -> FT_Error;
+    pub fn FT_Get_Sfnt_Table(face: FT_Face, tag: FT_Sfnt_Tag) -> *mut c_void;
+    pub fn FT_Load_Glyph(face: FT_Face, glyph_index: FT_UInt, load_flags: FT_Int32) -> FT_Error;
+    pub fn FT_Render_Glyph(slot: FT_GlyphSlot, render_mode: FT_Render_Mode) -> FT_Error;
+
+    pub fn FT_Get_Glyph(slot: FT_GlyphSlot, aglyph: *mut FT_Glyph) -> FT_Error;
+    pub fn FT_Done_Glyph(glyph: FT_Glyph);
+    pub fn FT_Glyph_To_Bitmap(
+        the_glyph: *mut FT_Glyph,
+        render_mode: FT_Render_Mode,
+        origin: *const FT_Vector,
+        destroy: FT_Bool,
+    ) -> FT_Error;
+
+    pub fn FT_Stroker_New(library: FT_Library, astroker: *mut FT_Stroker) -> FT_Error;
+    pub fn FT_Stroker_Set(
+        stroker: FT_Stroker,
+        radius: FT_Fixed,
+        line_cap: FT_Stroker_LineCap,
+        line_join: FT_Stroker_LineJoin,
+        miter_limit: FT_Fixed,
+    );
+    pub fn FT_Stroker_Done(stroker: FT_Stroker);
+    pub fn FT_Glyph_StrokeBorder(
+        pglyph: *mut FT_Glyph,
+        stroker: FT_Stroker,
+        inside: FT_Bool,
+        destroy: FT_Bool,
+    ) -> FT_Error;
+
+    pub fn FT_Get_MM_Var(face: FT_Face, amaster: *mut *mut FT_MM_Var) -> FT_Error;
+    pub fn FT_Done_MM_Var(library: FT_Library, amaster: *mut FT_MM_Var) -> FT_Error;
+    pub fn FT_Get_Var_Axis_Flags(
+        master: *mut FT_MM_Var,
+        axis_index: FT_UInt,
+        flags: *mut FT_UInt,
+    ) -> FT_Error;
+    pub fn FT_Set_Var_Design_Coordinates(
+        face: FT_Face,
+        num_coords: FT_UInt,
+        coords: *mut FT_Fixed,
+    ) -> FT_Error;
+    pub fn FT_Get_Var_Design_Coordinates(
+        face: FT_Face,
+        num_coords: FT_UInt,
+        coords: *mut FT_Fixed,
+    ) -> FT_Error;
+    pub fn FT_Set_Named_Instance(face: FT_Face, instance_index: FT_UInt) -> FT_Error;
+
+    pub fn FT_Outline_Get_Bitmap(
+        library: FT_Library,
+        outline: *const FT_Outline,
+        abitmap: *const FT_Bitmap,
+    ) -> FT_Error;
+
+    pub fn FT_Palette_Data_Get(face: FT_Face, apalette: *mut FT_Palette_Data) -> FT_Error;
+    pub fn FT_Palette_Select(
+        face: FT_Face,
+        palette_index: FT_UShort,
+        apalette: *mut *mut FT_Color,
+    ) -> FT_Error;
+    pub fn FT_Get_Color_Glyph_Layer(
+        face: FT_Face,
+        base_glyph: FT_UInt,
+        aglyph_index: *mut FT_UInt,
+        acolor_index: *mut FT_UInt,
+        iterator: *mut FT_LayerIterator,
+    ) -> FT_Bool;
+    pub fn FT_Get_Color_Glyph_Paint(
+        face: FT_Face,
+        base_glyph: FT_UInt,
+        root_transform: FT_Color_Root_Transform,
+        paint: *mut FT_OpaquePaint,
+    ) -> FT_Bool;
+    pub fn FT_Get_Color_Glyph_ClipBox(
+        face: FT_Face,
+        base_glyph: FT_UInt,
+        clip_box: *mut FT_ClipBox,
+    ) -> FT_Bool;
+    pub fn FT_Get_Paint_Layers(
+        face: FT_Face,
+        iterator: *mut FT_LayerIterator,
+        paint: *mut FT_OpaquePaint,
+    ) -> FT_Bool;
+    pub fn FT_Get_Colorline_Stops(
+        face: FT_Face,
+        color_stop: *mut FT_ColorStop,
+        iterator: *mut FT_ColorStopIterator,
+    ) -> FT_Bool;
+    pub fn FT_Get_Paint(
+        face: FT_Face,
+        opaque_paint: FT_OpaquePaint,
+        paint: *mut FT_COLR_Paint,
+    ) -> FT_Bool;
+
+    pub fn FT_Get_Sfnt_Name_Count(face: FT_Face) -> FT_UInt;
+    pub fn FT_Get_Sfnt_Name(face: FT_Face, idx: FT_UInt, aname: *mut FT_SfntName) -> FT_Error;
+}
@@ -0,0 +1,166 @@
+//! Hand written bindings for the parts of HarfBuzz that wscb uses.
+//!
+//! The layouts follow `hb-common.h`, `hb-buffer.h` and `hb-font.h`.
+//! HarfBuzz is built without FreeType integration, faces are created from
+//! the same font data that FreeType reads.
+
+#![allow(non_camel_case_types)]
+
+use ::std::ffi::{c_char, c_int, c_uint, c_void};
+
+pub type hb_bool_t = c_int;
+pub type hb_codepoint_t = u32;
+pub type hb_position_t = i32;
+pub type hb_mask_t = u32;
+pub type hb_tag_t = u32;
+pub type hb_script_t = u32;
+pub type hb_language_t = *const c_void;
+pub type hb_destroy_func_t = Option<unsafe extern "C" fn(user_data: *mut c_void)>;
+
+pub type hb_direction_t = c_uint;
+pub const HB_DIRECTION_INVALID: hb_direction_t = 0;
+pub const HB_DIRECTION_LTR: hb_direction_t = 4;
+pub const HB_DIRECTION_RTL: hb_direction_t = 5;
+pub const HB_DIRECTION_TTB: hb_direction_t = 6;
+pub const HB_DIRECTION_BTT: hb_direction_t = 7;
+
+pub type hb_memory_mode_t = c_uint;
+pub const HB_MEMORY_MODE_READONLY: hb_memory_mode_t = 1;
+
+pub type hb_buffer_flags_t = c_uint;
+pub const HB_BUFFER_FLAG_DEFAULT: hb_buffer_flags_t = 0;
+pub const HB_BUFFER_FLAG_BOT: hb_buffer_flags_t = 1;
+pub const HB_BUFFER_FLAG_EOT: hb_buffer_flags_t = 2;
+
+pub const HB_FEATURE_GLOBAL_START: c_uint = 0;
+pub const HB_FEATURE_GLOBAL_END: c_uint = c_uint::MAX;
+
+/// Equivalent of the `HB_TAG` macro.
+pub const fn hb_tag(tag: &[u8; 4]) -> hb_tag_t {
+    u32::from_be_bytes(*tag)
+}
+
+#[repr(C)]
+pub struct hb_blob_t {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+pub struct hb_face_t {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+pub struct hb_font_t {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+pub struct hb_buffer_t {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct hb_glyph_info_t {
+    pub codepoint: hb_codepoint_t,
+    pub mask: hb_mask_t,
+    pub cluster: u32,
+    pub var1: u32,
+    pub var2: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct hb_glyph_position_t {
+    pub x_advance: hb_position_t,
+    pub y_advance: hb_position_t,
+    pub x_offset: hb_position_t,
+    pub y_offset: hb_position_t,
+    pub var: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct hb_feature_t {
+    pub tag: hb_tag_t,
+    pub value: u32,
+    pub start: c_uint,
+    pub end: c_uint,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct hb_variation_t {
+    pub tag: hb_tag_t,
+    pub value: f32,
+}
+
+unsafe extern "C" {
+    pub fn hb_blob_create(
+        data: *const c_char,
+        length: c_uint,
+        mode: hb_memory_mode_t,
+        user_data: *mut c_void,
+        destroy: hb_destroy_func_t,
+    ) -> *mut hb_blob_t;
+    pub fn hb_blob_destroy(blob: *mut hb_blob_t);
+
+    pub fn hb_face_create(blob: *mut hb_blob_t, index: c_uint) -> *mut hb_face_t;
+    pub fn hb_face_destroy(face: *mut hb_face_t);
+
+    pub fn hb_font_create(face: *mut hb_face_t) -> *mut hb_font_t;
+    pub fn hb_font_destroy(font: *mut hb_font_t);
+    pub fn hb_font_set_scale(font: *mut hb_font_t, x_scale: c_int, y_scale: c_int);
+    pub fn hb_font_set_ppem(font: *mut hb_font_t, x_ppem: c_uint, y_ppem: c_uint);
+    pub fn hb_font_set_variations(
+        font: *mut hb_font_t,
+        variations: *const hb_variation_t,
+        variations_length: c_uint,
+    );
+
+    pub fn hb_language_from_string(str: *const c_char, len: c_int) -> hb_language_t;
+    pub fn hb_language_to_string(language: hb_language_t) -> *const c_char;
+    pub fn hb_script_from_iso15924_tag(tag: hb_tag_t) -> hb_script_t;
+    pub fn hb_feature_from_string(
+        str: *const c_char,
+        len: c_int,
+        feature: *mut hb_feature_t,
+    ) -> hb_bool_t;
+    pub fn hb_variation_from_string(
+        str: *const c_char,
+        len: c_int,
+        variation: *mut hb_variation_t,
+    ) -> hb_bool_t;
+
+    pub fn hb_buffer_create() -> *mut hb_buffer_t;
+    pub fn hb_buffer_destroy(buffer: *mut hb_buffer_t);
+    pub fn hb_buffer_add_utf8(
+        buffer: *mut hb_buffer_t,
+        text: *const c_char,
+        text_length: c_int,
+        item_offset: c_uint,
+        item_length: c_int,
+    );
+    pub fn hb_buffer_set_direction(buffer: *mut hb_buffer_t, direction: hb_direction_t);
+    pub fn hb_buffer_get_direction(buffer: *mut hb_buffer_t) -> hb_direction_t;
+    pub fn hb_buffer_set_script(buffer: *mut hb_buffer_t, script: hb_script_t);
+    pub fn hb_buffer_set_language(buffer: *mut hb_buffer_t, language: hb_language_t);
+    pub fn hb_buffer_set_flags(buffer: *mut hb_buffer_t, flags: hb_buffer_flags_t);
+    pub fn hb_buffer_guess_segment_properties(buffer: *mut hb_buffer_t);
+    pub fn hb_buffer_get_glyph_infos(
+        buffer: *mut hb_buffer_t,
+        length: *mut c_uint,
+    ) -> *mut hb_glyph_info_t;
+    pub fn hb_buffer_get_glyph_positions(
+        buffer: *mut hb_buffer_t,
+        length: *mut c_uint,
+    ) -> *mut hb_glyph_position_t;
+
+    pub fn hb_shape(
+        font: *mut hb_font_t,
+        buffer: *mut hb_buffer_t,
+        features: *const hb_feature_t,
+        num_features: c_uint,
+    );
+}
@@ -0,0 +1,5 @@
+//! Raw bindings to the native libraries linked by `build.rs`.
+
+pub mod freetype;
+pub mod harfbuzz;
+pub mod native;
@@ -0,0 +1,10 @@
+//! Functions of `native/src`, compiled into the `wscb-cpp` library.
+
+use ::std::ffi::c_int;
+
+use super::freetype::FT_Library;
+
+unsafe extern "C" {
+    /// Install the PlutoSVG hooks of the `ot-svg` module, returns `0` on success.
+    pub fn bind_plutosvg_freetype(library: FT_Library) -> c_int;
+}
@@ -0,0 +1,620 @@
+use ::std::{
+    cell::{Cell, OnceCell},
+    path::Path,
+    ptr::NonNull,
+    rc::Rc,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use crate::{
+    error::TextError,
+    ffi::freetype::{
+        FT_Done_Face, FT_Done_FreeType, FT_FACE_FLAG_COLOR, FT_FACE_FLAG_FIXED_SIZES,
+        FT_FACE_FLAG_FIXED_WIDTH, FT_FACE_FLAG_SCALABLE, FT_Face, FT_FaceRec, FT_Get_Char_Index,
+        FT_Get_Sfnt_Name, FT_Get_Sfnt_Name_Count, FT_Init_FreeType, FT_LCD_FILTER_DEFAULT,
+        FT_Library, FT_Library_SetLcdFilter, FT_Load_Sfnt_Table, FT_Long, FT_New_Memory_Face,
+        FT_Pos, FT_STYLE_FLAG_BOLD, FT_STYLE_FLAG_ITALIC, FT_Select_Size, FT_Set_Pixel_Sizes,
+        FT_SfntName, FT_ULong,
+    },
+    ffi::native::bind_plutosvg_freetype,
+    shape::HbFont,
+    variation::Variation,
+};
+
+#[derive(Debug)]
+struct LibraryInner {
+    pointer: NonNull<std::ffi::c_void>,
+}
+
+impl Drop for LibraryInner {
+    fn drop(&mut self) {
+        unsafe {
+            FT_Done_FreeType(self.pointer.as_ptr());
+        }
+    }
+}
+
+/// A FreeType library instance.
+///
+/// Cloning is cheap, every [`Face`] keeps its library alive.
+/// FreeType is not thread safe, so neither is this type.
+#[derive(Debug, Clone)]
+pub struct Library {
+    inner: Rc<LibraryInner>,
+}
+
+impl Library {
+    pub fn new() -> Result<Self, TextError> {
+        let mut raw: FT_Library = std::ptr::null_mut();
+
+        unsafe {
+            TextError::check_freetype(FT_Init_FreeType(&mut raw), "initializing library")?;
+        }
+
+        let pointer = NonNull::new(raw).ok_or(TextError::FreeType {
+            code: 0x21,
+            context: "initializing library",
+        })?;
+
+        let library = Self {
+            inner: Rc::new(LibraryInner { pointer }),
+        };
+
+        unsafe {
+            // LCD rendering without a filter produces heavy color fringes.
+            // Builds without subpixel rendering support report an error here, that is fine.
+            let _ = FT_Library_SetLcdFilter(library.get_pointer(), FT_LCD_FILTER_DEFAULT);
+
+            // OT-SVG glyphs are rendered by PlutoSVG
+            if bind_plutosvg_freetype(library.get_pointer()) != 0 {
+                return Err(TextError::SvgHooks);
+            }
+        }
+
+        Ok(library)
+    }
+
+    pub fn get_pointer(&self) -> FT_Library {
+        self.inner.pointer.as_ptr()
+    }
+
+    /// Load a face from a font file.
+    ///
+    /// The whole file is read into memory so it can be shared with the shaper.
+    pub fn load_face_from_file(
+        &self,
+        path: impl AsRef<Path>,
+        face_index: u32,
+    ) -> Result<Face, TextError> {
+        let path = path.as_ref();
+        let data = std::fs::read(path).map_err(|source| TextError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        self.load_face_from_memory(data, face_index)
+    }
+
+    /// Load a face from font data in memory.
+    ///
+    /// `face_index` selects the face inside a font collection (`.ttc`/`.otc`),
+    /// it is `0` for normal font files.
+    pub fn load_face_from_memory(
+        &self,
+        data: impl Into<Rc<[u8]>>,
+        face_index: u32,
+    ) -> Result<Face, TextError> {
+        self.load_face(data.into(), face_index, Vec::new())
+    }
+
+    fn load_face(
+        &self,
+        data: Rc<[u8]>,
+        face_index: u32,
+        variations: Vec<Variation>,
+    ) -> Result<Face, TextError> {
+        let size: FT_Long = data
+            .len()
+            .try_into()
+            .map_err(|_| TextError::FontTooLarge(data.len()))?;
+
+        let mut raw: FT_Face = std::ptr::null_mut();
+
+        unsafe {
+            TextError::check_freetype(
+                FT_New_Memory_Face(
+                    self.get_pointer(),
+                    data.as_ptr(),
+                    size,
+                    face_index as FT_Long,
+                    &mut raw,
+                ),
+                "loading face",
+            )?;
+        }
+
+        let pointer = NonNull::new(raw).ok_or(TextError::FreeType {
+            code: 0x23,
+            context: "loading face",
+        })?;
+
+        Ok(Face {
+            inner: Rc::new(FaceInner {
+                pointer,
+                id: FaceId::next(),
+                pixel_size: Cell::new(0),
+                bitmap_scale: Cell::new(1.0),
+                hb_font: OnceCell::new(),
+                variations,
+                data,
+                library: self.clone(),
+            }),
+        })
+    }
+}
+
+/// The process wide unique identity of a loaded [`Face`].
+///
+/// Loading the same file twice gives two different ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FaceId(u32);
+
+impl FaceId {
+    fn next() -> Self {
+        static NEXT: AtomicU32 = AtomicU32::new(1);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+#[derive(Debug)]
+struct FaceInner {
+    pointer: NonNull<FT_FaceRec>,
+    id: FaceId,
+    /// The pixel size currently selected in FreeType, `0` for none.
+    pixel_size: Cell<u32>,
+    /// How much the bitmaps of the selected strike must be scaled to match `pixel_size`.
+    bitmap_scale: Cell<f32>,
+    /// Created on the first shaping, it reads `data` so it must be dropped first.
+    hb_font: OnceCell<HbFont>,
+    /// The axis coordinates FreeType was set to, empty for the default instance.
+    variations: Vec<Variation>,
+    data: Rc<[u8]>,
+    library: Library,
+}
+
+impl Drop for FaceInner {
+    fn drop(&mut self) {
+        // `data` and `library` are dropped after this, so they outlive the face.
+        unsafe {
+            FT_Done_Face(self.pointer.as_ptr());
+        }
+    }
+}
+
+/// Vertical metrics of a face at one pixel size, in pixels.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FontMetrics {
+    /// The distance from the baseline to the top of the line.
+    pub ascent: f32,
+    /// The distance from the baseline to the bottom of the line, positive.
+    pub descent: f32,
+    /// The extra space the font recommends between lines.
+    pub line_gap: f32,
+    /// The distance from the baseline to the top of the underline, positive is below.
+    pub underline_position: f32,
+    pub underline_thickness: f32,
+}
+
+impl FontMetrics {
+    /// The recommended distance between two baselines.
+    pub fn line_height(&self) -> f32 {
+        self.ascent + self.descent + self.line_gap
+    }
+
+    /// The element wise maximum, used for lines mixing several fonts.
+    pub fn max(self, other: Self) -> Self {
+        Self {
+            ascent: self.ascent.max(other.ascent),
+            descent: self.descent.max(other.descent),
+            line_gap: self.line_gap.max(other.line_gap),
+            underline_position: self.underline_position.max(other.underline_position),
+            underline_thickness: self.underline_thickness.max(other.underline_thickness),
+        }
+    }
+}
+
+/// A font face.
+///
+/// Cloning is cheap and the clones refer to the same face.
+#[derive(Debug, Clone)]
+pub struct Face {
+    inner: Rc<FaceInner>,
+}
+
+impl PartialEq for Face {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner.id == other.inner.id
+    }
+}
+
+impl Eq for Face {}
+
+impl Face {
+    pub fn get_pointer(&self) -> FT_Face {
+        self.inner.pointer.as_ptr()
+    }
+
+    fn raw(&self) -> &FT_FaceRec {
+        unsafe { &*self.get_pointer() }
+    }
+
+    pub fn id(&self) -> FaceId {
+        self.inner.id
+    }
+
+    pub fn library(&self) -> &Library {
+        &self.inner.library
+    }
+
+    /// The font file data backing this face.
+    pub fn data(&self) -> &Rc<[u8]> {
+        &self.inner.data
+    }
+
+    pub(crate) fn hb_font(&self) -> Result<&HbFont, TextError> {
+        if let Some(font) = self.inner.hb_font.get() {
+            return Ok(font);
+        }
+
+        let font = HbFont::new(&self.inner.data, self.face_index(), &self.inner.variations)?;
+        Ok(self.inner.hb_font.get_or_init(|| font))
+    }
+
+    /// Load this face again from the same data, as a new face with its own id.
+    ///
+    /// The variations are only recorded, they must be set in FreeType by the caller.
+    pub(crate) fn reload(&self, variations: Vec<Variation>) -> Result<Self, TextError> {
+        self.library()
+            .load_face(self.inner.data.clone(), self.face_index(), variations)
+    }
+
+    /// The variation axis coordinates of this face, empty for the default instance.
+    pub fn variations(&self) -> &[Variation] {
+        &self.inner.variations
+    }
+
+    /// The number of faces in the font file, more than one for collections.
+    pub fn num_faces(&self) -> u32 {
+        self.raw().num_faces.max(1) as u32
+    }
+
+    /// The index of this face inside its font file.
+    pub fn face_index(&self) -> u32 {
+        (self.raw().face_index & 0xFFFF) as u32
+    }
+
+    pub fn family_name(&self) -> Option<String> {
+        let name = self.raw().family_name;
+        if name.is_null() {
+            return None;
+        }
+        unsafe {
+            Some(
+                std::ffi::CStr::from_ptr(name)
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+        }
+    }
+
+    pub fn style_name(&self) -> Option<String> {
+        let name = self.raw().style_name;
+        if name.is_null() {
+            return None;
+        }
+        unsafe {
+            Some(
+                std::ffi::CStr::from_ptr(name)
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+        }
+    }
+
+    pub fn is_bold(&self) -> bool {
+        self.raw().style_flags & FT_STYLE_FLAG_BOLD != 0
+    }
+
+    pub fn is_italic(&self) -> bool {
+        self.raw().style_flags & FT_STYLE_FLAG_ITALIC != 0
+    }
+
+    /// The weight class, `400` is regular and `700` bold.
+    ///
+    /// Read from the `wght` axis of variable fonts and the `OS/2` table of
+    /// others, faces without one are `400` or `700` depending on their bold flag.
+    pub fn weight(&self) -> u16 {
+        if let Some(weight) = self.variation(b"wght") {
+            return weight.round().clamp(1.0, 1000.0) as u16;
+        }
+        // usWeightClass follows the version and xAvgCharWidth fields
+        match self.read_os2_u16(4) {
+            Some(weight @ 1..=1000) => weight,
+            _ if self.is_bold() => 700,
+            _ => 400,
+        }
+    }
+
+    /// The width class from `1` (ultra condensed) to `9` (ultra expanded), `5` is normal.
+    pub fn width_class(&self) -> u16 {
+        self.read_os2_u16(6)
+            .filter(|width| (1..=9).contains(width))
+            .unwrap_or(5)
+    }
+
+    fn read_os2_u16(&self, offset: FT_Long) -> Option<u16> {
+        let mut bytes = [0u8; 2];
+        let mut length = bytes.len() as FT_ULong;
+        let loaded = unsafe {
+            FT_Load_Sfnt_Table(
+                self.get_pointer(),
+                FT_ULong::from(u32::from_be_bytes(*b"OS/2")),
+                offset,
+                bytes.as_mut_ptr(),
+                &mut length,
+            )
+        };
+        (loaded == 0).then_some(u16::from_be_bytes(bytes))
+    }
+
+    /// A string of the `name` table, like a family name or the name of a named instance.
+    ///
+    /// American English is preferred when the font has several languages.
+    pub fn sfnt_name(&self, name_id: u16) -> Option<String> {
+        let count = unsafe { FT_Get_Sfnt_Name_Count(self.get_pointer()) };
+        let mut best: Option<(u8, String)> = None;
+
+        for index in 0..count {
+            let mut name = FT_SfntName {
+                platform_id: 0,
+                encoding_id: 0,
+                language_id: 0,
+                name_id: 0,
+                string: std::ptr::null_mut(),
+                string_len: 0,
+            };
+            if unsafe { FT_Get_Sfnt_Name(self.get_pointer(), index, &mut name) } != 0
+                || name.name_id != name_id
+                || name.string.is_null()
+            {
+                continue;
+            }
+
+            let bytes =
+                unsafe { std::slice::from_raw_parts(name.string, name.string_len as usize) };
+            let utf16 = || {
+                let units: Vec<u16> = bytes
+                    .as_chunks::<2>()
+                    .0
+                    .iter()
+                    .map(|&pair| u16::from_be_bytes(pair))
+                    .collect();
+                String::from_utf16_lossy(&units)
+            };
+            // Windows English, other Windows languages, Unicode, then Mac Roman read as Latin-1
+            let (rank, string) = match (name.platform_id, name.encoding_id, name.language_id) {
+                (3, 1 | 10, 0x409) => (0, utf16()),
+                (3, 1 | 10, _) => (1, utf16()),
+                (0, _, _) => (2, utf16()),
+                (1, 0, _) => (3, bytes.iter().map(|&byte| char::from(byte)).collect()),
+                _ => continue,
+            };
+            if best.as_ref().is_none_or(|(best_rank, _)| rank < *best_rank) {
+                best = Some((rank, string));
+            }
+        }
+
+        best.map(|(_, string)| string)
+    }
+
+    /// Whether every glyph has the same advance.
+    pub fn is_monospace(&self) -> bool {
+        self.raw().face_flags & FT_FACE_FLAG_FIXED_WIDTH != 0
+    }
+
+    pub fn is_scalable(&self) -> bool {
+        self.raw().face_flags & FT_FACE_FLAG_SCALABLE != 0
+    }
+
+    /// Whether the face contains color glyphs (emoji and alike).
+    pub fn has_color(&self) -> bool {
+        self.raw().face_flags & FT_FACE_FLAG_COLOR != 0
+    }
+
+    pub fn num_glyphs(&self) -> u32 {
+        self.raw().num_glyphs as u32
+    }
+
+    pub fn units_per_em(&self) -> u16 {
+        self.raw().units_per_EM
+    }
+
+    /// Map a character to a glyph id, `None` if the face does not cover it.
+    pub fn glyph_index(&self, ch: char) -> Option<u32> {
+        let index = unsafe { FT_Get_Char_Index(self.get_pointer(), ch as u32 as _) };
+        (index != 0).then_some(index)
+    }
+
+    /// The pixel size currently selected, `0` if no size has been set yet.
+    pub fn pixel_size(&self) -> u32 {
+        self.inner.pixel_size.get()
+    }
+
+    /// The factor that glyph bitmaps of the current size must be scaled by.
+    ///
+    /// It is `1.0` except for bitmap only faces, whose strikes rarely match the
+    /// requested size exactly.
+    pub fn bitmap_scale(&self) -> f32 {
+        self.inner.bitmap_scale.get()
+    }
+
+    /// The vertical metrics at the given pixel size.
+    pub fn metrics(&self, pixel_size: u32) -> Result<FontMetrics, TextError> {
+        let raw = self.raw();
+
+        if raw.units_per_EM != 0 && self.is_scalable() {
+            let scale = pixel_size as f32 / raw.units_per_EM as f32;
+            let ascent = raw.ascender as f32 * scale;
+            let descent = -(raw.descender as f32) * scale;
+
+            return Ok(FontMetrics {
+                ascent,
+                descent,
+                line_gap: (raw.height as f32 * scale - ascent - descent).max(0.0),
+                underline_position: -(raw.underline_position as f32) * scale,
+                underline_thickness: (raw.underline_thickness as f32 * scale).max(1.0),
+            });
+        }
+
+        // bitmap only faces have no design units, use the metrics of the strike
+        self.set_pixel_size(pixel_size)?;
+        let raw = self.raw();
+        if raw.size.is_null() {
+            // FT_Err_Invalid_Size_Handle
+            return Err(TextError::FreeType {
+                code: 0x24,
+                context: "reading size metrics",
+            });
+        }
+
+        let metrics = unsafe { (*raw.size).metrics };
+        let scale = self.bitmap_scale() / 64.0;
+        let ascent = metrics.ascender as f32 * scale;
+        let descent = -(metrics.descender as f32) * scale;
+
+        Ok(FontMetrics {
+            ascent,
+            descent,
+            line_gap: (metrics.height as f32 * scale - ascent - descent).max(0.0),
+            underline_position: descent / 2.0,
+            underline_thickness: (pixel_size as f32 / 14.0).max(1.0),
+        })
+    }
+
+    /// Select the pixel size (the em height in pixels) that glyphs are loaded with.
+    ///
+    /// Bitmap only faces (like CBDT emoji fonts) select the closest available strike.
+    pub fn set_pixel_size(&self, pixel_size: u32) -> Result<(), TextError> {
+        if self.inner.pixel_size.get() == pixel_size {
+            return Ok(());
+        }
+
+        let raw = self.raw();
+
+        unsafe {
+            if raw.face_flags & FT_FACE_FLAG_SCALABLE == 0
+                && raw.face_flags & FT_FACE_FLAG_FIXED_SIZES != 0
+                && raw.num_fixed_sizes > 0
+            {
+                let sizes =
+                    std::slice::from_raw_parts(raw.available_sizes, raw.num_fixed_sizes as usize);
+                let wanted = pixel_size as FT_Pos * 64;
+                let best = sizes
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, size)| (size.y_ppem - wanted).abs())
+                    .map(|(index, _)| index)
+                    .unwrap_or(0);
+
+                TextError::check_freetype(
+                    FT_Select_Size(self.get_pointer(), best as i32),
+                    "selecting bitmap strike",
+                )?;
+
+                let strike_size = sizes[best].y_ppem as f32 / 64.0;
+                self.inner.bitmap_scale.set(if strike_size > 0.0 {
+                    pixel_size as f32 / strike_size
+                } else {
+                    1.0
+                });
+            } else {
+                TextError::check_freetype(
+                    FT_Set_Pixel_Sizes(self.get_pointer(), 0, pixel_size),
+                    "setting pixel size",
+                )?;
+
+                self.inner.bitmap_scale.set(1.0);
+            }
+        }
+
+        self.inner.pixel_size.set(pixel_size);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_font::{SPACE, SQUARE, TestFont};
+
+    #[test]
+    fn loads_faces() -> Result<(), TextError> {
+        let library = Library::new()?;
+        let face = library.load_face_from_memory(TestFont::new().build(), 0)?;
+        assert_eq!((face.num_faces(), face.face_index()), (1, 0));
+        assert_eq!((face.num_glyphs(), face.units_per_em()), (4, 1000));
+        assert!(face.is_scalable() && !face.has_color());
+        assert_eq!(face.glyph_index('a'), Some(SQUARE));
+        assert_eq!(face.glyph_index(' '), Some(SPACE));
+        assert_eq!(face.glyph_index('A'), None);
+
+        // faces keep their identity through clones only
+        let other = library.load_face_from_memory(TestFont::new().build(), 0)?;
+        assert_eq!(face.clone(), face);
+        assert_ne!(face, other);
+
+        assert!(matches!(
+            library.load_face_from_memory(TestFont::new().build(), 1),
+            Err(TextError::FreeType { .. })
+        ));
+        assert!(matches!(
+            library.load_face_from_memory(vec![0; 64], 0),
+            Err(TextError::FreeType { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn loads_faces_from_files() -> Result<(), TextError> {
+        let path = std::env::temp_dir().join(format!("wscb-font-{}.ttf", std::process::id()));
+        std::fs::write(&path, TestFont::new().build()).map_err(|source| TextError::Io {
+            path: path.clone(),
+            source,
+        })?;
+
+        let library = Library::new()?;
+        let loaded = library.load_face_from_file(&path, 0);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(loaded?.glyph_index('z'), Some(SQUARE));
+
+        assert!(matches!(
+            library.load_face_from_file(&path, 0),
+            Err(TextError::Io { path: missing, .. }) if missing == path
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn scales_to_pixel_sizes() -> Result<(), TextError> {
+        let face = TestFont::new().face()?;
+        assert_eq!(face.pixel_size(), 0);
+        face.set_pixel_size(16)?;
+        assert_eq!((face.pixel_size(), face.bitmap_scale()), (16, 1.0));
+
+        let metrics = face.metrics(10)?;
+        assert_eq!((metrics.ascent, metrics.descent), (8.0, 2.0));
+        assert_eq!(metrics.line_height(), 10.0);
+        assert_eq!(face.metrics(20)?.line_height(), 20.0);
+        Ok(())
+    }
+}
@@ -0,0 +1,431 @@
+use ::wscb_atlas::{AtlasManager, TextureHandle};
+use ::wscb_sdl::graph::Renderer;
+use ::wscb_type::{
+    HashMap, HashSet,
+    graph::{Point, PointUnit, Size},
+    graph_f,
+};
+
+use crate::{
+    effect::GlyphEffect,
+    error::TextError,
+    font::{Face, FaceId},
+    raster::{GLYPH_PIXEL_FORMAT, GlyphBitmap, GlyphFormat, Hinting, RasterOptions, RenderMode},
+};
+
+/// How many horizontal positions inside a pixel a glyph is rasterized at.
+pub const SUBPIXEL_STEPS: u8 = 4;
+
+/// Identifies one rasterization of a glyph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GlyphKey {
+    pub face: FaceId,
+    pub pixel_size: u32,
+    pub glyph_id: u32,
+    /// The horizontal offset in `1 / SUBPIXEL_STEPS` pixels.
+    pub subpixel: u8,
+    pub mode: RenderMode,
+    pub hinting: Hinting,
+    /// The effect silhouette, `None` for the glyph itself.
+    pub effect: Option<GlyphEffect>,
+}
+
+impl GlyphKey {
+    /// The key of a glyph, color glyphs ignore the render mode and subpixel
+    /// position and share one entry.
+    fn glyph(
+        face: &Face,
+        pixel_size: u32,
+        glyph_id: u32,
+        subpixel: u8,
+        options: RasterOptions,
+        color: bool,
+    ) -> Self {
+        Self {
+            face: face.id(),
+            pixel_size,
+            glyph_id,
+            subpixel: if color { 0 } else { subpixel % SUBPIXEL_STEPS },
+            mode: if color {
+                RenderMode::Grayscale
+            } else {
+                options.mode
+            },
+            hinting: options.hinting,
+            effect: None,
+        }
+    }
+
+    fn effect(
+        face: &Face,
+        pixel_size: u32,
+        glyph_id: u32,
+        subpixel: u8,
+        hinting: Hinting,
+        effect: GlyphEffect,
+    ) -> Self {
+        Self {
+            face: face.id(),
+            pixel_size,
+            glyph_id,
+            subpixel: subpixel % SUBPIXEL_STEPS,
+            mode: RenderMode::Grayscale,
+            hinting,
+            effect: Some(effect),
+        }
+    }
+}
+
+/// A glyph stored in the atlas of a [`GlyphCache`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CachedGlyph {
+    /// The region in the atlas, `None` for glyphs without ink like spaces.
+    pub texture: Option<TextureHandle>,
+    /// Which atlas `texture` belongs to.
+    pub format: GlyphFormat,
+    /// The offset from the (whole pixel) pen position to the top left corner of the image.
+    pub bearing: Point,
+    /// The pen advance in pixels.
+    pub advance: graph_f::Point,
+}
+
+/// Rasterized glyphs, uploaded into atlases on first use.
+///
+/// Coverage glyphs and color glyphs are kept in separate atlases because
+/// they are drawn differently.
+pub struct GlyphCache {
+    mask_atlas: AtlasManager,
+    color_atlas: AtlasManager,
+    glyphs: HashMap<GlyphKey, CachedGlyph>,
+    /// The glyphs that rasterized in color, they are cached once for every
+    /// render mode and subpixel position.
+    color_glyphs: HashSet<(FaceId, u32)>,
+    padding: PointUnit,
+    segment_size: Size,
+}
+
+impl GlyphCache {
+    /// Create an empty cache whose atlases grow in segments of `segment_size`.
+    pub fn new(renderer: &mut Renderer, segment_size: Size) -> Result<Self, TextError> {
+        let padding = 1;
+
+        Ok(Self {
+            mask_atlas: AtlasManager::empty(renderer, padding, segment_size, GLYPH_PIXEL_FORMAT)?,
+            color_atlas: AtlasManager::empty(renderer, padding, segment_size, GLYPH_PIXEL_FORMAT)?,
+            glyphs: HashMap::default(),
+            color_glyphs: HashSet::default(),
+            padding,
+            segment_size,
+        })
+    }
+
+    /// Split a horizontal pen position into the whole pixel part and the subpixel step.
+    pub fn subpixel_position(x: f32) -> (f32, u8) {
+        let whole = x.floor();
+        let step = ((x - whole) * SUBPIXEL_STEPS as f32).round() as u8;
+
+        if step >= SUBPIXEL_STEPS {
+            (whole + 1.0, 0)
+        } else {
+            (whole, step)
+        }
+    }
+
+    /// Split a horizontal pen position like [`GlyphCache::subpixel_position`],
+    /// or round it to a whole pixel when `options` turn subpixel positioning off.
+    pub fn pen_position(x: f32, options: &RasterOptions) -> (f32, u8) {
+        if options.subpixel_positioning {
+            Self::subpixel_position(x)
+        } else {
+            (x.round(), 0)
+        }
+    }
+
+    /// Get a glyph, rasterizing and uploading it if it is not cached yet.
+    ///
+    /// `subpixel` is the step returned by [`GlyphCache::pen_position`].
+    /// Glyphs that rasterize in color ignore it and the render mode, they
+    /// are cached once.
+    pub fn get(
+        &mut self,
+        renderer: &mut Renderer,
+        face: &Face,
+        pixel_size: u32,
+        glyph_id: u32,
+        subpixel: u8,
+        options: RasterOptions,
+    ) -> Result<CachedGlyph, TextError> {
+        let color = self.color_glyphs.contains(&(face.id(), glyph_id));
+        let key = GlyphKey::glyph(face, pixel_size, glyph_id, subpixel, options, color);
+
+        if let Some(glyph) = self.glyphs.get(&key) {
+            return Ok(*glyph);
+        }
+
+        let mut bitmap = face.rasterize_glyph_at(
+            glyph_id,
+            pixel_size,
+            options,
+            key.subpixel as f32 / SUBPIXEL_STEPS as f32,
+        )?;
+        if bitmap.format == GlyphFormat::Color && !color {
+            // only known now, the glyph goes under the key every mode shares
+            self.color_glyphs.insert((face.id(), glyph_id));
+            if key.subpixel != 0 {
+                bitmap = face.rasterize_glyph_at(glyph_id, pixel_size, options, 0.0)?;
+            }
+            let key = GlyphKey::glyph(face, pixel_size, glyph_id, 0, options, true);
+            return self.insert(renderer, key, bitmap);
+        }
+        self.insert(renderer, key, bitmap)
+    }
+
+    /// Get the silhouette of a glyph for an effect layer, like [`GlyphCache::get`].
+    ///
+    /// Every combination of effect parameters is cached separately, always
+    /// in the mask atlas. Silhouettes are always grayscale, `hinting` keeps
+    /// them the same shape as the glyphs they are drawn behind.
+    #[allow(clippy::too_many_arguments)]
+    pub fn get_effect(
+        &mut self,
+        renderer: &mut Renderer,
+        face: &Face,
+        pixel_size: u32,
+        glyph_id: u32,
+        subpixel: u8,
+        hinting: Hinting,
+        effect: GlyphEffect,
+    ) -> Result<CachedGlyph, TextError> {
+        let key = GlyphKey::effect(face, pixel_size, glyph_id, subpixel, hinting, effect);
+
+        if let Some(glyph) = self.glyphs.get(&key) {
+            return Ok(*glyph);
+        }
+
+        let bitmap = face.rasterize_glyph_effect(
+            glyph_id,
+            pixel_size,
+            key.subpixel as f32 / SUBPIXEL_STEPS as f32,
+            hinting,
+            effect,
+        )?;
+        self.insert(renderer, key, bitmap)
+    }
+
+    fn insert(
+        &mut self,
+        renderer: &mut Renderer,
+        key: GlyphKey,
+        bitmap: GlyphBitmap,
+    ) -> Result<CachedGlyph, TextError> {
+        let texture = match &bitmap.surface {
+            Some(surface) => {
+                let atlas = match bitmap.format {
+                    GlyphFormat::Mask => &mut self.mask_atlas,
+                    GlyphFormat::Color => &mut self.color_atlas,
+                };
+                Some(atlas.allocate_then_copy_surface(renderer, surface, None)?)
+            }
+            None => None,
+        };
+
+        let glyph = CachedGlyph {
+            texture,
+            format: bitmap.format,
+            bearing: bitmap.bearing,
+            advance: bitmap.advance,
+        };
+
+        self.glyphs.insert(key, glyph);
+        Ok(glyph)
+    }
+
+    /// The atlas holding the glyphs of the given format.
+    pub fn atlas(&self, format: GlyphFormat) -> &AtlasManager {
+        match format {
+            GlyphFormat::Mask => &self.mask_atlas,
+            GlyphFormat::Color => &self.color_atlas,
+        }
+    }
+
+    /// The number of cached glyphs.
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+
+    /// Drop every cached glyph and start over with fresh atlases.
+    ///
+    /// Atlas space can not be freed piece by piece, so this is the way to
+    /// reclaim it after fonts are unloaded.
+    pub fn clear(&mut self, renderer: &mut Renderer) -> Result<(), TextError> {
+        self.mask_atlas = AtlasManager::empty(
+            renderer,
+            self.padding,
+            self.segment_size,
+            GLYPH_PIXEL_FORMAT,
+        )?;
+        self.color_atlas = AtlasManager::empty(
+            renderer,
+            self.padding,
+            self.segment_size,
+            GLYPH_PIXEL_FORMAT,
+        )?;
+        self.glyphs.clear();
+        self.color_glyphs.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::sdl3_sys::render::SDL_CreateSoftwareRenderer;
+    use ::wscb_sdl::graph::Surface;
+    use ::wscb_type::{color::Color, error::SdlError};
+
+    use super::*;
+    use crate::{
+        effect::TextEffect,
+        test_font::{SPACE, SQUARE, TestFont},
+    };
+
+    /// A software renderer drawing into a surface, the surface has to outlive it.
+    fn renderer() -> Result<(Surface, Renderer), TextError> {
+        let surface = Surface::new(64, 64, GLYPH_PIXEL_FORMAT)?;
+        let renderer =
+            unsafe { Renderer::from_raw(SDL_CreateSoftwareRenderer(surface.get_pointer())) }
+                .ok_or_else(|| SdlError::sdl_err("failed to create renderer"))?;
+        Ok((surface, renderer))
+    }
+
+    #[test]
+    fn uploads_glyphs_once() -> Result<(), TextError> {
+        let (_surface, mut renderer) = renderer()?;
+        let mut cache = GlyphCache::new(&mut renderer, Size::new(64, 64))?;
+        let face = TestFont::new().face()?;
+        let options = RasterOptions::smooth();
+
+        let square = cache.get(&mut renderer, &face, 10, SQUARE, 0, options)?;
+        assert_eq!(cache.len(), 1);
+        assert_eq!(square.format, GlyphFormat::Mask);
+        assert_eq!(square.bearing, Point::new(0, -8));
+        let handle = square.texture.map(|handle| handle.rect.size);
+        assert_eq!(handle, Some(Size::new(10, 10)));
+        if let Some(handle) = &square.texture {
+            let texture = cache.atlas(GlyphFormat::Mask).get_texture(handle);
+            assert_eq!(texture.size()?, graph_f::Size::new(64.0, 64.0));
+        }
+
+        // a hit gives the same region back without uploading again
+        assert_eq!(
+            cache.get(&mut renderer, &face, 10, SQUARE, 0, options)?,
+            square
+        );
+        assert_eq!(cache.len(), 1);
+
+        // another subpixel position is another rasterization in another region
+        let shifted = cache.get(&mut renderer, &face, 10, SQUARE, 2, options)?;
+        assert_eq!(cache.len(), 2);
+        assert!(shifted.texture.is_some());
+        assert_ne!(shifted.texture, square.texture);
+
+        // spaces are cached without a region
+        let space = cache.get(&mut renderer, &face, 10, SPACE, 0, options)?;
+        assert_eq!((space.texture, space.advance.x), (None, 5.0));
+        assert_eq!(cache.len(), 3);
+
+        cache.clear(&mut renderer)?;
+        assert!(cache.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn snaps_pen_positions() {
+        let smooth = RasterOptions::smooth();
+        assert_eq!(GlyphCache::pen_position(10.3, &smooth), (10.0, 1));
+        assert_eq!(GlyphCache::pen_position(10.9, &smooth), (11.0, 0));
+        assert_eq!(
+            GlyphCache::pen_position(10.6, &RasterOptions::crisp()),
+            (11.0, 0)
+        );
+    }
+
+    #[test]
+    fn keeps_effects_apart_from_glyphs() -> Result<(), TextError> {
+        let (_surface, mut renderer) = renderer()?;
+        let mut cache = GlyphCache::new(&mut renderer, Size::new(64, 64))?;
+        let face = TestFont::new().face()?;
+        let options = RasterOptions::smooth();
+
+        // a hard shadow is the glyph itself in grayscale, under its own key
+        let shadow = TextEffect::shadow(graph_f::Point::new(1.0, 1.0), 0, Color::BLACK);
+        assert_eq!(shadow.effect, GlyphEffect::default());
+        let key = GlyphKey::effect(&face, 16, SQUARE, 1, options.hinting, shadow.effect);
+        assert_ne!(key, GlyphKey::glyph(&face, 16, SQUARE, 1, options, false));
+
+        let glyph = cache.get(&mut renderer, &face, 16, SQUARE, 1, options)?;
+        let get_shadow = |cache: &mut GlyphCache, renderer: &mut Renderer| {
+            cache.get_effect(
+                renderer,
+                &face,
+                16,
+                SQUARE,
+                1,
+                options.hinting,
+                shadow.effect,
+            )
+        };
+        let effect = get_shadow(&mut cache, &mut renderer)?;
+        assert_eq!(cache.len(), 2);
+        assert_eq!(
+            (glyph.format, effect.format),
+            (GlyphFormat::Mask, GlyphFormat::Mask)
+        );
+        assert!(glyph.texture.is_some() && effect.texture.is_some());
+        assert_ne!(
+            glyph.texture.map(|handle| handle.rect),
+            effect.texture.map(|handle| handle.rect)
+        );
+
+        // the second lookup is a hit
+        assert_eq!(get_shadow(&mut cache, &mut renderer)?, effect);
+        assert_eq!(cache.len(), 2);
+
+        // an outline is another silhouette
+        let outline = TextEffect::outline(1.0, Color::BLACK).effect;
+        cache.get_effect(
+            &mut renderer,
+            &face,
+            16,
+            SQUARE,
+            1,
+            options.hinting,
+            outline,
+        )?;
+        assert_eq!(cache.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn shares_color_keys_between_modes() -> Result<(), TextError> {
+        let face = TestFont::new().face()?;
+        let smooth = RasterOptions::smooth();
+        let crisp = RasterOptions {
+            mode: RenderMode::Mono,
+            ..smooth
+        };
+
+        assert_ne!(
+            GlyphKey::glyph(&face, 16, SQUARE, 1, crisp, false),
+            GlyphKey::glyph(&face, 16, SQUARE, 3, smooth, false)
+        );
+        assert_eq!(
+            GlyphKey::glyph(&face, 16, SQUARE, 1, crisp, true),
+            GlyphKey::glyph(&face, 16, SQUARE, 3, smooth, true)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,467 @@
+//! Mapping between points of a [`Layout`] and byte offsets of its text.
+//!
+//! Carets stand between clusters. One byte offset can be shown at two places:
+//! at the end of a wrapped line and the start of the next one, or on both
+//! sides of a bidi run. The [`Affinity`] of a caret says which character it
+//! sticks to and so where it is drawn.
+
+use ::std::ops::Range;
+
+use ::wscb_type::{HashMap, graph_f};
+
+use crate::{
+    layout::{Layout, Line},
+    segment,
+};
+
+/// The character a caret at a byte offset belongs to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Affinity {
+    /// The character before the offset, the caret is drawn at its trailing edge.
+    Upstream,
+    /// The character after the offset, the caret is drawn at its leading edge.
+    #[default]
+    Downstream,
+}
+
+/// The result of [`Layout::hit_test`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HitTest {
+    /// The caret position nearest to the point.
+    pub offset: usize,
+    pub affinity: Affinity,
+    /// The byte range of the grapheme under the point, or the nearest one on
+    /// its line. Empty on empty lines.
+    pub grapheme: Range<usize>,
+    /// Whether the point is over the grapheme and not beside the text.
+    pub inside: bool,
+    /// The index of the line nearest to the point.
+    pub line: usize,
+}
+
+/// The start and end of a rectangle along the lines of a layout.
+fn inline_span(vertical: bool, rect: &graph_f::Rect) -> (f32, f32) {
+    if vertical {
+        (rect.position.y, rect.position.y + rect.size.height)
+    } else {
+        (rect.position.x, rect.position.x + rect.size.width)
+    }
+}
+
+/// The start and end of a rectangle across the lines of a layout.
+fn cross_span(vertical: bool, rect: &graph_f::Rect) -> (f32, f32) {
+    if vertical {
+        (rect.position.x, rect.position.x + rect.size.width)
+    } else {
+        (rect.position.y, rect.position.y + rect.size.height)
+    }
+}
+
+/// The part of a cluster's span that covers some of its bytes.
+///
+/// Clusters of several graphemes, like ligatures, are split evenly by bytes.
+fn sub_span(
+    cluster: &Range<usize>,
+    level: u8,
+    (start, end): (f32, f32),
+    bytes: &Range<usize>,
+) -> (f32, f32) {
+    let len = cluster.len().max(1) as f32;
+    let from = (bytes.start.clamp(cluster.start, cluster.end) - cluster.start) as f32 / len;
+    let to = (bytes.end.clamp(cluster.start, cluster.end) - cluster.start) as f32 / len;
+    let width = end - start;
+    if level % 2 == 1 {
+        (end - width * to, end - width * from)
+    } else {
+        (start + width * from, start + width * to)
+    }
+}
+
+impl Layout {
+    /// The index of the line nearest to a point, measured across the lines.
+    pub fn line_at_point(&self, point: graph_f::Point) -> Option<usize> {
+        let vertical = self.writing_mode().is_vertical();
+        let across = if vertical { point.x } else { point.y };
+
+        self.lines()
+            .iter()
+            .enumerate()
+            .map(|(index, line)| {
+                let (start, end) = cross_span(vertical, &line.rect);
+                let distance = (start - across).max(across - end).max(0.0);
+                (index, distance)
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, _)| index)
+    }
+
+    /// The index of the line a caret is drawn on.
+    ///
+    /// An upstream caret at the end of a wrapped line stays on that line.
+    pub fn line_at_caret(&self, offset: usize, affinity: Affinity) -> Option<usize> {
+        let index = self.line_at_offset(offset)?;
+        let previous = index.checked_sub(1).map(|previous| &self.lines()[previous]);
+        match previous {
+            Some(line)
+                if affinity == Affinity::Upstream
+                    && !line.hard_break
+                    && line.range.end == offset
+                    && !line.range.is_empty() =>
+            {
+                Some(index - 1)
+            }
+            _ => Some(index),
+        }
+    }
+
+    /// Find the caret position and the grapheme at a point of the layout.
+    ///
+    /// Points before or after a line go to its visual ends, points above or
+    /// below the text to the first or last line. `text` is the text the layout
+    /// was made of. Returns `None` for a layout without lines.
+    pub fn hit_test(&self, text: &str, point: graph_f::Point) -> Option<HitTest> {
+        let line_index = self.line_at_point(point)?;
+        let line = &self.lines()[line_index];
+        let vertical = self.writing_mode().is_vertical();
+        let along = if vertical { point.y } else { point.x };
+
+        let clusters = self.caret_clusters(line);
+        let cluster_spans = self.cluster_spans(line);
+        let spans: Vec<(f32, f32)> = clusters
+            .iter()
+            .map(|(range, _)| span_of(&cluster_spans, range.start))
+            .collect();
+
+        // the cluster under the point, or the nearest one at the ends of the line
+        let hit = spans
+            .iter()
+            .position(|&(_, end)| along < end)
+            .or_else(|| spans.len().checked_sub(1));
+        let Some(hit) = hit else {
+            return Some(HitTest {
+                offset: line.range.start,
+                affinity: Affinity::Downstream,
+                grapheme: line.range.start..line.range.start,
+                inside: false,
+                line: line_index,
+            });
+        };
+
+        let (range, level) = &clusters[hit];
+        let span = spans[hit];
+        let inside = along >= span.0 && along < span.1 && self.is_inside_across(line, point);
+
+        // graphemes of a ligature share its advance evenly
+        let graphemes: Vec<(Range<usize>, (f32, f32))> = text
+            .get(range.clone())
+            .into_iter()
+            .flat_map(segment::graphemes)
+            .map(|grapheme| {
+                let grapheme = grapheme.start + range.start..grapheme.end + range.start;
+                let grapheme_span = sub_span(range, *level, span, &grapheme);
+                (grapheme, grapheme_span)
+            })
+            .collect();
+        let (grapheme, (start, end)) = graphemes
+            .iter()
+            .find(|(_, (start, end))| along >= *start && along < *end)
+            .or_else(|| {
+                graphemes.iter().min_by(|(_, (a, _)), (_, (b, _))| {
+                    (a - along).abs().total_cmp(&(b - along).abs())
+                })
+            })
+            .cloned()
+            .unwrap_or((range.clone(), span));
+
+        // the half of the grapheme the point is in picks its edge
+        let before = along < (start + end) / 2.0;
+        let (offset, affinity) = if before != (level % 2 == 1) {
+            (grapheme.start, Affinity::Downstream)
+        } else {
+            (grapheme.end, Affinity::Upstream)
+        };
+
+        Some(HitTest {
+            offset,
+            affinity,
+            grapheme,
+            inside,
+            line: line_index,
+        })
+    }
+
+    fn is_inside_across(&self, line: &Line, point: graph_f::Point) -> bool {
+        let vertical = self.writing_mode().is_vertical();
+        let (start, end) = cross_span(vertical, &line.rect);
+        let across = if vertical { point.x } else { point.y };
+        across >= start && across < end
+    }
+
+    /// The rectangle of a caret at a byte offset, one pixel wide and as high as its line.
+    ///
+    /// In vertical layouts the caret lies across its column.
+    pub fn caret_rect(&self, offset: usize, affinity: Affinity) -> Option<graph_f::Rect> {
+        let line = &self.lines()[self.line_at_caret(offset, affinity)?];
+        let vertical = self.writing_mode().is_vertical();
+        let clusters = self.caret_clusters(line);
+        let spans = self.cluster_spans(line);
+
+        let leading = clusters.iter().find(|(range, _)| range.start == offset);
+        let inside = clusters
+            .iter()
+            .find(|(range, _)| range.start < offset && offset < range.end);
+        let trailing = clusters.iter().find(|(range, _)| range.end == offset);
+
+        let leading_edge = |(range, level): &(Range<usize>, u8)| {
+            let (start, end) = span_of(&spans, range.start);
+            if level % 2 == 1 { end } else { start }
+        };
+        let trailing_edge = |(range, level): &(Range<usize>, u8)| {
+            let (start, end) = span_of(&spans, range.start);
+            if level % 2 == 1 { start } else { end }
+        };
+        // a caret inside a ligature
+        let inside_edge = |(range, level): &(Range<usize>, u8)| {
+            let span = span_of(&spans, range.start);
+            let (start, end) = sub_span(range, *level, span, &(range.start..offset));
+            if level % 2 == 1 { start } else { end }
+        };
+
+        let edge = match affinity {
+            Affinity::Downstream => leading
+                .map(leading_edge)
+                .or_else(|| inside.map(inside_edge))
+                .or_else(|| trailing.map(trailing_edge)),
+            Affinity::Upstream => trailing
+                .map(trailing_edge)
+                .or_else(|| inside.map(inside_edge))
+                .or_else(|| leading.map(leading_edge)),
+        };
+        let edge = edge.unwrap_or_else(|| {
+            let (start, end) = inline_span(vertical, &line.rect);
+            if line.level % 2 == 1 { end } else { start }
+        });
+
+        Some(if vertical {
+            graph_f::Rect::new(line.rect.position.x, edge, line.rect.size.width, 1.0)
+        } else {
+            graph_f::Rect::new(edge, line.rect.position.y, 1.0, line.rect.size.height)
+        })
+    }
+
+    /// The rectangles covering a byte range, to draw a selection behind the text.
+    ///
+    /// There is one rectangle for every visually continuous piece on a line,
+    /// so a selection through bidi text can have several on one line. They are
+    /// as high as their lines, in vertical layouts as wide as their columns.
+    pub fn selection_rects(&self, range: Range<usize>) -> Vec<graph_f::Rect> {
+        let vertical = self.writing_mode().is_vertical();
+        let mut rects = Vec::new();
+        if range.is_empty() {
+            return rects;
+        }
+
+        for line in self.lines() {
+            if line.range.end <= range.start {
+                continue;
+            }
+            if line.range.start >= range.end {
+                break;
+            }
+
+            let spans = self.cluster_spans(line);
+            let mut pieces: Vec<(f32, f32)> = Vec::new();
+            for (cluster, level) in self.visual_clusters(line) {
+                if cluster.end <= range.start || cluster.start >= range.end {
+                    continue;
+                }
+                let span = span_of(&spans, cluster.start);
+                let (start, end) = sub_span(&cluster, level, span, &range);
+                match pieces.last_mut() {
+                    Some((_, last_end)) if (*last_end - start).abs() < 0.5 => *last_end = end,
+                    _ => pieces.push((start, end)),
+                }
+            }
+
+            let (across_start, across_end) = cross_span(vertical, &line.rect);
+            let thickness = across_end - across_start;
+            for (start, end) in pieces.into_iter().filter(|(start, end)| end > start) {
+                rects.push(if vertical {
+                    graph_f::Rect::new(across_start, start, thickness, end - start)
+                } else {
+                    graph_f::Rect::new(start, across_start, end - start, thickness)
+                });
+            }
+        }
+
+        rects
+    }
+
+    /// The clusters of a line a caret can stand before, in visual order.
+    ///
+    /// The line separator of a paragraph is left out, a caret never stands after it.
+    fn caret_clusters(&self, line: &Line) -> Vec<(Range<usize>, u8)> {
+        let mut clusters = self.visual_clusters(line);
+        let is_last = self
+            .lines()
+            .last()
+            .is_some_and(|last| ::std::ptr::eq(last, line));
+        if line.hard_break && !is_last {
+            // the separator is the last cluster in logical order
+            if let Some(separator) = clusters.iter().map(|(range, _)| range.start).max() {
+                clusters.retain(|(range, _)| range.start != separator);
+                for (range, _) in &mut clusters {
+                    range.end = range.end.min(separator);
+                }
+            }
+        }
+        clusters
+    }
+
+    /// The start and end along the line of the glyphs of every cluster, by cluster start.
+    fn cluster_spans(&self, line: &Line) -> HashMap<usize, (f32, f32)> {
+        let vertical = self.writing_mode().is_vertical();
+        let mut spans: HashMap<usize, (f32, f32)> = HashMap::default();
+        for glyph in self.line_glyphs(line) {
+            let (start, end) = inline_span(vertical, &glyph.rect);
+            spans
+                .entry(glyph.cluster)
+                .and_modify(|span| *span = (span.0.min(start), span.1.max(end)))
+                .or_insert((start, end));
+        }
+        spans
+    }
+}
+
+/// The span of the cluster starting at `cluster`, from [`Layout::cluster_spans`].
+fn span_of(spans: &HashMap<usize, (f32, f32)>, cluster: usize) -> (f32, f32) {
+    spans.get(&cluster).copied().unwrap_or((0.0, 0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        collection::FontCollection, error::TextError, layout::LayoutOptions, shape::ShapeOptions,
+        test_font::TestFont,
+    };
+
+    /// Lay out text with the test font at 10 pixels, letters are 10 pixels
+    /// wide, spaces 5 and lines 10 high.
+    fn layout(text: &str, max_width: Option<f32>) -> Result<Layout, TextError> {
+        let fonts = FontCollection::new([TestFont::new().face()?]);
+        let runs = fonts.shape(text, 10, &ShapeOptions::default())?;
+        let options = LayoutOptions {
+            max_width,
+            ..LayoutOptions::default()
+        };
+        Layout::new(text, &runs, &options)
+    }
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> graph_f::Rect {
+        graph_f::Rect::new(x, y, width, height)
+    }
+
+    #[test]
+    fn splits_ligatures_by_bytes() {
+        assert_eq!(sub_span(&(0..4), 0, (10.0, 30.0), &(1..3)), (15.0, 25.0));
+        assert_eq!(sub_span(&(0..4), 1, (10.0, 30.0), &(0..1)), (25.0, 30.0));
+        assert_eq!(sub_span(&(4..8), 0, (10.0, 30.0), &(0..100)), (10.0, 30.0));
+    }
+
+    #[test]
+    fn hits_the_nearest_caret() -> Result<(), TextError> {
+        let text = "abc";
+        let layout = layout(text, None)?;
+        let hit = |x, y| layout.hit_test(text, graph_f::Point::new(x, y));
+
+        assert_eq!(
+            hit(12.0, 5.0),
+            Some(HitTest {
+                offset: 1,
+                affinity: Affinity::Downstream,
+                grapheme: 1..2,
+                inside: true,
+                line: 0,
+            })
+        );
+        let after_middle = hit(18.0, 5.0);
+        assert_eq!(
+            after_middle.map(|hit| (hit.offset, hit.affinity)),
+            Some((2, Affinity::Upstream))
+        );
+
+        // beside and below the text the ends of the line are hit
+        let beside = hit(100.0, 5.0);
+        assert_eq!(
+            beside.map(|hit| (hit.offset, hit.grapheme, hit.inside)),
+            Some((3, 2..3, false))
+        );
+        let below = hit(2.0, 50.0);
+        assert_eq!(
+            below.map(|hit| (hit.offset, hit.inside, hit.line)),
+            Some((0, false, 0))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn keeps_upstream_carets_on_wrapped_lines() -> Result<(), TextError> {
+        let layout = layout("ab cd", Some(25.0))?;
+        assert_eq!(layout.lines().len(), 2);
+        assert_eq!(layout.lines()[0].range, 0..3);
+
+        // the offset between the lines is drawn after the space or before `c`
+        assert_eq!(
+            layout.caret_rect(3, Affinity::Upstream),
+            Some(rect(25.0, 0.0, 1.0, 10.0))
+        );
+        assert_eq!(
+            layout.caret_rect(3, Affinity::Downstream),
+            Some(rect(0.0, 10.0, 1.0, 10.0))
+        );
+        assert_eq!(layout.line_at_caret(3, Affinity::Upstream), Some(0));
+        assert_eq!(
+            layout.caret_rect(5, Affinity::Upstream),
+            Some(rect(20.0, 10.0, 1.0, 10.0))
+        );
+
+        // a hit after the end of the first line sticks to it
+        let hit = layout.hit_test("ab cd", graph_f::Point::new(40.0, 5.0));
+        assert_eq!(
+            hit.map(|hit| (hit.offset, hit.affinity, hit.line)),
+            Some((3, Affinity::Upstream, 0))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn selects_across_bidi_runs() -> Result<(), TextError> {
+        // `אב` takes bytes 3..7 and is drawn reversed at 25..45
+        let text = "ab אב cd";
+        let layout = layout(text, None)?;
+
+        // `b`, the space and `א`, but not `ב` between them
+        assert_eq!(
+            layout.selection_rects(1..5),
+            [rect(10.0, 0.0, 15.0, 10.0), rect(35.0, 0.0, 10.0, 10.0)]
+        );
+        assert_eq!(
+            layout.selection_rects(0..text.len()),
+            [rect(0.0, 0.0, 70.0, 10.0)]
+        );
+        assert!(layout.selection_rects(2..2).is_empty());
+
+        // the caret after `א` is on its left, inside the right to left run
+        assert_eq!(
+            layout.caret_rect(5, Affinity::Upstream),
+            Some(rect(35.0, 0.0, 1.0, 10.0))
+        );
+        let hit = layout.hit_test(text, graph_f::Point::new(37.0, 5.0));
+        assert_eq!(
+            hit.map(|hit| (hit.offset, hit.affinity, hit.grapheme)),
+            Some((5, Affinity::Upstream, 3..5))
+        );
+        Ok(())
+    }
+}
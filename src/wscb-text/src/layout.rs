@@ -0,0 +1,957 @@
+use ::std::ops::Range;
+
+use ::unicode_linebreak::{BreakOpportunity, linebreaks};
+use ::wscb_type::{HashMap, graph_f};
+
+use crate::{
+    bidi::{BidiText, reorder},
+    error::TextError,
+    font::{Face, FontMetrics},
+    segment::{is_grapheme_boundary, is_line_separator},
+    shape::{Direction, ShapedGlyph, ShapedRun},
+    vertical::WritingMode,
+};
+
+/// The alignment of lines, in vertical layouts left is the top and right the bottom.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Align {
+    #[default]
+    Left,
+    Right,
+    Center,
+    /// Left in left to right paragraphs, right in right to left ones.
+    Start,
+    /// Right in left to right paragraphs, left in right to left ones.
+    End,
+    /// Stretch the spaces of every wrapped line to fill the width.
+    ///
+    /// The last line of a paragraph is aligned to the start.
+    Justify,
+}
+
+impl Align {
+    /// Resolve to left, right or center for a paragraph of the direction.
+    fn resolve(self, rtl: bool) -> Self {
+        match (self, rtl) {
+            (Self::Start | Self::Justify, false) | (Self::End, true) => Self::Left,
+            (Self::Start | Self::Justify, true) | (Self::End, false) => Self::Right,
+            (align, _) => align,
+        }
+    }
+}
+
+/// The distance between two baselines.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum LineHeight {
+    /// What the fonts recommend.
+    #[default]
+    Normal,
+    /// A multiple of what the fonts recommend.
+    Scale(f32),
+    /// A fixed height in pixels.
+    Pixels(f32),
+}
+
+impl LineHeight {
+    fn resolve(self, metrics: &FontMetrics) -> f32 {
+        match self {
+            Self::Normal => metrics.line_height(),
+            Self::Scale(scale) => metrics.line_height() * scale,
+            Self::Pixels(pixels) => pixels,
+        }
+    }
+}
+
+/// How [`Layout::new`] arranges the text.
+#[derive(Debug, Clone, Default)]
+pub struct LayoutOptions {
+    /// Wrap lines longer than this, `None` only breaks at line separators.
+    ///
+    /// In vertical layouts this is the height of the columns.
+    pub max_width: Option<f32>,
+    pub writing_mode: WritingMode,
+    /// The base direction of every paragraph, `None` detects it from the text.
+    pub direction: Option<Direction>,
+    pub align: Align,
+    pub line_height: LineHeight,
+    /// Drop the lines after this many.
+    pub max_lines: Option<usize>,
+    /// The shaped ellipsis (usually `…`) put at the end of the last line when
+    /// lines are dropped.
+    pub ellipsis: Option<ShapedRun>,
+}
+
+/// The font of some glyphs in a [`Layout`].
+#[derive(Debug, Clone)]
+pub struct LayoutRun {
+    pub face: Face,
+    pub pixel_size: u32,
+    pub direction: Direction,
+    /// The byte range of the run in the text, empty for the ellipsis.
+    pub range: Range<usize>,
+}
+
+/// A glyph placed by the layout.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PositionedGlyph {
+    /// The index of the run in [`Layout::runs`].
+    pub run: usize,
+    pub glyph_id: u32,
+    /// The byte offset of the first character of the cluster in the text.
+    pub cluster: usize,
+    /// The bidi embedding level, odd for right to left text.
+    pub level: u8,
+    /// The pen position on the baseline, shaping offsets included.
+    ///
+    /// Glyphs of horizontal runs in a vertical layout are drawn turned 90°
+    /// clockwise around this point.
+    pub position: graph_f::Point,
+    /// The advance box of the glyph, from the top to the bottom of the line.
+    pub rect: graph_f::Rect,
+}
+
+/// A line of a [`Layout`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    /// The byte range of the text on this line, trailing spaces and line separators included.
+    pub range: Range<usize>,
+    /// The range of the glyphs in [`Layout::glyphs`], in visual order.
+    pub glyphs: Range<usize>,
+    /// The box of the visible content, trailing spaces excluded.
+    pub rect: graph_f::Rect,
+    /// The y coordinate of the baseline, the x coordinate of the central
+    /// baseline in vertical layouts.
+    pub baseline: f32,
+    /// The largest metrics of the fonts on this line.
+    pub metrics: FontMetrics,
+    /// The bidi level of the paragraph, odd for right to left paragraphs.
+    pub level: u8,
+    /// Whether the line ends the paragraph, by a line separator or the end of the text.
+    pub hard_break: bool,
+    /// Whether text was cut off after this line.
+    pub ellipsized: bool,
+}
+
+/// Positioned lines of shaped text.
+///
+/// The origin is the top left corner of the layout box, y goes down.
+#[derive(Debug, Clone)]
+pub struct Layout {
+    writing_mode: WritingMode,
+    runs: Vec<LayoutRun>,
+    glyphs: Vec<PositionedGlyph>,
+    lines: Vec<Line>,
+    size: graph_f::Size,
+}
+
+/// One or more glyphs that can not be split, in logical order.
+#[derive(Debug, Clone)]
+pub(crate) struct Cluster {
+    pub(crate) run: usize,
+    pub(crate) glyphs: Vec<ShapedGlyph>,
+    pub(crate) bytes: Range<usize>,
+    pub(crate) width: f32,
+    pub(crate) whitespace: bool,
+    /// The bidi embedding level, filled in by the layout.
+    pub(crate) level: u8,
+}
+
+/// A line found by [`wrap`], as a range of clusters.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct WrappedLine {
+    pub(crate) clusters: Range<usize>,
+    pub(crate) hard_break: bool,
+}
+
+/// Split the shaped runs into clusters in logical order.
+pub(crate) fn collect_clusters(text: &str, runs: &[ShapedRun]) -> Vec<Cluster> {
+    let mut clusters: Vec<Cluster> = Vec::new();
+
+    for (index, run) in runs.iter().enumerate() {
+        let first = clusters.len();
+        let mut logical = run.glyphs.clone();
+        if run.direction.is_backward() {
+            logical.reverse();
+        }
+
+        for glyph in logical {
+            let in_run = clusters.len() > first;
+            let advance = inline_advance(run.direction, &glyph);
+            match clusters.last_mut() {
+                Some(last) if in_run && last.bytes.start == glyph.cluster => {
+                    last.width += advance;
+                    last.glyphs.push(glyph);
+                }
+                _ => clusters.push(Cluster {
+                    run: index,
+                    glyphs: vec![glyph],
+                    bytes: glyph.cluster..glyph.cluster,
+                    width: advance,
+                    whitespace: false,
+                    level: 0,
+                }),
+            }
+        }
+
+        for cluster in first..clusters.len() {
+            let end = clusters
+                .get(cluster + 1)
+                .map(|next| next.bytes.start)
+                .filter(|&next| next > clusters[cluster].bytes.start)
+                .unwrap_or(run.range.end)
+                .max(clusters[cluster].bytes.start);
+            let cluster = &mut clusters[cluster];
+            cluster.bytes.end = end;
+            cluster.whitespace = text
+                .get(cluster.bytes.clone())
+                .is_some_and(|text| !text.is_empty() && text.chars().all(char::is_whitespace));
+        }
+    }
+
+    clusters
+}
+
+/// The advance of a glyph along the line, down for vertical runs.
+fn inline_advance(direction: Direction, glyph: &ShapedGlyph) -> f32 {
+    if direction.is_vertical() {
+        glyph.advance.y
+    } else {
+        glyph.advance.x
+    }
+}
+
+/// The advance of a whole run along the line.
+fn run_inline_advance(run: &ShapedRun) -> f32 {
+    run.glyphs
+        .iter()
+        .map(|glyph| inline_advance(run.direction, glyph))
+        .sum()
+}
+
+/// The width of the clusters without the trailing spaces.
+pub(crate) fn content_width(clusters: &[Cluster]) -> f32 {
+    let end = clusters
+        .iter()
+        .rposition(|cluster| !cluster.whitespace)
+        .map_or(0, |index| index + 1);
+    clusters[..end].iter().map(|cluster| cluster.width).sum()
+}
+
+/// The cluster at or before `index`, but after `min`, that starts a grapheme.
+///
+/// Clusters of different runs can split a grapheme, like an emoji sequence
+/// that falls back to two fonts.
+fn grapheme_start(text: &str, clusters: &[Cluster], min: usize, index: usize) -> usize {
+    let mut index = index;
+    while index > min + 1 && !is_grapheme_boundary(text, clusters[index].bytes.start) {
+        index -= 1;
+    }
+    index
+}
+
+/// Break the clusters into lines with the Unicode line breaking algorithm.
+///
+/// Lines are only broken inside a word when the word alone does not fit.
+pub(crate) fn wrap(text: &str, clusters: &[Cluster], max_width: Option<f32>) -> Vec<WrappedLine> {
+    let breaks: HashMap<usize, BreakOpportunity> = linebreaks(text).collect();
+
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut width = 0.0;
+    let mut last_break = None;
+    let mut index = 0;
+
+    while index < clusters.len() {
+        let cluster = &clusters[index];
+
+        if index > start {
+            match breaks.get(&cluster.bytes.start) {
+                Some(BreakOpportunity::Mandatory) => {
+                    lines.push(WrappedLine {
+                        clusters: start..index,
+                        hard_break: true,
+                    });
+                    start = index;
+                    width = 0.0;
+                    last_break = None;
+                    continue;
+                }
+                Some(BreakOpportunity::Allowed) => last_break = Some(index),
+                None => {}
+            }
+
+            if let Some(max_width) = max_width
+                && !cluster.whitespace
+                && width + cluster.width > max_width
+            {
+                // a word alone too long for the line is broken between graphemes
+                let end =
+                    last_break.unwrap_or_else(|| grapheme_start(text, clusters, start, index));
+                lines.push(WrappedLine {
+                    clusters: start..end,
+                    hard_break: false,
+                });
+                start = end;
+                index = end;
+                width = 0.0;
+                last_break = None;
+                continue;
+            }
+        }
+
+        width += cluster.width;
+        index += 1;
+    }
+
+    if start < clusters.len() || lines.is_empty() {
+        lines.push(WrappedLine {
+            clusters: start..clusters.len(),
+            hard_break: true,
+        });
+    }
+    // a line separator at the end starts one more, empty line
+    if !clusters.is_empty() && text.ends_with(is_line_separator) {
+        lines.push(WrappedLine {
+            clusters: clusters.len()..clusters.len(),
+            hard_break: true,
+        });
+    }
+
+    lines
+}
+
+impl Layout {
+    /// Lay out the shaped runs of `text`.
+    ///
+    /// The runs must cover the text in logical order, like the runs produced by
+    /// [`Face::shape`] for the ranges of [`BidiText::runs`]. A run must not mix
+    /// bidi levels, lines are reordered visually cluster by cluster.
+    ///
+    /// Vertical layouts take runs shaped by
+    /// [`FontCollection::shape_vertical`](crate::collection::FontCollection::shape_vertical).
+    /// Their lines are columns from right to left, and the line height is the
+    /// width of a column.
+    pub fn new(text: &str, runs: &[ShapedRun], options: &LayoutOptions) -> Result<Self, TextError> {
+        let vertical = options.writing_mode.is_vertical();
+        let mut layout_runs: Vec<LayoutRun> = runs
+            .iter()
+            .map(|run| LayoutRun {
+                face: run.face.clone(),
+                pixel_size: run.pixel_size,
+                direction: run.direction,
+                range: run.range.clone(),
+            })
+            .collect();
+        let run_metrics_of = |run: &LayoutRun| {
+            if vertical {
+                run.face.vertical_metrics(run.pixel_size)
+            } else {
+                run.face.metrics(run.pixel_size)
+            }
+        };
+        let mut run_metrics = layout_runs
+            .iter()
+            .map(run_metrics_of)
+            .collect::<Result<Vec<_>, _>>()?;
+        // rotated runs of vertical layouts sit on their own baseline
+        let mut horizontal_metrics = if vertical {
+            layout_runs
+                .iter()
+                .map(|run| run.face.metrics(run.pixel_size))
+                .collect::<Result<Vec<_>, _>>()?
+        } else {
+            Vec::new()
+        };
+
+        let bidi = BidiText::new(text, options.direction);
+        let mut clusters = collect_clusters(text, runs);
+        for cluster in &mut clusters {
+            cluster.level = bidi.level_at(cluster.bytes.start);
+        }
+        let mut wrapped = wrap(text, &clusters, options.max_width);
+
+        let mut ellipsized = false;
+        if let Some(max_lines) = options.max_lines
+            && wrapped.len() > max_lines.max(1)
+        {
+            let max_lines = max_lines.max(1);
+            let last = &wrapped[max_lines - 1];
+            // let the last line take the rest of its paragraph, it is cut to the width below
+            let end = if last.hard_break {
+                last.clusters.end
+            } else {
+                wrapped[max_lines..]
+                    .iter()
+                    .find(|line| line.hard_break)
+                    .map_or(clusters.len(), |line| line.clusters.end)
+            };
+            let start = last.clusters.start;
+            wrapped.truncate(max_lines);
+            wrapped[max_lines - 1] = WrappedLine {
+                clusters: start..end,
+                hard_break: false,
+            };
+            ellipsized = true;
+        }
+
+        let ellipsis_run = match (&options.ellipsis, ellipsized) {
+            (Some(ellipsis), true) => {
+                let run = LayoutRun {
+                    face: ellipsis.face.clone(),
+                    pixel_size: ellipsis.pixel_size,
+                    direction: ellipsis.direction,
+                    range: 0..0,
+                };
+                run_metrics.push(run_metrics_of(&run)?);
+                if vertical {
+                    horizontal_metrics.push(run.face.metrics(run.pixel_size)?);
+                }
+                layout_runs.push(run);
+                Some((layout_runs.len() - 1, ellipsis))
+            }
+            _ => None,
+        };
+
+        if let Some(last) = wrapped.last_mut()
+            && ellipsized
+        {
+            let ellipsis_width = ellipsis_run.map_or(0.0, |(_, run)| run_inline_advance(run));
+            if let Some(max_width) = options.max_width {
+                let mut width = 0.0;
+                let mut end = last.clusters.start;
+                for (index, cluster) in clusters[last.clusters.clone()].iter().enumerate() {
+                    if !cluster.whitespace && width + cluster.width + ellipsis_width > max_width {
+                        break;
+                    }
+                    width += cluster.width;
+                    end = last.clusters.start + index + 1;
+                }
+                last.clusters.end = if end < clusters.len() {
+                    grapheme_start(text, &clusters, last.clusters.start, end)
+                } else {
+                    end
+                };
+            }
+            // the ellipsis follows the last visible character
+            while last.clusters.end > last.clusters.start
+                && clusters[last.clusters.end - 1].whitespace
+            {
+                last.clusters.end -= 1;
+            }
+        }
+
+        let line_count = wrapped.len();
+        let widths: Vec<f32> = wrapped
+            .iter()
+            .enumerate()
+            .map(|(index, line)| {
+                let mut width = content_width(&clusters[line.clusters.clone()]);
+                if ellipsized && index + 1 == line_count {
+                    width += ellipsis_run.map_or(0.0, |(_, run)| run_inline_advance(run));
+                }
+                width
+            })
+            .collect();
+        let available = options
+            .max_width
+            .unwrap_or_else(|| widths.iter().copied().fold(0.0, f32::max));
+
+        let mut glyphs = Vec::new();
+        let mut lines = Vec::with_capacity(line_count);
+        let mut top = 0.0;
+        let mut previous_end = 0;
+
+        for (index, line) in wrapped.iter().enumerate() {
+            let line_clusters = &clusters[line.clusters.clone()];
+            let is_ellipsized = ellipsized && index + 1 == line_count;
+            let ellipsis = ellipsis_run.filter(|_| is_ellipsized);
+
+            let metrics = line_clusters
+                .iter()
+                .map(|cluster| cluster.run)
+                .chain(ellipsis.map(|(run, _)| run))
+                .map(|run| run_metrics[run])
+                .reduce(FontMetrics::max)
+                .or_else(|| run_metrics.first().copied())
+                .unwrap_or_default();
+            let height = options.line_height.resolve(&metrics);
+            // columns are placed leftwards from 0 and moved into the box at the end
+            let baseline = if vertical {
+                -top - height / 2.0
+            } else {
+                top + (height - metrics.ascent - metrics.descent) / 2.0 + metrics.ascent
+            };
+
+            let width = widths[index];
+            let level = line_clusters
+                .first()
+                .map_or(bidi.level_at(previous_end), |cluster| {
+                    bidi.paragraph_at(cluster.bytes.start)
+                        .map_or(0, |paragraph| paragraph.level)
+                });
+            let rtl = level % 2 == 1;
+            let mut x = match options.align.resolve(rtl) {
+                Align::Right => available - width,
+                Align::Center => (available - width) / 2.0,
+                _ => 0.0,
+            };
+
+            // the spaces between words, trailing spaces do not stretch
+            let content_end = line_clusters
+                .iter()
+                .rposition(|cluster| !cluster.whitespace)
+                .map_or(0, |index| index + 1);
+            let gaps = line_clusters[..content_end]
+                .iter()
+                .filter(|cluster| cluster.whitespace)
+                .count();
+            let extra = if options.align == Align::Justify
+                && !line.hard_break
+                && !is_ellipsized
+                && gaps > 0
+            {
+                ((available - width) / gaps as f32).max(0.0)
+            } else {
+                0.0
+            };
+            if extra > 0.0 {
+                x = 0.0;
+            }
+
+            // trailing spaces take the paragraph level (rule L1), in a right to
+            // left line they end up on the left and hang out of the box
+            let levels: Vec<u8> = line_clusters
+                .iter()
+                .enumerate()
+                .map(|(index, cluster)| {
+                    if index >= content_end {
+                        level
+                    } else {
+                        cluster.level
+                    }
+                })
+                .collect();
+            let line_left = x;
+            if rtl {
+                x -= line_clusters[content_end..]
+                    .iter()
+                    .map(|cluster| cluster.width)
+                    .sum::<f32>();
+            }
+
+            let first_glyph = glyphs.len();
+
+            let mut place =
+                |x: &mut f32, run: usize, level: u8, glyph: &ShapedGlyph, cluster, advance| {
+                    let (position, rect) = if !vertical {
+                        (
+                            graph_f::Point::new(*x + glyph.offset.x, baseline + glyph.offset.y),
+                            graph_f::Rect::new(*x, top, advance, height),
+                        )
+                    } else {
+                        let rect = graph_f::Rect::new(-top - height, *x, height, advance);
+                        let position = if layout_runs[run].direction.is_vertical() {
+                            graph_f::Point::new(baseline + glyph.offset.x, *x + glyph.offset.y)
+                        } else {
+                            // turned clockwise, the ascent points right, center the em box
+                            let metrics = &horizontal_metrics[run];
+                            let rotated = baseline - (metrics.ascent - metrics.descent) / 2.0;
+                            graph_f::Point::new(rotated - glyph.offset.y, *x + glyph.offset.x)
+                        };
+                        (position, rect)
+                    };
+
+                    glyphs.push(PositionedGlyph {
+                        run,
+                        glyph_id: glyph.glyph_id,
+                        cluster,
+                        level,
+                        position,
+                        rect,
+                    });
+                    *x += advance;
+                };
+
+            let range = match (line_clusters.first(), line_clusters.last()) {
+                (Some(first), Some(last)) => first.bytes.start..last.bytes.end,
+                _ => previous_end..previous_end,
+            };
+
+            // the ellipsis goes after the content, which is on the left in
+            // right to left paragraphs
+            if let Some((run, ellipsis)) = ellipsis
+                && rtl
+            {
+                for glyph in ellipsis.glyphs.iter() {
+                    let advance = inline_advance(ellipsis.direction, glyph);
+                    place(&mut x, run, level, glyph, range.end, advance);
+                }
+            }
+
+            for logical in reorder(&levels) {
+                let cluster = &line_clusters[logical];
+                let stretch = if cluster.whitespace && logical < content_end {
+                    extra
+                } else {
+                    0.0
+                };
+                // glyphs of backward runs were reversed into logical order
+                let backward = layout_runs[cluster.run].direction.is_backward();
+                let count = cluster.glyphs.len();
+                for visual in 0..count {
+                    let glyph = if backward {
+                        &cluster.glyphs[count - 1 - visual]
+                    } else {
+                        &cluster.glyphs[visual]
+                    };
+                    let advance = inline_advance(layout_runs[cluster.run].direction, glyph)
+                        + if visual + 1 == count { stretch } else { 0.0 };
+                    place(
+                        &mut x,
+                        cluster.run,
+                        cluster.level,
+                        glyph,
+                        glyph.cluster,
+                        advance,
+                    );
+                }
+            }
+
+            if let Some((run, ellipsis)) = ellipsis
+                && !rtl
+            {
+                // placed right after the content, over any trailing spaces
+                let mut x = line_left + width - run_inline_advance(ellipsis);
+                for glyph in ellipsis.glyphs.iter() {
+                    let advance = inline_advance(ellipsis.direction, glyph);
+                    place(&mut x, run, level, glyph, range.end, advance);
+                }
+            }
+
+            if let Some((run, _)) = ellipsis {
+                layout_runs[run].range = range.end..range.end;
+            }
+            let stretched = if extra > 0.0 { available } else { width };
+            previous_end = range.end;
+            lines.push(Line {
+                range,
+                glyphs: first_glyph..glyphs.len(),
+                rect: if vertical {
+                    graph_f::Rect::new(-top - height, line_left, height, stretched)
+                } else {
+                    graph_f::Rect::new(line_left, top, stretched, height)
+                },
+                baseline,
+                metrics,
+                level,
+                hard_break: line.hard_break,
+                ellipsized: is_ellipsized,
+            });
+
+            top += height;
+        }
+
+        if !vertical {
+            return Ok(Self {
+                writing_mode: options.writing_mode,
+                runs: layout_runs,
+                glyphs,
+                lines,
+                size: graph_f::Size::new(available, top),
+            });
+        }
+
+        // the first column ends at the right edge of the box
+        for glyph in &mut glyphs {
+            glyph.position.x += top;
+            glyph.rect.position.x += top;
+        }
+        for line in &mut lines {
+            line.rect.position.x += top;
+            line.baseline += top;
+        }
+
+        Ok(Self {
+            writing_mode: options.writing_mode,
+            runs: layout_runs,
+            glyphs,
+            lines,
+            size: graph_f::Size::new(top, available),
+        })
+    }
+
+    pub fn writing_mode(&self) -> WritingMode {
+        self.writing_mode
+    }
+
+    /// Whether the glyphs of a run are drawn turned 90° clockwise.
+    pub fn is_rotated(&self, run: &LayoutRun) -> bool {
+        self.writing_mode.is_vertical() && run.direction.is_horizontal()
+    }
+
+    /// The fonts used by the glyphs.
+    pub fn runs(&self) -> &[LayoutRun] {
+        &self.runs
+    }
+
+    /// All glyphs, line by line.
+    pub fn glyphs(&self) -> &[PositionedGlyph] {
+        &self.glyphs
+    }
+
+    pub fn lines(&self) -> &[Line] {
+        &self.lines
+    }
+
+    /// The glyphs of one line in visual order.
+    pub fn line_glyphs(&self, line: &Line) -> &[PositionedGlyph] {
+        self.glyphs.get(line.glyphs.clone()).unwrap_or_default()
+    }
+
+    /// The size of the layout box, the width (or the height of vertical layouts) is
+    /// the max width when one was given.
+    pub fn size(&self) -> graph_f::Size {
+        self.size
+    }
+
+    /// The smallest rectangle containing the content of every line.
+    pub fn bounds(&self) -> graph_f::Rect {
+        let Some(first) = self.lines.first() else {
+            return graph_f::Rect::new(0.0, 0.0, 0.0, 0.0);
+        };
+
+        if self.writing_mode.is_vertical() {
+            let (top, bottom) = self.lines.iter().fold(
+                (first.rect.position.y, first.rect.position.y),
+                |(top, bottom), line| {
+                    (
+                        top.min(line.rect.position.y),
+                        bottom.max(line.rect.position.y + line.rect.size.height),
+                    )
+                },
+            );
+            return graph_f::Rect::new(0.0, top, self.size.width, bottom - top);
+        }
+
+        let (left, right) = self.lines.iter().fold(
+            (first.rect.position.x, first.rect.position.x),
+            |(left, right), line| {
+                (
+                    left.min(line.rect.position.x),
+                    right.max(line.rect.position.x + line.rect.size.width),
+                )
+            },
+        );
+
+        graph_f::Rect::new(left, 0.0, right - left, self.size.height)
+    }
+
+    /// The index of the line containing the byte offset.
+    ///
+    /// An offset between two lines belongs to the second one.
+    pub fn line_at_offset(&self, offset: usize) -> Option<usize> {
+        let index = self
+            .lines
+            .partition_point(|line| line.range.end <= offset && !line.range.is_empty());
+        if index < self.lines.len() {
+            Some(index)
+        } else {
+            self.lines
+                .len()
+                .checked_sub(1)
+                .filter(|&last| self.lines[last].range.end == offset)
+        }
+    }
+
+    /// The clusters of a line in visual order, as byte ranges with their bidi level.
+    pub fn visual_clusters(&self, line: &Line) -> Vec<(Range<usize>, u8)> {
+        let glyphs = self.line_glyphs(line);
+
+        // a cluster ends where the next one in logical order starts
+        let mut starts: Vec<usize> = glyphs
+            .iter()
+            .map(|glyph| glyph.cluster)
+            .filter(|&cluster| cluster < line.range.end)
+            .collect();
+        starts.sort_unstable();
+        starts.dedup();
+        let end_of = |start: usize| {
+            starts
+                .get(starts.partition_point(|&other| other <= start))
+                .copied()
+                .unwrap_or(line.range.end)
+        };
+
+        let mut clusters: Vec<(Range<usize>, u8)> = Vec::new();
+        for glyph in glyphs.iter().filter(|glyph| glyph.cluster < line.range.end) {
+            if clusters
+                .last()
+                .is_some_and(|(range, _)| range.start == glyph.cluster)
+            {
+                continue;
+            }
+            clusters.push((glyph.cluster..end_of(glyph.cluster), glyph.level));
+        }
+        clusters
+    }
+
+    /// The index of the first glyph, in visual order, of the cluster containing the byte offset.
+    pub fn logical_to_visual(&self, offset: usize) -> Option<usize> {
+        let line = &self.lines[self.line_at_offset(offset)?];
+        self.line_glyphs(line)
+            .iter()
+            .enumerate()
+            .filter(|(_, glyph)| glyph.cluster <= offset && glyph.cluster < line.range.end)
+            .max_by_key(|(index, glyph)| (glyph.cluster, usize::MAX - index))
+            .map(|(index, _)| line.glyphs.start + index)
+    }
+
+    /// The byte offset of the cluster of a glyph.
+    pub fn visual_to_logical(&self, glyph: usize) -> Option<usize> {
+        self.glyphs.get(glyph).map(|glyph| glyph.cluster)
+    }
+
+    /// Move a caret one cluster to the left or right on screen.
+    ///
+    /// In vertical layouts left is up and right is down.
+    /// A caret at a byte offset stands at the leading edge of the cluster
+    /// starting there, so the stops of a line are its clusters in visual order
+    /// plus the end of the line, on the right of left to right paragraphs and on
+    /// the left of right to left ones. Every stop is visited exactly once.
+    /// Returns `None` when the line of the offset has no stop in that direction.
+    pub fn move_visually(&self, offset: usize, right: bool) -> Option<usize> {
+        let line_index = self.line_at_offset(offset)?;
+        let line = &self.lines[line_index];
+        let mut stops: Vec<usize> = self
+            .visual_clusters(line)
+            .into_iter()
+            .map(|(range, _)| range.start)
+            .collect();
+        let end = if line.hard_break && line_index + 1 < self.lines.len() {
+            // the caret stops before the line separator, the last cluster in logical order
+            stops.iter().copied().max().unwrap_or(line.range.end)
+        } else {
+            line.range.end
+        };
+        stops.retain(|&start| start != end);
+        if line.level % 2 == 1 {
+            stops.insert(0, end);
+        } else {
+            stops.push(end);
+        }
+
+        let index = stops.iter().position(|&stop| stop == offset)?;
+        let index = if right {
+            index.checked_add(1)?
+        } else {
+            index.checked_sub(1)?
+        };
+        stops.get(index).copied()
+    }
+
+    /// Whether some text was cut off because of [`LayoutOptions::max_lines`].
+    pub fn is_ellipsized(&self) -> bool {
+        self.lines.last().is_some_and(|line| line.ellipsized)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        shape::ShapeOptions,
+        test_font::{SQUARE, TestFont},
+    };
+
+    fn clusters(text: &str, width: f32) -> Vec<Cluster> {
+        text.char_indices()
+            .map(|(index, ch)| Cluster {
+                run: 0,
+                glyphs: Vec::new(),
+                bytes: index..index + ch.len_utf8(),
+                width,
+                whitespace: ch.is_whitespace(),
+                level: 0,
+            })
+            .collect()
+    }
+
+    fn lines(text: &str, max_width: Option<f32>) -> Vec<&str> {
+        let clusters = clusters(text, 1.0);
+        wrap(text, &clusters, max_width)
+            .into_iter()
+            .map(|line| {
+                let start = clusters
+                    .get(line.clusters.start)
+                    .map_or(text.len(), |c| c.bytes.start);
+                let end = clusters[..line.clusters.end]
+                    .last()
+                    .filter(|_| !line.clusters.is_empty())
+                    .map_or(start, |c| c.bytes.end);
+                &text[start..end]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn wraps_at_spaces() {
+        assert_eq!(
+            lines("the quick brown fox", Some(10.0)),
+            ["the quick ", "brown fox"]
+        );
+    }
+
+    #[test]
+    fn breaks_long_words() {
+        assert_eq!(lines("abcdefgh ij", Some(4.0)), ["abcd", "efgh ", "ij"]);
+    }
+
+    #[test]
+    fn keeps_line_separators() {
+        assert_eq!(lines("a\n\nb", None), ["a\n", "\n", "b"]);
+        assert_eq!(lines("a\n", None), ["a\n", ""]);
+        assert_eq!(lines("", None), [""]);
+    }
+
+    #[test]
+    fn trailing_spaces_hang() {
+        assert_eq!(lines("ab    cd", Some(2.0)), ["ab    ", "cd"]);
+        assert_eq!(content_width(&clusters("ab  ", 1.0)), 2.0);
+    }
+
+    #[test]
+    fn ends_vertical_columns_with_the_ellipsis() -> Result<(), TextError> {
+        let face = TestFont::new().face()?;
+        let down = ShapeOptions {
+            direction: Some(Direction::TopToBottom),
+            ..ShapeOptions::default()
+        };
+        let text = "abcdefgh";
+        let runs = [face.shape(text, 0..text.len(), 10, &down)?];
+        let ellipsis = face.shape("z", 0..1, 10, &down)?;
+        assert_eq!(run_inline_advance(&ellipsis), 10.0);
+
+        let layout = Layout::new(
+            text,
+            &runs,
+            &LayoutOptions {
+                max_width: Some(45.0),
+                writing_mode: WritingMode::VerticalRl,
+                max_lines: Some(1),
+                ellipsis: Some(ellipsis),
+                ..LayoutOptions::default()
+            },
+        )?;
+
+        assert_eq!(layout.lines().len(), 1);
+        let line = &layout.lines()[0];
+        assert!(line.ellipsized);
+        assert_eq!(line.range, 0..3);
+        let glyphs = layout.line_glyphs(line);
+        let tops: Vec<f32> = glyphs.iter().map(|glyph| glyph.rect.position.y).collect();
+        assert_eq!(tops, [0.0, 10.0, 20.0, 30.0]);
+        let last = glyphs.last().map(|glyph| (glyph.run, glyph.glyph_id));
+        assert_eq!(last, Some((layout.runs().len() - 1, SQUARE)));
+        Ok(())
+    }
+}
@@ -1,14 +1,25 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
-}
+pub mod bidi;
+pub mod collection;
+mod colr;
+pub mod discovery;
+pub mod edit;
+pub mod effect;
+pub mod error;
+pub mod ffi;
+pub mod font;
+pub mod glyph_cache;
+pub mod hit;
+pub mod layout;
+pub mod markup;
+pub mod measure;
+pub mod raster;
+pub mod render;
+pub mod rich;
+pub mod segment;
+pub mod shape;
+pub mod shape_cache;
+pub mod variation;
+pub mod vertical;
 
 #[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+mod test_font;
@@ -0,0 +1,215 @@
+//! A small markup language for rich text.
+//!
+//! Tags are written in square brackets and closed in reverse order:
+//!
+//! - `[b]bold[/b]`
+//! - `[i]italic[/i]`
+//! - `[u]underlined[/u]`
+//! - `[color=#ff0000]red[/color]`, colors are given as `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`
+//! - `[size=24]big[/size]`, the size is in pixels
+//! - `[features=smcp,-liga]small caps[/features]`, OpenType features in the syntax of [`Feature`]
+//!
+//! `[[` is a literal `[`. Tags still open at the end of the text are closed there.
+
+use ::std::borrow::Cow;
+
+use ::wscb_type::color::Color;
+
+use crate::{
+    error::MarkupError,
+    rich::{AttributedString, TextStyle},
+    shape::Feature,
+};
+
+/// A tag that is not closed yet.
+struct OpenTag<'a> {
+    name: &'a str,
+    /// The style from before the tag.
+    previous: TextStyle,
+    /// The features of the tag, applied from `start` to its end.
+    features: Vec<Feature>,
+    start: usize,
+}
+
+impl OpenTag<'_> {
+    fn close(self, string: &mut AttributedString) {
+        for feature in self.features {
+            // the range always lies in the text
+            let _ = string.add_feature(self.start..string.len(), feature);
+        }
+    }
+}
+
+/// Parse markup into an attributed string, unstyled text uses `base`.
+pub fn parse(markup: &str, base: TextStyle) -> Result<AttributedString, MarkupError> {
+    let mut string = AttributedString::default();
+    let mut stack: Vec<OpenTag> = Vec::new();
+    let mut style = base;
+    let mut rest = markup;
+
+    while let Some(open) = rest.find('[') {
+        string.push_str(&rest[..open], style);
+        let offset = markup.len() - rest.len() + open;
+        let after = &rest[open + 1..];
+
+        if let Some(after) = after.strip_prefix('[') {
+            string.push_str("[", style);
+            rest = after;
+            continue;
+        }
+
+        let close = after
+            .find(']')
+            .ok_or(MarkupError::UnterminatedTag { offset })?;
+        let tag = after[..close].trim();
+        rest = &after[close + 1..];
+
+        if let Some(name) = tag.strip_prefix('/') {
+            let name = name.trim();
+            let Some(open) = stack.pop() else {
+                return Err(MarkupError::UnexpectedClose {
+                    tag: name.to_string(),
+                    offset,
+                });
+            };
+            if open.name != name {
+                return Err(MarkupError::MismatchedClose {
+                    expected: open.name.to_string(),
+                    found: name.to_string(),
+                    offset,
+                });
+            }
+            style = open.previous;
+            open.close(&mut string);
+            continue;
+        }
+
+        let (name, value) = match tag.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim())),
+            None => (tag, None),
+        };
+        let invalid = || MarkupError::InvalidValue {
+            tag: name.to_string(),
+            value: value.unwrap_or_default().to_string(),
+            offset,
+        };
+
+        let mut next = style;
+        let mut features = Vec::new();
+        match (name, value) {
+            ("b", None) => next.bold = true,
+            ("i", None) => next.italic = true,
+            ("u", None) => next.underline = true,
+            ("color", Some(value)) => next.color = Color::from_hex(value).ok_or_else(invalid)?,
+            ("size", Some(value)) => {
+                next.size = value
+                    .parse()
+                    .ok()
+                    .filter(|&size| size > 0)
+                    .ok_or_else(invalid)?;
+            }
+            ("features", Some(value)) => {
+                features = value
+                    .split(',')
+                    .map(|feature| feature.trim().parse::<Feature>())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| invalid())?;
+            }
+            ("b" | "i" | "u", Some(_)) | ("color" | "size" | "features", None) => {
+                return Err(invalid());
+            }
+            _ => {
+                return Err(MarkupError::UnknownTag {
+                    tag: name.to_string(),
+                    offset,
+                });
+            }
+        }
+
+        stack.push(OpenTag {
+            name,
+            previous: style,
+            features,
+            start: string.len(),
+        });
+        style = next;
+    }
+
+    string.push_str(rest, style);
+    for open in stack {
+        open.close(&mut string);
+    }
+    Ok(string)
+}
+
+/// Make plain text safe to put into markup, so its `[` are not read as tags.
+pub fn escape(text: &str) -> Cow<'_, str> {
+    if text.contains('[') {
+        Cow::Owned(text.replace('[', "[["))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_tags() -> Result<(), MarkupError> {
+        let base = TextStyle::default();
+        let string = parse("a [b]b [color=#f00]c[/color][/b] [[d]", base)?;
+
+        assert_eq!(string.text(), "a b c [d]");
+        let styles: Vec<_> = string
+            .spans()
+            .iter()
+            .map(|span| {
+                (
+                    &string.text()[span.range.clone()],
+                    span.style.bold,
+                    span.style.color,
+                )
+            })
+            .collect();
+        assert_eq!(
+            styles,
+            [
+                ("a ", false, Color::WHITE),
+                ("b ", true, Color::WHITE),
+                ("c", true, Color::RED),
+                (" [d]", false, Color::WHITE),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn reports_errors() {
+        let base = TextStyle::default();
+        assert_eq!(
+            parse("[b]x[/i]", base),
+            Err(MarkupError::MismatchedClose {
+                expected: "b".to_string(),
+                found: "i".to_string(),
+                offset: 4,
+            })
+        );
+        assert_eq!(
+            parse("[size=big]", base),
+            Err(MarkupError::InvalidValue {
+                tag: "size".to_string(),
+                value: "big".to_string(),
+                offset: 0,
+            })
+        );
+        assert_eq!(
+            parse("x [b", base),
+            Err(MarkupError::UnterminatedTag { offset: 2 })
+        );
+        assert!(matches!(
+            parse("[wave]", base),
+            Err(MarkupError::UnknownTag { .. })
+        ));
+    }
+}
@@ -0,0 +1,123 @@
+//! Measuring text before drawing it.
+//!
+//! Measurements go through the same [`ShapeCache`] and [`Layout`] as
+//! drawing, so the numbers match the drawn text exactly.
+
+use crate::{
+    collection::FontCollection,
+    error::TextError,
+    font::FontMetrics,
+    layout::{Layout, LayoutOptions},
+    shape::ShapeOptions,
+    shape_cache::ShapeCache,
+};
+
+/// The size of laid out text.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Measurement {
+    /// The width of the widest line, trailing spaces excluded.
+    pub width: f32,
+    /// The height of all lines.
+    pub height: f32,
+    /// The distance from the top to the baseline of the first line.
+    pub baseline: f32,
+    pub line_count: usize,
+}
+
+impl Measurement {
+    /// Measure a layout.
+    ///
+    /// For vertical layouts the width is across the columns and the baseline
+    /// is the central baseline of the first column.
+    pub fn of(layout: &Layout) -> Self {
+        let bounds = layout.bounds();
+        let (width, height) = if layout.writing_mode().is_vertical() {
+            (layout.size().width, bounds.size.height)
+        } else {
+            (bounds.size.width, layout.size().height)
+        };
+
+        Self {
+            width,
+            height,
+            baseline: layout.lines().first().map_or(0.0, |line| line.baseline),
+            line_count: layout.lines().len(),
+        }
+    }
+}
+
+impl ShapeCache {
+    /// Measure a single line of text, it is only broken at line separators.
+    pub fn measure_line(
+        &mut self,
+        fonts: &FontCollection,
+        text: &str,
+        pixel_size: u32,
+        options: &ShapeOptions,
+    ) -> Result<Measurement, TextError> {
+        self.measure(fonts, text, pixel_size, options, &LayoutOptions::default())
+    }
+
+    /// Measure a paragraph laid out with the options, wrapped at `max_width` when one is given.
+    pub fn measure(
+        &mut self,
+        fonts: &FontCollection,
+        text: &str,
+        pixel_size: u32,
+        shape_options: &ShapeOptions,
+        layout_options: &LayoutOptions,
+    ) -> Result<Measurement, TextError> {
+        let layout = self.layout(fonts, text, pixel_size, shape_options, layout_options)?;
+        Ok(Measurement::of(&layout))
+    }
+}
+
+impl FontCollection {
+    /// The metrics of the first face at the given pixel size.
+    ///
+    /// Lines only use the metrics of fallback faces when they have glyphs
+    /// from them, so this is the height of a line of text the first face
+    /// covers. Empty text has no glyphs to take metrics from and measures
+    /// zero, use this for the height of an empty text box.
+    pub fn metrics(&self, pixel_size: u32) -> Result<FontMetrics, TextError> {
+        match self.faces().first() {
+            Some(face) => face.metrics(pixel_size),
+            None => Ok(FontMetrics::default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_font::TestFont;
+
+    #[test]
+    fn measures_what_is_laid_out() -> Result<(), TextError> {
+        let fonts = FontCollection::new([TestFont::new().face()?]);
+        let mut cache = ShapeCache::new();
+        let shape_options = ShapeOptions::default();
+        let layout_options = LayoutOptions {
+            max_width: Some(25.0),
+            ..LayoutOptions::default()
+        };
+
+        let text = "ab cd\nef";
+        let measurement = cache.measure(&fonts, text, 10, &shape_options, &layout_options)?;
+        let layout = cache.layout(&fonts, text, 10, &shape_options, &layout_options)?;
+        assert_eq!(cache.layout_stats().hits, 1);
+
+        assert_eq!(measurement, Measurement::of(&layout));
+        assert_eq!(measurement.line_count, layout.lines().len());
+        assert_eq!(
+            measurement,
+            Measurement {
+                width: 20.0,
+                height: 30.0,
+                baseline: fonts.metrics(10)?.ascent,
+                line_count: 3,
+            }
+        );
+        Ok(())
+    }
+}
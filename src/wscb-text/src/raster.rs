@@ -0,0 +1,474 @@
+use ::sdl3_sys::{pixels::SDL_PixelFormat, surface::SDL_ScaleMode};
+use ::wscb_sdl::graph::Surface;
+use ::wscb_type::{graph::Point, graph_f};
+
+use crate::{
+    error::TextError,
+    ffi::freetype::{
+        FT_Bitmap, FT_GLYPH_FORMAT_OUTLINE, FT_Get_Color_Glyph_Layer, FT_Int32, FT_LOAD_COLOR,
+        FT_LOAD_DEFAULT, FT_LOAD_NO_HINTING, FT_LOAD_TARGET_, FT_LayerIterator, FT_Load_Glyph,
+        FT_PIXEL_MODE_BGRA, FT_PIXEL_MODE_GRAY, FT_PIXEL_MODE_LCD, FT_PIXEL_MODE_MONO, FT_Pos,
+        FT_RENDER_MODE_LCD, FT_RENDER_MODE_LIGHT, FT_RENDER_MODE_MONO, FT_RENDER_MODE_NORMAL,
+        FT_Render_Glyph, FT_Render_Mode, FT_Set_Transform, FT_Vector,
+    },
+    font::Face,
+};
+
+/// The pixel format of every surface produced by the rasterizer.
+pub const GLYPH_PIXEL_FORMAT: SDL_PixelFormat = SDL_PixelFormat::RGBA32;
+
+/// How the coverage of a glyph is rasterized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RenderMode {
+    /// 8-bit antialiased coverage.
+    #[default]
+    Grayscale,
+    /// Horizontal subpixel (RGB) coverage.
+    ///
+    /// The drawing functions of [`render`](crate::render) blend it by the
+    /// strongest channel, like grayscale.
+    Lcd,
+    /// 1-bit coverage, every pixel is either inked or not.
+    Mono,
+}
+
+impl RenderMode {
+    fn freetype_render_mode(self) -> FT_Render_Mode {
+        match self {
+            RenderMode::Grayscale => FT_RENDER_MODE_NORMAL,
+            RenderMode::Lcd => FT_RENDER_MODE_LCD,
+            RenderMode::Mono => FT_RENDER_MODE_MONO,
+        }
+    }
+}
+
+/// How strongly outlines are fitted to the pixel grid before rasterizing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Hinting {
+    /// The outlines as designed, smooth but blurry at small sizes.
+    None,
+    /// Only vertically, glyph shapes and spacing stay close to the design.
+    Light,
+    /// In both directions with the hinter suited to the render mode, the crispest.
+    #[default]
+    Full,
+}
+
+impl Hinting {
+    /// The load flags for a glyph rendered in a mode.
+    pub(crate) fn load_flags(self, render_mode: FT_Render_Mode) -> FT_Int32 {
+        match self {
+            Hinting::None => FT_LOAD_NO_HINTING,
+            Hinting::Light => FT_LOAD_TARGET_(FT_RENDER_MODE_LIGHT),
+            Hinting::Full => FT_LOAD_DEFAULT | FT_LOAD_TARGET_(render_mode),
+        }
+    }
+}
+
+/// How glyphs are turned into pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RasterOptions {
+    pub mode: RenderMode,
+    pub hinting: Hinting,
+    /// Rasterize glyphs at quarter pixel pen positions instead of snapping
+    /// them to whole pixels, see [`SUBPIXEL_STEPS`](crate::glyph_cache::SUBPIXEL_STEPS).
+    pub subpixel_positioning: bool,
+}
+
+impl Default for RasterOptions {
+    fn default() -> Self {
+        Self {
+            mode: RenderMode::Grayscale,
+            hinting: Hinting::Full,
+            subpixel_positioning: true,
+        }
+    }
+}
+
+impl RasterOptions {
+    /// Hinted monochrome glyphs on whole pixels, for pixel art interfaces.
+    pub fn crisp() -> Self {
+        Self {
+            mode: RenderMode::Mono,
+            hinting: Hinting::Full,
+            subpixel_positioning: false,
+        }
+    }
+
+    /// Unhinted antialiased glyphs at subpixel positions, for text that moves or scales.
+    pub fn smooth() -> Self {
+        Self {
+            mode: RenderMode::Grayscale,
+            hinting: Hinting::None,
+            subpixel_positioning: true,
+        }
+    }
+}
+
+/// What the pixels of a rasterized glyph mean.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GlyphFormat {
+    /// Coverage only, tinted with the text color when drawn.
+    Mask,
+    /// Final colors (emoji and alike), drawn as they are.
+    Color,
+}
+
+/// A rasterized glyph.
+#[derive(Debug)]
+pub struct GlyphBitmap {
+    /// The glyph image in [`GLYPH_PIXEL_FORMAT`], `None` for glyphs without ink like spaces.
+    ///
+    /// Grayscale glyphs are white with the coverage in alpha. LCD glyphs keep
+    /// the per channel coverage in RGB and the strongest channel in alpha.
+    /// Color glyphs use straight (not premultiplied) alpha.
+    pub surface: Option<Surface>,
+    pub format: GlyphFormat,
+    /// The offset from the pen position to the top left corner of the image, y goes down.
+    pub bearing: Point,
+    /// The pen advance in pixels.
+    pub advance: graph_f::Point,
+}
+
+impl Face {
+    /// Rasterize a glyph at the given pixel size.
+    ///
+    /// Glyphs with color data are rendered in color, whatever the render
+    /// mode: OT-SVG glyphs through the PlutoSVG hooks, COLRv0 layers are
+    /// composed by FreeType, COLRv1 paint graphs are composed in software
+    /// from palette 0 and CBDT/sbix bitmaps are scaled from the closest
+    /// strike. The other glyphs of a color face are rendered like any other.
+    pub fn rasterize_glyph(
+        &self,
+        glyph_id: u32,
+        pixel_size: u32,
+        options: RasterOptions,
+    ) -> Result<GlyphBitmap, TextError> {
+        self.rasterize_glyph_at(glyph_id, pixel_size, options, 0.0)
+    }
+
+    /// Rasterize a glyph shifted right by `subpixel_offset` (in `0.0..1.0`) pixels.
+    ///
+    /// Glyphs placed at fractional pen positions look more even when they are
+    /// rasterized with the fraction instead of snapped to whole pixels.
+    /// [`RasterOptions::subpixel_positioning`] is up to the caller, the offset
+    /// is used as given.
+    pub fn rasterize_glyph_at(
+        &self,
+        glyph_id: u32,
+        pixel_size: u32,
+        options: RasterOptions,
+        subpixel_offset: f32,
+    ) -> Result<GlyphBitmap, TextError> {
+        self.set_pixel_size(pixel_size)?;
+
+        let render_mode = options.mode.freetype_render_mode();
+        let delta = FT_Vector {
+            x: (subpixel_offset * 64.0).round() as FT_Pos,
+            y: 0,
+        };
+        let load = |flags: FT_Int32| unsafe {
+            FT_Set_Transform(self.get_pointer(), std::ptr::null(), &delta);
+            let loaded = FT_Load_Glyph(self.get_pointer(), glyph_id, flags);
+            FT_Set_Transform(self.get_pointer(), std::ptr::null(), std::ptr::null());
+            TextError::check_freetype(loaded, "loading glyph")
+        };
+
+        unsafe {
+            let slot = (*self.get_pointer()).glyph;
+
+            // FreeType only composes color layers when rendering in normal mode
+            let mut render_mode = render_mode;
+            if self.has_color() {
+                load(options.hinting.load_flags(FT_RENDER_MODE_NORMAL) | FT_LOAD_COLOR)?;
+
+                let advance = (*slot).advance;
+                if let Some(image) = self.render_colr_glyph(glyph_id, subpixel_offset) {
+                    return Ok(GlyphBitmap {
+                        surface: image.to_surface()?,
+                        format: GlyphFormat::Color,
+                        bearing: image.bearing,
+                        advance: graph_f::Point::new(
+                            advance.x as f32 / 64.0,
+                            advance.y as f32 / 64.0,
+                        ),
+                    });
+                }
+
+                if (*slot).format != FT_GLYPH_FORMAT_OUTLINE || self.has_color_layers(glyph_id) {
+                    render_mode = FT_RENDER_MODE_NORMAL;
+                } else if render_mode != FT_RENDER_MODE_NORMAL {
+                    // a plain outline, loaded again for the hinter of the mode
+                    load(options.hinting.load_flags(render_mode))?;
+                }
+            } else {
+                load(options.hinting.load_flags(render_mode))?;
+            }
+
+            TextError::check_freetype(FT_Render_Glyph(slot, render_mode), "rendering glyph")?;
+
+            let slot = &*slot;
+
+            let format = if slot.bitmap.pixel_mode == FT_PIXEL_MODE_BGRA {
+                GlyphFormat::Color
+            } else {
+                GlyphFormat::Mask
+            };
+
+            let mut surface = bitmap_to_surface(&slot.bitmap)?;
+            let mut bearing = Point::new(slot.bitmap_left, -slot.bitmap_top);
+            let mut advance =
+                graph_f::Point::new(slot.advance.x as f32 / 64.0, slot.advance.y as f32 / 64.0);
+
+            let scale = self.bitmap_scale();
+            if scale != 1.0 {
+                if let Some(original) = surface.take() {
+                    let size = original.size()?;
+                    let width = ((size.width as f32 * scale).round() as u32).max(1);
+                    let height = ((size.height as f32 * scale).round() as u32).max(1);
+                    surface = Some(original.scale(width, height, SDL_ScaleMode::LINEAR)?);
+                }
+                bearing = Point::new(
+                    (bearing.x as f32 * scale).round() as i32,
+                    (bearing.y as f32 * scale).round() as i32,
+                );
+                advance = graph_f::Point::new(advance.x * scale, advance.y * scale);
+            }
+
+            // scaling works on premultiplied colors, the rest of the pipeline does not
+            if format == GlyphFormat::Color
+                && let Some(surface) = &surface
+            {
+                unpremultiply(surface)?;
+            }
+
+            Ok(GlyphBitmap {
+                surface,
+                format,
+                bearing,
+                advance,
+            })
+        }
+    }
+}
+
+impl Face {
+    /// Whether a glyph has COLRv0 layers.
+    fn has_color_layers(&self, glyph_id: u32) -> bool {
+        let mut iterator = FT_LayerIterator {
+            num_layers: 0,
+            layer: 0,
+            p: std::ptr::null_mut(),
+        };
+        let (mut layer_glyph, mut color_index) = (0, 0);
+        unsafe {
+            FT_Get_Color_Glyph_Layer(
+                self.get_pointer(),
+                glyph_id,
+                &mut layer_glyph,
+                &mut color_index,
+                &mut iterator,
+            ) != 0
+        }
+    }
+}
+
+/// Convert premultiplied [`GLYPH_PIXEL_FORMAT`] pixels to straight alpha in place.
+fn unpremultiply(surface: &Surface) -> Result<(), TextError> {
+    let size = surface.size()?;
+    let pitch = surface.pitch() as usize;
+    let pixels = surface.pixels();
+
+    for y in 0..size.height as usize {
+        let row = unsafe {
+            std::slice::from_raw_parts_mut(pixels.add(y * pitch), size.width as usize * 4)
+        };
+
+        for pixel in row.as_chunks_mut::<4>().0 {
+            let alpha = pixel[3] as u32;
+            if alpha == 0 || alpha == 255 {
+                continue;
+            }
+            for channel in &mut pixel[..3] {
+                *channel = ((*channel as u32 * 255 + alpha / 2) / alpha).min(255) as u8;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Convert a FreeType bitmap into a [`GLYPH_PIXEL_FORMAT`] surface.
+pub(crate) fn bitmap_to_surface(bitmap: &FT_Bitmap) -> Result<Option<Surface>, TextError> {
+    let width = match bitmap.pixel_mode {
+        FT_PIXEL_MODE_LCD => bitmap.width / 3,
+        _ => bitmap.width,
+    };
+    let height = bitmap.rows;
+
+    if width == 0 || height == 0 || bitmap.buffer.is_null() {
+        return Ok(None);
+    }
+
+    let surface = Surface::new(width, height, GLYPH_PIXEL_FORMAT)?;
+    let dst_pitch = surface.pitch() as isize;
+    let dst = surface.pixels();
+
+    let src_pitch = bitmap.pitch as isize;
+    // a negative pitch means the rows are stored bottom up and `buffer`
+    // points at the last row, walk from the visually top row anyway
+    let src_top = if src_pitch < 0 {
+        unsafe { bitmap.buffer.offset(-src_pitch * (height as isize - 1)) }
+    } else {
+        bitmap.buffer
+    };
+
+    for y in 0..height as isize {
+        unsafe {
+            let src_row = src_top.offset(y * src_pitch);
+            let dst_row = dst.offset(y * dst_pitch);
+
+            for x in 0..width as usize {
+                let pixel: [u8; 4] = match bitmap.pixel_mode {
+                    FT_PIXEL_MODE_GRAY => [255, 255, 255, *src_row.add(x)],
+                    FT_PIXEL_MODE_MONO => {
+                        let bit = (*src_row.add(x / 8) >> (7 - x % 8)) & 1;
+                        [255, 255, 255, bit * 255]
+                    }
+                    FT_PIXEL_MODE_BGRA => {
+                        let pixel = src_row.add(x * 4);
+                        [*pixel.add(2), *pixel.add(1), *pixel, *pixel.add(3)]
+                    }
+                    FT_PIXEL_MODE_LCD => {
+                        let r = *src_row.add(x * 3);
+                        let g = *src_row.add(x * 3 + 1);
+                        let b = *src_row.add(x * 3 + 2);
+                        [r, g, b, r.max(g).max(b)]
+                    }
+                    mode => return Err(TextError::UnsupportedPixelMode(mode)),
+                };
+
+                std::ptr::copy_nonoverlapping(pixel.as_ptr(), dst_row.add(x * 4), 4);
+            }
+        }
+    }
+
+    Ok(Some(surface))
+}
+
+#[cfg(test)]
+mod tests {
+    use ::wscb_type::graph::Size;
+
+    use super::*;
+    use crate::test_font::{BLANK, SPACE, SQUARE, TestFont, longs, words};
+
+    /// The pixels of a glyph image, row by row.
+    fn pixels(bitmap: &GlyphBitmap) -> Result<Vec<[u8; 4]>, TextError> {
+        let Some(surface) = &bitmap.surface else {
+            return Ok(Vec::new());
+        };
+        let size = surface.size()?;
+        let mut pixels = Vec::new();
+        for y in 0..size.height as usize {
+            let row = unsafe {
+                std::slice::from_raw_parts(
+                    surface.pixels().add(y * surface.pitch() as usize),
+                    size.width as usize * 4,
+                )
+            };
+            pixels.extend_from_slice(row.as_chunks::<4>().0);
+        }
+        Ok(pixels)
+    }
+
+    /// The test font with [`BLANK`] drawn as a red square through one
+    /// COLRv0 layer.
+    fn color_font() -> TestFont {
+        let mut colr = Vec::new();
+        words(&mut colr, &[0, 1]);
+        longs(&mut colr, &[14, 20]);
+        words(&mut colr, &[1, BLANK as i32, 0, 1, SQUARE as i32, 0]);
+
+        let mut cpal = Vec::new();
+        words(&mut cpal, &[0, 1, 1, 1]);
+        longs(&mut cpal, &[14]);
+        words(&mut cpal, &[0]);
+        cpal.extend([0, 0, 255, 255]);
+
+        TestFont::new()
+            .with_table(b"COLR", colr)
+            .with_table(b"CPAL", cpal)
+    }
+
+    #[test]
+    fn renders_only_color_glyphs_in_color() -> Result<(), TextError> {
+        let face = color_font().face()?;
+        assert!(face.has_color());
+        let crisp = RasterOptions::crisp();
+
+        // the plain square keeps the render mode of the options, half a
+        // pixel off the grid grayscale would give it gray edges
+        let square = face.rasterize_glyph_at(SQUARE, 10, crisp, 0.5)?;
+        assert_eq!(square.format, GlyphFormat::Mask);
+        let coverage = pixels(&square)?;
+        assert!(!coverage.is_empty());
+        assert!(coverage.iter().all(|pixel| matches!(pixel[3], 0 | 255)));
+
+        let blank = face.rasterize_glyph(BLANK, 10, crisp)?;
+        assert_eq!(blank.format, GlyphFormat::Color);
+        assert!(pixels(&blank)?.contains(&[255, 0, 0, 255]));
+        Ok(())
+    }
+
+    #[test]
+    fn rasterizes_coverage() -> Result<(), TextError> {
+        let face = TestFont::new().face()?;
+        let smooth = RasterOptions::smooth();
+
+        let square = face.rasterize_glyph(SQUARE, 10, smooth)?;
+        assert_eq!(square.format, GlyphFormat::Mask);
+        assert_eq!(square.bearing, Point::new(0, -8));
+        assert_eq!(square.advance, graph_f::Point::new(10.0, 0.0));
+        assert_eq!(
+            square.surface.as_ref().map(Surface::size).transpose()?,
+            Some(Size::new(10, 10))
+        );
+        assert!(
+            pixels(&square)?
+                .iter()
+                .all(|pixel| *pixel == [255, 255, 255, 255])
+        );
+
+        // half a pixel off the grid the edges are partly covered
+        let shifted = face.rasterize_glyph_at(SQUARE, 10, smooth, 0.5)?;
+        assert!(
+            pixels(&shifted)?
+                .iter()
+                .any(|pixel| pixel[3] > 0 && pixel[3] < 255)
+        );
+
+        let space = face.rasterize_glyph(SPACE, 10, smooth)?;
+        assert!(space.surface.is_none());
+        assert_eq!(space.advance.x, 5.0);
+        Ok(())
+    }
+
+    #[test]
+    fn rasterizes_lcd_coverage_per_channel() -> Result<(), TextError> {
+        let face = TestFont::new().face()?;
+        let lcd = RasterOptions {
+            mode: RenderMode::Lcd,
+            ..RasterOptions::smooth()
+        };
+
+        let square = face.rasterize_glyph_at(SQUARE, 10, lcd, 0.5)?;
+        assert_eq!(square.format, GlyphFormat::Mask);
+        let pixels = pixels(&square)?;
+        assert!(pixels.contains(&[255, 255, 255, 255]));
+        // the alpha is the strongest channel, and at the edges the channels differ
+        assert!((pixels.iter()).all(|pixel| pixel[3] == pixel[0].max(pixel[1]).max(pixel[2])));
+        assert!(pixels.iter().any(|pixel| pixel[0] != pixel[2]));
+        Ok(())
+    }
+}
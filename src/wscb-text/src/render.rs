@@ -0,0 +1,563 @@
+use ::sdl3_sys::{
+    rect::SDL_FPoint,
+    render::{SDL_Texture, SDL_Vertex},
+};
+use ::wscb_atlas::TextureHandle;
+use ::wscb_sdl::graph::Renderer;
+use ::wscb_type::{color::Color, graph::Rect, graph_f};
+
+use crate::{
+    effect::TextEffect,
+    error::TextError,
+    glyph_cache::GlyphCache,
+    layout::Layout,
+    raster::{GlyphFormat, RasterOptions},
+    rich::AttributedString,
+};
+
+/// How a layout is drawn.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DrawOptions {
+    /// The color of text without a style, and the tint of every glyph.
+    pub color: Color,
+    /// Only draw inside this rectangle of the render target.
+    pub clip: Option<Rect>,
+    /// The render mode, hinting and subpixel positioning of the glyphs.
+    ///
+    /// LCD glyphs are drawn like grayscale ones, tinted by the coverage of
+    /// their strongest channel: the renderer blends whole pixels and can not
+    /// blend the color channels apart.
+    pub raster: RasterOptions,
+    /// Layers drawn behind the text, the first one at the bottom.
+    pub effects: Vec<TextEffect>,
+}
+
+/// The triangles of one texture.
+struct Batch {
+    /// Any handle into the texture, to look it up in its atlas.
+    texture: Option<(GlyphFormat, TextureHandle)>,
+    pointer: *mut SDL_Texture,
+    vertices: Vec<SDL_Vertex>,
+    indices: Vec<i32>,
+}
+
+impl Batch {
+    /// Add a textured rectangle, turned 90° clockwise around `pivot` when one is given.
+    fn push_quad(
+        &mut self,
+        rect: graph_f::Rect,
+        uv: graph_f::Rect,
+        color: Color,
+        pivot: Option<graph_f::Point>,
+    ) {
+        let base = self.vertices.len() as i32;
+        let color = color.into();
+        let (x, y, w, h) = (
+            rect.position.x,
+            rect.position.y,
+            rect.size.width,
+            rect.size.height,
+        );
+        let (u, v, uw, vh) = (uv.position.x, uv.position.y, uv.size.width, uv.size.height);
+
+        for (dx, dy) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)] {
+            let (px, py) = (x + w * dx, y + h * dy);
+            let position = match pivot {
+                Some(pivot) => SDL_FPoint {
+                    x: pivot.x - (py - pivot.y),
+                    y: pivot.y + (px - pivot.x),
+                },
+                None => SDL_FPoint { x: px, y: py },
+            };
+
+            self.vertices.push(SDL_Vertex {
+                position,
+                color,
+                tex_coord: SDL_FPoint {
+                    x: u + uw * dx,
+                    y: v + vh * dy,
+                },
+            });
+        }
+        self.indices
+            .extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+    }
+}
+
+/// Draw a layout with its top left corner at `origin`.
+///
+/// Glyphs are rasterized into the cache on first use, then every atlas
+/// texture is drawn with a single geometry call per layer. The effects of
+/// `options` are drawn first, behind the text.
+pub fn draw_layout(
+    renderer: &mut Renderer,
+    cache: &mut GlyphCache,
+    layout: &Layout,
+    origin: graph_f::Point,
+    options: &DrawOptions,
+) -> Result<(), TextError> {
+    draw(
+        renderer,
+        cache,
+        layout,
+        origin,
+        options,
+        |_| options.color,
+        &[],
+    )
+}
+
+/// Draw a layout of rich text, with the colors and underlines of its spans.
+///
+/// The alpha of `options.color` fades the whole text.
+pub fn draw_attributed(
+    renderer: &mut Renderer,
+    cache: &mut GlyphCache,
+    layout: &Layout,
+    text: &AttributedString,
+    origin: graph_f::Point,
+    options: &DrawOptions,
+) -> Result<(), TextError> {
+    let fade = |color: Color| color.with_alpha(mul_u8(color.a, options.color.a));
+    let underlines: Vec<(graph_f::Rect, Color)> = text
+        .underlines(layout)
+        .into_iter()
+        .map(|(rect, color)| (rect, fade(color)))
+        .collect();
+
+    draw(
+        renderer,
+        cache,
+        layout,
+        origin,
+        options,
+        |cluster| {
+            fade(
+                text.style_at(cluster)
+                    .map_or(options.color, |style| style.color),
+            )
+        },
+        &underlines,
+    )
+}
+
+fn mul_u8(a: u8, b: u8) -> u8 {
+    ((u16::from(a) * u16::from(b) + 127) / 255) as u8
+}
+
+/// A glyph image ready to be batched.
+struct Quad {
+    format: GlyphFormat,
+    handle: TextureHandle,
+    rect: graph_f::Rect,
+    color: Color,
+    /// The pen position that rotated glyphs turn around.
+    pivot: Option<graph_f::Point>,
+}
+
+fn draw(
+    renderer: &mut Renderer,
+    cache: &mut GlyphCache,
+    layout: &Layout,
+    origin: graph_f::Point,
+    options: &DrawOptions,
+    color_of: impl Fn(usize) -> Color,
+    rects: &[(graph_f::Rect, Color)],
+) -> Result<(), TextError> {
+    // every layer gets its own batches so that layers never interleave
+    let mut batches = Vec::new();
+
+    for effect in &options.effects {
+        let origin = graph_f::Point::new(origin.x + effect.offset.x, origin.y + effect.offset.y);
+        let quads = rasterize_layer(
+            renderer,
+            cache,
+            layout,
+            origin,
+            options,
+            Some(effect),
+            &color_of,
+        )?;
+        batch_layer(quads, &mut batches, |quad| atlas_texture(cache, quad))?;
+    }
+
+    let quads = rasterize_layer(renderer, cache, layout, origin, options, None, &color_of)?;
+    batch_layer(quads, &mut batches, |quad| atlas_texture(cache, quad))?;
+
+    // untextured rectangles such as underlines go on top
+    if !rects.is_empty() {
+        let mut batch = Batch {
+            texture: None,
+            pointer: std::ptr::null_mut(),
+            vertices: Vec::new(),
+            indices: Vec::new(),
+        };
+        for (rect, color) in rects {
+            let rect = graph_f::Rect::new(
+                origin.x + rect.position.x,
+                origin.y + rect.position.y,
+                rect.size.width,
+                rect.size.height,
+            );
+            batch.push_quad(rect, graph_f::Rect::new(0.0, 0.0, 0.0, 0.0), *color, None);
+        }
+        batches.push(batch);
+    }
+
+    submit(&mut AtlasCanvas { renderer, cache }, &batches, options.clip)
+}
+
+/// Where batches are drawn.
+trait Canvas {
+    fn clip_rect(&self) -> Result<Option<Rect>, TextError>;
+    fn set_clip_rect(&mut self, clip: Option<Rect>) -> Result<(), TextError>;
+    fn render_batch(&mut self, batch: &Batch) -> Result<(), TextError>;
+}
+
+/// A renderer drawing with the atlas textures of a glyph cache.
+struct AtlasCanvas<'a> {
+    renderer: &'a Renderer,
+    cache: &'a GlyphCache,
+}
+
+impl Canvas for AtlasCanvas<'_> {
+    fn clip_rect(&self) -> Result<Option<Rect>, TextError> {
+        Ok(self.renderer.clip_rect()?)
+    }
+
+    fn set_clip_rect(&mut self, clip: Option<Rect>) -> Result<(), TextError> {
+        Ok(self.renderer.set_clip_rect(clip)?)
+    }
+
+    fn render_batch(&mut self, batch: &Batch) -> Result<(), TextError> {
+        let texture = batch
+            .texture
+            .map(|(format, handle)| self.cache.atlas(format).get_texture(&handle));
+        Ok(self
+            .renderer
+            .render_geometry(texture, &batch.vertices, &batch.indices)?)
+    }
+}
+
+/// Draw the batches in order with one geometry call each, inside `clip` when one is given.
+///
+/// The clip rectangle of the canvas is restored afterwards, also when a batch fails to draw.
+fn submit(
+    canvas: &mut impl Canvas,
+    batches: &[Batch],
+    clip: Option<Rect>,
+) -> Result<(), TextError> {
+    let previous_clip = if clip.is_some() {
+        let previous = canvas.clip_rect()?;
+        canvas.set_clip_rect(clip)?;
+        Some(previous)
+    } else {
+        None
+    };
+
+    let result = batches
+        .iter()
+        .try_for_each(|batch| canvas.render_batch(batch));
+
+    if let Some(previous) = previous_clip {
+        canvas.set_clip_rect(previous)?;
+    }
+
+    result
+}
+
+/// Rasterize the glyphs of the text, or of one effect layer.
+///
+/// This happens before batching, the atlases can not be borrowed while the cache grows.
+fn rasterize_layer(
+    renderer: &mut Renderer,
+    cache: &mut GlyphCache,
+    layout: &Layout,
+    origin: graph_f::Point,
+    options: &DrawOptions,
+    effect: Option<&TextEffect>,
+    color_of: &impl Fn(usize) -> Color,
+) -> Result<Vec<Quad>, TextError> {
+    let mut quads = Vec::with_capacity(layout.glyphs().len());
+
+    for glyph in layout.glyphs() {
+        let run = &layout.runs()[glyph.run];
+        let rotated = layout.is_rotated(run);
+        let pen = graph_f::Point::new(origin.x + glyph.position.x, origin.y + glyph.position.y);
+        // the subpixel offset is along the baseline of the glyph, which points down when rotated
+        let (x, y, subpixel) = if rotated {
+            let (y, subpixel) = GlyphCache::pen_position(pen.y, &options.raster);
+            (pen.x.round(), y, subpixel)
+        } else {
+            let (x, subpixel) = GlyphCache::pen_position(pen.x, &options.raster);
+            (x, pen.y.round(), subpixel)
+        };
+
+        let cached = match effect {
+            Some(effect) => cache.get_effect(
+                renderer,
+                &run.face,
+                run.pixel_size,
+                glyph.glyph_id,
+                subpixel,
+                options.raster.hinting,
+                effect.effect,
+            )?,
+            None => cache.get(
+                renderer,
+                &run.face,
+                run.pixel_size,
+                glyph.glyph_id,
+                subpixel,
+                options.raster,
+            )?,
+        };
+        let Some(handle) = cached.texture else {
+            continue;
+        };
+
+        let text_color = color_of(glyph.cluster);
+        let color = match (effect, cached.format) {
+            // effect layers fade with the text they are drawn for
+            (Some(effect), _) => effect
+                .color
+                .with_alpha(mul_u8(effect.color.a, text_color.a)),
+            (None, GlyphFormat::Mask) => text_color,
+            // color glyphs keep their own colors, only the alpha applies
+            (None, GlyphFormat::Color) => Color::WHITE.with_alpha(text_color.a),
+        };
+        let rect = graph_f::Rect::new(
+            x + cached.bearing.x as f32,
+            y + cached.bearing.y as f32,
+            handle.rect.size.width as f32,
+            handle.rect.size.height as f32,
+        );
+        quads.push(Quad {
+            format: cached.format,
+            handle,
+            rect,
+            color,
+            pivot: rotated.then_some(graph_f::Point::new(x, y)),
+        });
+    }
+
+    Ok(quads)
+}
+
+/// The atlas texture a quad is drawn from and its size.
+fn atlas_texture(
+    cache: &GlyphCache,
+    quad: &Quad,
+) -> Result<(*mut SDL_Texture, graph_f::Size), TextError> {
+    let texture = cache.atlas(quad.format).get_texture(&quad.handle);
+    Ok((texture.get_pointer(), texture.size()?))
+}
+
+/// Append the batches of one layer, one per texture `texture_of` gives for the quads.
+fn batch_layer(
+    quads: Vec<Quad>,
+    batches: &mut Vec<Batch>,
+    texture_of: impl Fn(&Quad) -> Result<(*mut SDL_Texture, graph_f::Size), TextError>,
+) -> Result<(), TextError> {
+    let first = batches.len();
+
+    for quad in quads {
+        let (pointer, size) = texture_of(&quad)?;
+
+        let index = match batches[first..]
+            .iter()
+            .position(|batch| batch.pointer == pointer)
+        {
+            Some(index) => first + index,
+            None => {
+                batches.push(Batch {
+                    texture: Some((quad.format, quad.handle)),
+                    pointer,
+                    vertices: Vec::new(),
+                    indices: Vec::new(),
+                });
+                batches.len() - 1
+            }
+        };
+
+        let rect = quad.handle.rect;
+        let uv = graph_f::Rect::new(
+            rect.position.x as f32 / size.width,
+            rect.position.y as f32 / size.height,
+            rect.size.width as f32 / size.width,
+            rect.size.height as f32 / size.height,
+        );
+        batches[index].push_quad(quad.rect, uv, quad.color, quad.pivot);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ::sdl3_sys::render::SDL_CreateSoftwareRenderer;
+    use ::wscb_atlas::AtlasManager;
+    use ::wscb_sdl::graph::Surface;
+    use ::wscb_type::{
+        error::SdlError,
+        graph::{Point, Size},
+    };
+
+    use super::*;
+    use crate::raster::GLYPH_PIXEL_FORMAT;
+
+    /// A software renderer drawing into a surface, the surface has to outlive it.
+    fn renderer() -> Result<(Surface, Renderer), TextError> {
+        let surface = Surface::new(64, 64, GLYPH_PIXEL_FORMAT)?;
+        let renderer =
+            unsafe { Renderer::from_raw(SDL_CreateSoftwareRenderer(surface.get_pointer())) }
+                .ok_or_else(|| SdlError::sdl_err("failed to create renderer"))?;
+        Ok((surface, renderer))
+    }
+
+    fn quad(format: GlyphFormat, handle: TextureHandle, x: f32) -> Quad {
+        Quad {
+            format,
+            handle,
+            rect: graph_f::Rect::new(x, 0.0, 4.0, 4.0),
+            color: Color::WHITE,
+            pivot: None,
+        }
+    }
+
+    #[test]
+    fn batches_layers_by_texture() -> Result<(), TextError> {
+        let (_surface, mut renderer) = renderer()?;
+        let size = Size::new(16, 16);
+        let mut masks = AtlasManager::empty(&mut renderer, 0, size, GLYPH_PIXEL_FORMAT)?;
+        let mut colors = AtlasManager::empty(&mut renderer, 0, size, GLYPH_PIXEL_FORMAT)?;
+        let a = masks.allocate(&mut renderer, Size::new(4, 4))?;
+        let b = masks.allocate(&mut renderer, Size::new(4, 4))?;
+        let c = colors.allocate(&mut renderer, Size::new(4, 4))?;
+        assert_eq!(b.rect.position, Point::new(4, 0));
+
+        let texture_of = |quad: &Quad| {
+            let texture = match quad.format {
+                GlyphFormat::Mask => masks.get_texture(&quad.handle),
+                GlyphFormat::Color => colors.get_texture(&quad.handle),
+            };
+            Ok((texture.get_pointer(), texture.size()?))
+        };
+
+        let mut batches = Vec::new();
+        let layer = vec![
+            quad(GlyphFormat::Mask, a, 0.0),
+            quad(GlyphFormat::Color, c, 4.0),
+            quad(GlyphFormat::Mask, b, 8.0),
+        ];
+        batch_layer(layer, &mut batches, texture_of)?;
+        assert_eq!(batches.len(), 2);
+        assert_eq!(
+            (batches[0].vertices.len(), batches[0].indices.len()),
+            (8, 12)
+        );
+        assert_eq!(batches[0].indices[6..], [4, 5, 6, 4, 6, 7]);
+        // the second quad of the mask texture samples its own region
+        let corner = batches[0].vertices[4];
+        assert_eq!(
+            (corner.position.x, corner.tex_coord.x, corner.tex_coord.y),
+            (8.0, 0.25, 0.0)
+        );
+        assert_eq!(batches[1].vertices.len(), 4);
+
+        // a second layer never shares the batches of the first
+        batch_layer(
+            vec![quad(GlyphFormat::Mask, a, 0.0)],
+            &mut batches,
+            texture_of,
+        )?;
+        assert_eq!(batches.len(), 3);
+        assert_eq!(batches[2].pointer, batches[0].pointer);
+        Ok(())
+    }
+
+    #[test]
+    fn turns_rotated_quads_around_the_pen() {
+        let mut batch = Batch {
+            texture: None,
+            pointer: std::ptr::null_mut(),
+            vertices: Vec::new(),
+            indices: Vec::new(),
+        };
+        batch.push_quad(
+            graph_f::Rect::new(10.0, 0.0, 4.0, 2.0),
+            graph_f::Rect::new(0.0, 0.0, 1.0, 1.0),
+            Color::WHITE,
+            Some(graph_f::Point::new(10.0, 0.0)),
+        );
+        let corners: Vec<(f32, f32)> = (batch.vertices.iter())
+            .map(|vertex| (vertex.position.x, vertex.position.y))
+            .collect();
+        assert_eq!(corners, [(10.0, 0.0), (10.0, 4.0), (8.0, 4.0), (8.0, 0.0)]);
+    }
+
+    /// Records what is drawn, failing the batch at `fail_at`.
+    #[derive(Default)]
+    struct Recording {
+        clip: Option<Rect>,
+        clips: Vec<Option<Rect>>,
+        drawn: usize,
+        fail_at: Option<usize>,
+    }
+
+    impl Canvas for Recording {
+        fn clip_rect(&self) -> Result<Option<Rect>, TextError> {
+            Ok(self.clip)
+        }
+
+        fn set_clip_rect(&mut self, clip: Option<Rect>) -> Result<(), TextError> {
+            self.clip = clip;
+            self.clips.push(clip);
+            Ok(())
+        }
+
+        fn render_batch(&mut self, _batch: &Batch) -> Result<(), TextError> {
+            if self.fail_at == Some(self.drawn) {
+                return Err(SdlError::sdl_err("failed to render geometry").into());
+            }
+            self.drawn += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn restores_the_clip_rect() {
+        let batches: Vec<Batch> = (0..3)
+            .map(|_| Batch {
+                texture: None,
+                pointer: std::ptr::null_mut(),
+                vertices: Vec::new(),
+                indices: Vec::new(),
+            })
+            .collect();
+        let outer = Some(Rect::new(0, 0, 100, 100));
+        let clip = Some(Rect::new(10, 10, 20, 20));
+
+        let mut canvas = Recording {
+            clip: outer,
+            ..Recording::default()
+        };
+        assert!(submit(&mut canvas, &batches, clip).is_ok());
+        assert_eq!((canvas.drawn, canvas.clips), (3, vec![clip, outer]));
+
+        let mut canvas = Recording {
+            fail_at: Some(1),
+            ..Recording::default()
+        };
+        assert!(submit(&mut canvas, &batches, clip).is_err());
+        assert_eq!((canvas.drawn, canvas.clip), (1, None));
+
+        // without a clip the one of the canvas is left alone
+        let mut canvas = Recording {
+            clip: outer,
+            ..Recording::default()
+        };
+        assert!(submit(&mut canvas, &batches, None).is_ok());
+        assert!(canvas.clips.is_empty());
+    }
+}
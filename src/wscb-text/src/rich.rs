@@ -0,0 +1,284 @@
+use ::std::ops::Range;
+
+use ::wscb_type::{color::Color, graph_f};
+
+use crate::{
+    bidi::BidiText,
+    collection::FontFamily,
+    error::{MarkupError, TextError},
+    layout::Layout,
+    markup,
+    shape::{Feature, ShapeOptions, ShapedRun},
+};
+
+/// The style of a range of rich text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextStyle {
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub color: Color,
+    /// The pixel size of the font.
+    pub size: u32,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            bold: false,
+            italic: false,
+            underline: false,
+            color: Color::WHITE,
+            size: 16,
+        }
+    }
+}
+
+/// A range of an [`AttributedString`] with one style.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StyleSpan {
+    pub range: Range<usize>,
+    pub style: TextStyle,
+}
+
+/// Text with styles.
+///
+/// The spans cover the whole text in order, neighbours always differ in style.
+/// OpenType features can be set on ranges independently of the styles.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AttributedString {
+    text: String,
+    spans: Vec<StyleSpan>,
+    /// Features with the byte range they apply to.
+    features: Vec<Feature>,
+}
+
+impl AttributedString {
+    pub fn new(text: impl Into<String>, style: TextStyle) -> Self {
+        let mut string = Self::default();
+        string.push_str(&text.into(), style);
+        string
+    }
+
+    /// Parse markup like `[b]bold[/b] and [color=#f00]red[/color]`.
+    ///
+    /// See [`markup`] for the tags.
+    pub fn from_markup(markup: &str, base: TextStyle) -> Result<Self, MarkupError> {
+        markup::parse(markup, base)
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn spans(&self) -> &[StyleSpan] {
+        &self.spans
+    }
+
+    pub fn len(&self) -> usize {
+        self.text.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Append text with a style.
+    pub fn push_str(&mut self, text: &str, style: TextStyle) {
+        if text.is_empty() {
+            return;
+        }
+
+        let start = self.text.len();
+        self.text.push_str(text);
+
+        match self.spans.last_mut() {
+            Some(last) if last.style == style => last.range.end = self.text.len(),
+            _ => self.spans.push(StyleSpan {
+                range: start..self.text.len(),
+                style,
+            }),
+        }
+    }
+
+    /// The style of the character at the byte offset.
+    pub fn style_at(&self, offset: usize) -> Option<&TextStyle> {
+        let index = self.spans.partition_point(|span| span.range.end <= offset);
+        self.spans.get(index).map(|span| &span.style)
+    }
+
+    fn check_range(&self, range: &Range<usize>) -> Result<(), TextError> {
+        if range.start > range.end
+            || range.end > self.text.len()
+            || !self.text.is_char_boundary(range.start)
+            || !self.text.is_char_boundary(range.end)
+        {
+            return Err(TextError::InvalidRange {
+                start: range.start,
+                end: range.end,
+                len: self.text.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// The features set on ranges of the text, in the order they were added.
+    pub fn features(&self) -> &[Feature] {
+        &self.features
+    }
+
+    /// Set an OpenType feature on a range, like small capitals or tabular digits.
+    ///
+    /// Features added later win where ranges overlap, and all of them win
+    /// over the features of the [`ShapeOptions`] the text is shaped with.
+    pub fn add_feature(&mut self, range: Range<usize>, feature: Feature) -> Result<(), TextError> {
+        self.check_range(&range)?;
+        if !range.is_empty() {
+            self.features.push(feature.with_range(range));
+        }
+        Ok(())
+    }
+
+    /// Change the style of a range.
+    pub fn update_style(
+        &mut self,
+        range: Range<usize>,
+        mut update: impl FnMut(&mut TextStyle),
+    ) -> Result<(), TextError> {
+        self.check_range(&range)?;
+
+        let mut spans = Vec::with_capacity(self.spans.len() + 2);
+        for span in self.spans.drain(..) {
+            // split the span into the parts before, inside and after the range
+            let pieces = [
+                span.range.start..span.range.end.min(range.start),
+                span.range.start.max(range.start)..span.range.end.min(range.end),
+                span.range.start.max(range.end)..span.range.end,
+            ];
+            for (index, piece) in pieces.into_iter().enumerate() {
+                if piece.is_empty() {
+                    continue;
+                }
+                let mut style = span.style;
+                if index == 1 {
+                    update(&mut style);
+                }
+
+                match spans.last_mut() {
+                    Some(StyleSpan {
+                        range: last,
+                        style: last_style,
+                    }) if *last_style == style => last.end = piece.end,
+                    _ => spans.push(StyleSpan {
+                        range: piece,
+                        style,
+                    }),
+                }
+            }
+        }
+        self.spans = spans;
+
+        Ok(())
+    }
+
+    /// Shape every span with the faces of its style and the features of its range.
+    ///
+    /// Bidi levels are resolved for the whole text, so the runs can be given
+    /// to [`Layout::new`] together and wrap as one paragraph.
+    pub fn shape(
+        &self,
+        fonts: &FontFamily,
+        options: &ShapeOptions,
+    ) -> Result<Vec<ShapedRun>, TextError> {
+        let bidi = BidiText::new(&self.text, options.direction);
+        // feature ranges are byte offsets of the whole text, like the clusters
+        let mut options = options.clone();
+        options.features.extend(self.features.iter().copied());
+
+        let mut runs = Vec::new();
+        for span in &self.spans {
+            let collection = fonts.select(span.style.bold, span.style.italic);
+            runs.extend(collection.shape_range(
+                &self.text,
+                span.range.clone(),
+                &bidi,
+                span.style.size,
+                &options,
+            )?);
+        }
+
+        Ok(runs)
+    }
+
+    /// The underlines of a layout of this text, with their colors.
+    ///
+    /// Trailing spaces of a line are not underlined.
+    pub fn underlines(&self, layout: &Layout) -> Vec<(graph_f::Rect, Color)> {
+        let mut underlines = Vec::new();
+
+        for line in layout.lines() {
+            let left = line.rect.position.x;
+            let right = left + line.rect.size.width;
+            let y = line.baseline + line.metrics.underline_position;
+            let thickness = line.metrics.underline_thickness;
+
+            let mut current: Option<(f32, f32, Color)> = None;
+            for glyph in layout.line_glyphs(line) {
+                let style = self.style_at(glyph.cluster).filter(|style| style.underline);
+                let start = glyph.rect.position.x.max(left);
+                let end = (glyph.rect.position.x + glyph.rect.size.width).min(right);
+
+                let extends = matches!(
+                    (&current, style),
+                    (Some((_, _, color)), Some(style)) if *color == style.color
+                );
+                if extends {
+                    if let Some((_, current_end, _)) = &mut current {
+                        *current_end = current_end.max(end);
+                    }
+                    continue;
+                }
+
+                if let Some((start, end, color)) = current.take()
+                    && end > start
+                {
+                    underlines.push((graph_f::Rect::new(start, y, end - start, thickness), color));
+                }
+                current = style.map(|style| (start, end, style.color));
+            }
+
+            if let Some((start, end, color)) = current
+                && end > start
+            {
+                underlines.push((graph_f::Rect::new(start, y, end - start, thickness), color));
+            }
+        }
+
+        underlines
+    }
+}
+
+impl From<&str> for AttributedString {
+    fn from(value: &str) -> Self {
+        Self::new(value, TextStyle::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn updates_and_merges_spans() -> Result<(), TextError> {
+        let mut string = AttributedString::from("hello world");
+        string.update_style(6..11, |style| style.bold = true)?;
+        assert_eq!(string.spans().len(), 2);
+        assert_eq!(string.spans()[1].range, 6..11);
+        assert!(string.style_at(6).is_some_and(|style| style.bold));
+        assert!(string.style_at(5).is_some_and(|style| !style.bold));
+
+        string.update_style(0..11, |style| style.bold = true)?;
+        assert_eq!(string.spans().len(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,188 @@
+//! Splitting text into graphemes, words and sentences, following UAX #29.
+//!
+//! A grapheme is what a user sees as one character, like `e` with a combining
+//! accent or a family emoji joined by ZWJs. Carets, deletion, wrapping and
+//! truncation only ever cut text between graphemes. All offsets are bytes.
+
+use ::std::ops::Range;
+
+use ::unicode_segmentation::{GraphemeCursor, UnicodeSegmentation};
+
+/// What a [`WordSegment`] contains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WordKind {
+    /// Letters or digits, like `can't`, `3.14` or `日本`.
+    Word,
+    Whitespace,
+    /// Punctuation, symbols and emoji.
+    Other,
+}
+
+/// A piece of text between two word boundaries.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WordSegment {
+    pub range: Range<usize>,
+    pub kind: WordKind,
+}
+
+impl WordSegment {
+    pub fn is_word(&self) -> bool {
+        self.kind == WordKind::Word
+    }
+}
+
+/// Whether a character ends a line.
+pub fn is_line_separator(ch: char) -> bool {
+    matches!(
+        ch,
+        '\n' | '\r' | '\u{0B}' | '\u{0C}' | '\u{85}' | '\u{2028}' | '\u{2029}'
+    )
+}
+
+/// The graphemes of a text, as byte ranges.
+pub fn graphemes(text: &str) -> impl DoubleEndedIterator<Item = Range<usize>> + '_ {
+    text.grapheme_indices(true)
+        .map(|(start, grapheme)| start..start + grapheme.len())
+}
+
+/// The number of graphemes in a text.
+pub fn grapheme_count(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+/// Whether a byte offset is between two graphemes, the start and end of the text included.
+pub fn is_grapheme_boundary(text: &str, offset: usize) -> bool {
+    if offset > text.len() || !text.is_char_boundary(offset) {
+        return false;
+    }
+    // the whole text is one chunk, so the cursor never asks for more context
+    GraphemeCursor::new(offset, text.len(), true)
+        .is_boundary(text, 0)
+        .unwrap_or(true)
+}
+
+/// The grapheme boundary before a byte offset, 0 at the start.
+pub fn previous_grapheme_boundary(text: &str, offset: usize) -> usize {
+    let offset = grapheme_floor(text, offset);
+    GraphemeCursor::new(offset, text.len(), true)
+        .prev_boundary(text, 0)
+        .ok()
+        .flatten()
+        .unwrap_or(0)
+}
+
+/// The grapheme boundary after a byte offset, the length of the text at the end.
+pub fn next_grapheme_boundary(text: &str, offset: usize) -> usize {
+    let offset = grapheme_floor(text, offset);
+    GraphemeCursor::new(offset, text.len(), true)
+        .next_boundary(text, 0)
+        .ok()
+        .flatten()
+        .unwrap_or(text.len())
+}
+
+/// The grapheme boundary at or before a byte offset.
+pub fn grapheme_floor(text: &str, offset: usize) -> usize {
+    if offset >= text.len() {
+        return text.len();
+    }
+    let mut offset = offset;
+    while !is_grapheme_boundary(text, offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+/// The word segments of a text, words and the spaces and punctuation between them.
+pub fn words(text: &str) -> impl DoubleEndedIterator<Item = WordSegment> + '_ {
+    text.split_word_bound_indices().map(|(start, segment)| {
+        let kind = if segment.chars().any(char::is_alphanumeric) {
+            WordKind::Word
+        } else if segment.chars().all(char::is_whitespace) {
+            WordKind::Whitespace
+        } else {
+            WordKind::Other
+        };
+        WordSegment {
+            range: start..start + segment.len(),
+            kind,
+        }
+    })
+}
+
+/// The word segment containing a byte offset, the last one at the end of the text.
+pub fn word_at(text: &str, offset: usize) -> Option<WordSegment> {
+    words(text).find(|segment| segment.range.contains(&offset) || segment.range.end == text.len())
+}
+
+/// The start of the word before a byte offset, 0 when there is none.
+pub fn previous_word_start(text: &str, offset: usize) -> usize {
+    words(text)
+        .rev()
+        .find(|segment| segment.range.start < offset && segment.is_word())
+        .map_or(0, |segment| segment.range.start)
+}
+
+/// The end of the word after a byte offset, or of the word it is in.
+///
+/// The length of the text when there is none.
+pub fn next_word_end(text: &str, offset: usize) -> usize {
+    words(text)
+        .find(|segment| segment.range.end > offset && segment.is_word())
+        .map_or(text.len(), |segment| segment.range.end)
+}
+
+/// The sentences of a text with the spaces after them, as byte ranges.
+pub fn sentences(text: &str) -> impl Iterator<Item = Range<usize>> + '_ {
+    text.split_sentence_bound_indices()
+        .map(|(start, sentence)| start..start + sentence.len())
+}
+
+/// The sentence containing a byte offset, the last one at the end of the text.
+pub fn sentence_at(text: &str, offset: usize) -> Option<Range<usize>> {
+    sentences(text).find(|range| range.contains(&offset) || range.end == text.len())
+}
+
+/// The first `max` graphemes of a text.
+pub fn truncate(text: &str, max: usize) -> &str {
+    match graphemes(text).nth(max) {
+        Some(range) => &text[..range.start],
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_graphemes_together() {
+        let family = "👨\u{200D}👩\u{200D}👧";
+        let text = format!("e\u{301}{family}!");
+        let ranges: Vec<Range<usize>> = graphemes(&text).collect();
+        assert_eq!(
+            ranges,
+            [0..3, 3..3 + family.len(), 3 + family.len()..text.len()]
+        );
+
+        assert!(!is_grapheme_boundary(&text, 1));
+        assert_eq!(grapheme_floor(&text, 5), 3);
+        assert_eq!(next_grapheme_boundary(&text, 3), 3 + family.len());
+        assert_eq!(previous_grapheme_boundary(&text, text.len() - 1), 3);
+        assert_eq!(truncate(&text, 2), &text[..3 + family.len()]);
+        assert_eq!(grapheme_count(&text), 3);
+    }
+
+    #[test]
+    fn finds_words_and_sentences() {
+        let text = "Don't stop. Go on!";
+        let kinds: Vec<WordKind> = words(text).map(|segment| segment.kind).collect();
+        assert_eq!(
+            kinds[..3],
+            [WordKind::Word, WordKind::Whitespace, WordKind::Word]
+        );
+        assert_eq!(next_word_end(text, 0), 5);
+        assert_eq!(previous_word_start(text, 10), 6);
+        assert_eq!(sentence_at(text, 13), Some(12..18));
+    }
+}
@@ -0,0 +1,558 @@
+use ::std::{ops::Range, ptr::NonNull, str::FromStr};
+
+use ::unicode_script::UnicodeScript;
+use ::wscb_type::graph_f;
+
+use crate::{
+    error::TextError,
+    ffi::harfbuzz::{
+        HB_BUFFER_FLAG_BOT, HB_BUFFER_FLAG_DEFAULT, HB_BUFFER_FLAG_EOT, HB_DIRECTION_BTT,
+        HB_DIRECTION_INVALID, HB_DIRECTION_LTR, HB_DIRECTION_RTL, HB_DIRECTION_TTB,
+        HB_FEATURE_GLOBAL_END, HB_FEATURE_GLOBAL_START, HB_MEMORY_MODE_READONLY, hb_blob_create,
+        hb_blob_destroy, hb_buffer_add_utf8, hb_buffer_create, hb_buffer_destroy,
+        hb_buffer_get_direction, hb_buffer_get_glyph_infos, hb_buffer_get_glyph_positions,
+        hb_buffer_guess_segment_properties, hb_buffer_set_direction, hb_buffer_set_flags,
+        hb_buffer_set_language, hb_buffer_set_script, hb_buffer_t, hb_direction_t, hb_face_create,
+        hb_face_destroy, hb_feature_from_string, hb_feature_t, hb_font_create, hb_font_destroy,
+        hb_font_set_ppem, hb_font_set_scale, hb_font_set_variations, hb_font_t,
+        hb_language_from_string, hb_language_t, hb_language_to_string, hb_script_from_iso15924_tag,
+        hb_script_t, hb_shape, hb_tag, hb_variation_t,
+    },
+    font::Face,
+    variation::Variation,
+};
+
+/// The HarfBuzz font object of a [`Face`].
+///
+/// It reads the font data owned by the face without copying it.
+#[derive(Debug)]
+pub(crate) struct HbFont {
+    pointer: NonNull<hb_font_t>,
+}
+
+impl HbFont {
+    pub(crate) fn new(
+        data: &[u8],
+        face_index: u32,
+        variations: &[Variation],
+    ) -> Result<Self, TextError> {
+        let length: u32 = data
+            .len()
+            .try_into()
+            .map_err(|_| TextError::FontTooLarge(data.len()))?;
+
+        unsafe {
+            let blob = hb_blob_create(
+                data.as_ptr().cast(),
+                length,
+                HB_MEMORY_MODE_READONLY,
+                std::ptr::null_mut(),
+                None,
+            );
+            let face = hb_face_create(blob, face_index);
+            let font = hb_font_create(face);
+
+            // the font keeps its own references to the face and the blob
+            hb_face_destroy(face);
+            hb_blob_destroy(blob);
+
+            if !font.is_null() && !variations.is_empty() {
+                let variations: Vec<hb_variation_t> = variations
+                    .iter()
+                    .map(|variation| variation.to_harfbuzz())
+                    .collect();
+                hb_font_set_variations(font, variations.as_ptr(), variations.len() as u32);
+            }
+
+            NonNull::new(font)
+                .map(|pointer| Self { pointer })
+                .ok_or(TextError::HarfBuzz("creating font"))
+        }
+    }
+
+    pub(crate) fn get_pointer(&self) -> *mut hb_font_t {
+        self.pointer.as_ptr()
+    }
+}
+
+impl Drop for HbFont {
+    fn drop(&mut self) {
+        unsafe {
+            hb_font_destroy(self.pointer.as_ptr());
+        }
+    }
+}
+
+/// The direction that a run of text is laid out in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    LeftToRight,
+    RightToLeft,
+    TopToBottom,
+    BottomToTop,
+}
+
+impl Direction {
+    pub fn is_horizontal(self) -> bool {
+        matches!(self, Direction::LeftToRight | Direction::RightToLeft)
+    }
+
+    pub fn is_vertical(self) -> bool {
+        !self.is_horizontal()
+    }
+
+    /// Whether the glyphs of the run come out in the reverse of the logical order.
+    pub fn is_backward(self) -> bool {
+        matches!(self, Direction::RightToLeft | Direction::BottomToTop)
+    }
+
+    fn to_harfbuzz(self) -> hb_direction_t {
+        match self {
+            Direction::LeftToRight => HB_DIRECTION_LTR,
+            Direction::RightToLeft => HB_DIRECTION_RTL,
+            Direction::TopToBottom => HB_DIRECTION_TTB,
+            Direction::BottomToTop => HB_DIRECTION_BTT,
+        }
+    }
+
+    fn from_harfbuzz(direction: hb_direction_t) -> Option<Self> {
+        match direction {
+            HB_DIRECTION_LTR => Some(Direction::LeftToRight),
+            HB_DIRECTION_RTL => Some(Direction::RightToLeft),
+            HB_DIRECTION_TTB => Some(Direction::TopToBottom),
+            HB_DIRECTION_BTT => Some(Direction::BottomToTop),
+            _ => None,
+        }
+    }
+}
+
+/// A Unicode script, stored as its ISO 15924 tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Script(hb_script_t);
+
+impl Script {
+    pub const COMMON: Self = Self(hb_tag(b"Zyyy"));
+    pub const INHERITED: Self = Self(hb_tag(b"Zinh"));
+    pub const UNKNOWN: Self = Self(hb_tag(b"Zzzz"));
+    pub const LATIN: Self = Self(hb_tag(b"Latn"));
+    pub const GREEK: Self = Self(hb_tag(b"Grek"));
+    pub const CYRILLIC: Self = Self(hb_tag(b"Cyrl"));
+    pub const ARABIC: Self = Self(hb_tag(b"Arab"));
+    pub const HEBREW: Self = Self(hb_tag(b"Hebr"));
+    pub const DEVANAGARI: Self = Self(hb_tag(b"Deva"));
+    pub const THAI: Self = Self(hb_tag(b"Thai"));
+    pub const HAN: Self = Self(hb_tag(b"Hani"));
+    pub const HIRAGANA: Self = Self(hb_tag(b"Hira"));
+    pub const KATAKANA: Self = Self(hb_tag(b"Kana"));
+    pub const HANGUL: Self = Self(hb_tag(b"Hang"));
+
+    /// Get the script of an ISO 15924 code like `"Arab"`.
+    pub fn from_iso15924(code: &str) -> Option<Self> {
+        let tag: [u8; 4] = code.as_bytes().try_into().ok()?;
+        let script = unsafe { hb_script_from_iso15924_tag(hb_tag(&tag)) };
+        (script != 0).then_some(Self(script))
+    }
+
+    /// The ISO 15924 code of the script.
+    pub fn to_iso15924(self) -> String {
+        String::from_utf8_lossy(&self.0.to_be_bytes()).into_owned()
+    }
+
+    /// The script of a character, from the Unicode `Script` property.
+    pub fn of(ch: char) -> Self {
+        Self(ch.script().as_iso15924_tag())
+    }
+
+    /// Whether characters of this script take the script of the text around them,
+    /// like spaces, digits and combining marks.
+    pub fn is_neutral(self) -> bool {
+        self == Self::COMMON || self == Self::INHERITED || self == Self::UNKNOWN
+    }
+}
+
+/// A BCP 47 language tag, interned by HarfBuzz.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Language(hb_language_t);
+
+impl Language {
+    /// Get the language of a BCP 47 tag like `"ar"` or `"zh-Hant"`.
+    pub fn new(tag: &str) -> Option<Self> {
+        let length = tag.len().try_into().ok()?;
+        let language = unsafe { hb_language_from_string(tag.as_ptr().cast(), length) };
+        (!language.is_null()).then_some(Self(language))
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        unsafe {
+            let tag = hb_language_to_string(self.0);
+            if tag.is_null() {
+                return "";
+            }
+            std::ffi::CStr::from_ptr(tag).to_str().unwrap_or("")
+        }
+    }
+}
+
+/// An OpenType feature setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Feature {
+    pub tag: [u8; 4],
+    /// `0` disables the feature, `1` enables it, larger values pick an alternate.
+    pub value: u32,
+    /// The byte range of the text the feature applies to, `None` for the whole run.
+    pub range: Option<(usize, usize)>,
+}
+
+impl Feature {
+    pub fn new(tag: &[u8; 4], value: u32) -> Self {
+        Self {
+            tag: *tag,
+            value,
+            range: None,
+        }
+    }
+
+    /// Restrict the feature to the byte range of the text.
+    #[must_use]
+    pub fn with_range(mut self, range: Range<usize>) -> Self {
+        self.range = Some((range.start, range.end));
+        self
+    }
+
+    fn to_harfbuzz(self) -> hb_feature_t {
+        let (start, end) = self
+            .range
+            .map(|(start, end)| {
+                (
+                    u32::try_from(start).unwrap_or(HB_FEATURE_GLOBAL_END),
+                    u32::try_from(end).unwrap_or(HB_FEATURE_GLOBAL_END),
+                )
+            })
+            .unwrap_or((HB_FEATURE_GLOBAL_START, HB_FEATURE_GLOBAL_END));
+
+        hb_feature_t {
+            tag: hb_tag(&self.tag),
+            value: self.value,
+            start,
+            end,
+        }
+    }
+}
+
+impl FromStr for Feature {
+    type Err = TextError;
+
+    /// Parse the CSS like feature syntax of HarfBuzz, like `"-liga"`, `"smcp"` or `"aalt=2"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let length = s
+            .len()
+            .try_into()
+            .map_err(|_| TextError::HarfBuzz("parsing feature"))?;
+
+        let mut feature = hb_feature_t {
+            tag: 0,
+            value: 0,
+            start: 0,
+            end: 0,
+        };
+
+        if unsafe { hb_feature_from_string(s.as_ptr().cast(), length, &mut feature) } == 0 {
+            return Err(TextError::HarfBuzz("parsing feature"));
+        }
+
+        let range = (feature.start != HB_FEATURE_GLOBAL_START
+            || feature.end != HB_FEATURE_GLOBAL_END)
+            .then_some((feature.start as usize, feature.end as usize));
+
+        Ok(Self {
+            tag: feature.tag.to_be_bytes(),
+            value: feature.value,
+            range,
+        })
+    }
+}
+
+/// How a run of text is shaped.
+///
+/// Properties left as `None` are guessed from the text.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ShapeOptions {
+    pub direction: Option<Direction>,
+    pub script: Option<Script>,
+    pub language: Option<Language>,
+    pub features: Vec<Feature>,
+}
+
+/// A glyph produced by the shaper.
+///
+/// Positions are in pixels and y goes down.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapedGlyph {
+    pub glyph_id: u32,
+    /// The byte offset of the first character of the cluster in the text.
+    pub cluster: usize,
+    pub advance: graph_f::Point,
+    pub offset: graph_f::Point,
+}
+
+/// The shaped glyphs of a run of text, in visual order.
+#[derive(Debug, Clone)]
+pub struct ShapedRun {
+    pub face: Face,
+    pub pixel_size: u32,
+    /// The byte range of the run in the text.
+    pub range: Range<usize>,
+    pub direction: Direction,
+    pub glyphs: Vec<ShapedGlyph>,
+}
+
+impl ShapedRun {
+    /// The total advance of the run.
+    pub fn advance(&self) -> graph_f::Point {
+        self.glyphs
+            .iter()
+            .fold(graph_f::Point::new(0.0, 0.0), |sum, glyph| {
+                graph_f::Point::new(sum.x + glyph.advance.x, sum.y + glyph.advance.y)
+            })
+    }
+}
+
+struct HbBuffer {
+    pointer: NonNull<hb_buffer_t>,
+}
+
+impl Drop for HbBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            hb_buffer_destroy(self.pointer.as_ptr());
+        }
+    }
+}
+
+impl Face {
+    /// Shape the `range` of `text`.
+    ///
+    /// The text around the range is used as context, so an Arabic word split
+    /// into several runs still joins correctly. Cluster values of the result
+    /// are byte offsets into `text`.
+    pub fn shape(
+        &self,
+        text: &str,
+        range: Range<usize>,
+        pixel_size: u32,
+        options: &ShapeOptions,
+    ) -> Result<ShapedRun, TextError> {
+        if range.start > range.end
+            || range.end > text.len()
+            || !text.is_char_boundary(range.start)
+            || !text.is_char_boundary(range.end)
+        {
+            return Err(TextError::InvalidRange {
+                start: range.start,
+                end: range.end,
+                len: text.len(),
+            });
+        }
+
+        let text_length: i32 = text
+            .len()
+            .try_into()
+            .map_err(|_| TextError::HarfBuzz("adding text longer than 2GiB"))?;
+
+        let font = self.hb_font()?.get_pointer();
+        let scale = (pixel_size as i32).saturating_mul(64);
+
+        unsafe {
+            hb_font_set_scale(font, scale, scale);
+            hb_font_set_ppem(font, pixel_size, pixel_size);
+
+            let buffer = HbBuffer {
+                pointer: NonNull::new(hb_buffer_create())
+                    .ok_or(TextError::HarfBuzz("creating buffer"))?,
+            };
+            let raw = buffer.pointer.as_ptr();
+
+            let mut flags = HB_BUFFER_FLAG_DEFAULT;
+            if range.start == 0 {
+                flags |= HB_BUFFER_FLAG_BOT;
+            }
+            if range.end == text.len() {
+                flags |= HB_BUFFER_FLAG_EOT;
+            }
+            hb_buffer_set_flags(raw, flags);
+
+            hb_buffer_add_utf8(
+                raw,
+                text.as_ptr().cast(),
+                text_length,
+                range.start as u32,
+                (range.end - range.start) as i32,
+            );
+
+            if let Some(direction) = options.direction {
+                hb_buffer_set_direction(raw, direction.to_harfbuzz());
+            }
+            if let Some(script) = options.script {
+                hb_buffer_set_script(raw, script.0);
+            }
+            if let Some(language) = options.language {
+                hb_buffer_set_language(raw, language.0);
+            }
+            hb_buffer_guess_segment_properties(raw);
+
+            let direction = hb_buffer_get_direction(raw);
+            let direction = if direction == HB_DIRECTION_INVALID {
+                Direction::LeftToRight
+            } else {
+                Direction::from_harfbuzz(direction).unwrap_or(Direction::LeftToRight)
+            };
+
+            let features: Vec<hb_feature_t> = options
+                .features
+                .iter()
+                .map(|feature| feature.to_harfbuzz())
+                .collect();
+
+            hb_shape(font, raw, features.as_ptr(), features.len() as u32);
+
+            let mut length = 0;
+            let infos = hb_buffer_get_glyph_infos(raw, &mut length);
+            let positions = hb_buffer_get_glyph_positions(raw, &mut length);
+
+            let glyphs = if infos.is_null() || positions.is_null() || length == 0 {
+                Vec::new()
+            } else {
+                let infos = std::slice::from_raw_parts(infos, length as usize);
+                let positions = std::slice::from_raw_parts(positions, length as usize);
+
+                infos
+                    .iter()
+                    .zip(positions)
+                    .map(|(info, position)| ShapedGlyph {
+                        glyph_id: info.codepoint,
+                        cluster: info.cluster as usize,
+                        advance: graph_f::Point::new(
+                            position.x_advance as f32 / 64.0,
+                            -position.y_advance as f32 / 64.0,
+                        ),
+                        offset: graph_f::Point::new(
+                            position.x_offset as f32 / 64.0,
+                            -position.y_offset as f32 / 64.0,
+                        ),
+                    })
+                    .collect()
+            };
+
+            Ok(ShapedRun {
+                face: self.clone(),
+                pixel_size,
+                range,
+                direction,
+                glyphs,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_font::{SPACE, SQUARE, TestFont};
+
+    fn clusters(run: &ShapedRun) -> Vec<usize> {
+        run.glyphs.iter().map(|glyph| glyph.cluster).collect()
+    }
+
+    #[test]
+    fn shapes_clusters_and_advances() -> Result<(), TextError> {
+        let face = TestFont::new().face()?;
+        let text = "ab cé";
+        let run = face.shape(text, 0..text.len(), 10, &ShapeOptions::default())?;
+
+        assert_eq!(run.direction, Direction::LeftToRight);
+        assert_eq!(
+            run.glyphs
+                .iter()
+                .map(|glyph| glyph.glyph_id)
+                .collect::<Vec<_>>(),
+            [SQUARE, SQUARE, SPACE, SQUARE, 0]
+        );
+        // clusters are byte offsets, `é` takes two bytes
+        assert_eq!(clusters(&run), [0, 1, 2, 3, 4]);
+        assert_eq!(
+            run.glyphs
+                .iter()
+                .map(|glyph| glyph.advance.x)
+                .collect::<Vec<_>>(),
+            [10.0, 10.0, 5.0, 10.0, 10.0]
+        );
+        assert_eq!(run.advance(), graph_f::Point::new(45.0, 0.0));
+
+        // the clusters of a range stay offsets into the whole text
+        let run = face.shape(text, 1..4, 10, &ShapeOptions::default())?;
+        assert_eq!((run.range.clone(), clusters(&run)), (1..4, vec![1, 2, 3]));
+
+        assert!(matches!(
+            face.shape(text, 3..5, 10, &ShapeOptions::default()),
+            Err(TextError::InvalidRange {
+                start: 3,
+                end: 5,
+                len: 6
+            })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn shapes_right_to_left_in_visual_order() -> Result<(), TextError> {
+        let face = TestFont::new().face()?;
+
+        let text = "אב";
+        let run = face.shape(text, 0..text.len(), 10, &ShapeOptions::default())?;
+        assert_eq!(run.direction, Direction::RightToLeft);
+        assert_eq!(clusters(&run), [2, 0]);
+
+        let options = ShapeOptions {
+            direction: Some(Direction::RightToLeft),
+            ..ShapeOptions::default()
+        };
+        let run = face.shape("abc", 0..3, 10, &options)?;
+        assert_eq!(run.direction, Direction::RightToLeft);
+        assert_eq!(clusters(&run), [2, 1, 0]);
+        assert_eq!(run.advance().x, 30.0);
+        Ok(())
+    }
+
+    #[test]
+    fn parses_features() -> Result<(), TextError> {
+        assert_eq!("-liga".parse::<Feature>()?, Feature::new(b"liga", 0));
+        assert_eq!("smcp".parse::<Feature>()?, Feature::new(b"smcp", 1));
+        assert_eq!("aalt=2".parse::<Feature>()?, Feature::new(b"aalt", 2));
+        assert_eq!(
+            "kern[3:5]".parse::<Feature>()?,
+            Feature::new(b"kern", 1).with_range(3..5)
+        );
+        assert!("".parse::<Feature>().is_err());
+        assert!("=1".parse::<Feature>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn passes_feature_ranges_in_bytes() {
+        let global = Feature::new(b"liga", 1).to_harfbuzz();
+        assert_eq!(
+            (global.start, global.end),
+            (HB_FEATURE_GLOBAL_START, HB_FEATURE_GLOBAL_END)
+        );
+
+        let ranged = Feature::new(b"liga", 1).with_range(2..5).to_harfbuzz();
+        assert_eq!((ranged.tag, ranged.value), (hb_tag(b"liga"), 1));
+        assert_eq!((ranged.start, ranged.end), (2, 5));
+
+        // offsets past what HarfBuzz can address run to the end
+        let open = Feature::new(b"liga", 1)
+            .with_range(2..usize::MAX)
+            .to_harfbuzz();
+        assert_eq!((open.start, open.end), (2, HB_FEATURE_GLOBAL_END));
+    }
+}
@@ -0,0 +1,864 @@
+use ::std::{
+    borrow::Borrow,
+    collections::BTreeMap,
+    hash::{Hash, Hasher},
+    rc::Rc,
+    slice,
+};
+
+use ::wscb_type::HashMap;
+
+use crate::{
+    collection::{FontCollection, FontFamily},
+    error::TextError,
+    font::{Face, FaceId},
+    layout::{Align, Layout, LayoutOptions, LineHeight},
+    rich::{AttributedString, StyleSpan},
+    shape::{Direction, Feature, ShapeOptions, ShapedGlyph, ShapedRun},
+    vertical::WritingMode,
+};
+
+/// The number of shaped texts and of layouts a [`ShapeCache::new`] keeps each.
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+/// The styles of a family, in the order attributed keys list their collections.
+const FAMILY_STYLES: [(bool, bool); 4] =
+    [(false, false), (true, false), (false, true), (true, true)];
+
+/// The styles a text is shaped with.
+#[derive(Debug, Clone)]
+enum Styles {
+    /// Plain text at one pixel size.
+    Plain(u32),
+    /// The spans and range features of an [`AttributedString`].
+    Attributed(Vec<StyleSpan>, Vec<Feature>),
+}
+
+impl Styles {
+    fn parts(&self) -> StyleParts<'_> {
+        match self {
+            Self::Plain(pixel_size) => StyleParts::Plain(*pixel_size),
+            Self::Attributed(spans, features) => StyleParts::Attributed(spans, features),
+        }
+    }
+}
+
+/// [`Styles`], borrowed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum StyleParts<'a> {
+    Plain(u32),
+    Attributed(&'a [StyleSpan], &'a [Feature]),
+}
+
+/// The fallback lists of the collections that shaped a text, one for plain
+/// text and one per style of a family for attributed text.
+///
+/// Families that fall back the same way share entries.
+#[derive(Debug, Clone, Copy)]
+enum Faces<'a> {
+    /// The ids kept by a [`ShapeKey`].
+    Ids(&'a [Vec<FaceId>]),
+    Collection(&'a FontCollection),
+    Family(&'a FontFamily),
+}
+
+impl<'a> Faces<'a> {
+    fn list(self, index: usize) -> Option<FaceIds<'a>> {
+        match self {
+            Self::Ids(lists) => lists.get(index).map(|ids| FaceIds::Ids(ids.iter())),
+            Self::Collection(fonts) => (index == 0).then(|| FaceIds::Faces(fonts.faces().iter())),
+            Self::Family(family) => FAMILY_STYLES
+                .get(index)
+                .map(|&(bold, italic)| FaceIds::Faces(family.select(bold, italic).faces().iter())),
+        }
+    }
+
+    fn lists(self) -> impl Iterator<Item = FaceIds<'a>> {
+        (0..).map_while(move |index| self.list(index))
+    }
+}
+
+impl PartialEq for Faces<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        let mut lists = self.lists();
+        let mut others = other.lists();
+        loop {
+            match (lists.next(), others.next()) {
+                (Some(list), Some(other)) => {
+                    if !list.eq(other) {
+                        return false;
+                    }
+                }
+                (None, None) => return true,
+                _ => return false,
+            }
+        }
+    }
+}
+
+impl Eq for Faces<'_> {}
+
+impl Hash for Faces<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for list in self.lists() {
+            let mut len = 0_usize;
+            for face in list {
+                face.hash(state);
+                len += 1;
+            }
+            len.hash(state);
+        }
+    }
+}
+
+/// The ids of one fallback list of [`Faces`].
+enum FaceIds<'a> {
+    Ids(slice::Iter<'a, FaceId>),
+    Faces(slice::Iter<'a, Face>),
+}
+
+impl Iterator for FaceIds<'_> {
+    type Item = FaceId;
+
+    fn next(&mut self) -> Option<FaceId> {
+        match self {
+            Self::Ids(ids) => ids.next().copied(),
+            Self::Faces(faces) => faces.next().map(Face::id),
+        }
+    }
+}
+
+/// What a shaped text is looked up by, borrowed from a [`ShapeKey`] or from
+/// the arguments of [`ShapeCache::shape`], so lookups copy nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct ShapeParts<'a> {
+    text: &'a str,
+    faces: Faces<'a>,
+    styles: StyleParts<'a>,
+    options: &'a ShapeOptions,
+    writing_mode: WritingMode,
+}
+
+impl ShapeParts<'_> {
+    fn to_key(self) -> ShapeKey {
+        ShapeKey {
+            text: self.text.to_string(),
+            faces: self.faces.lists().map(|list| list.collect()).collect(),
+            styles: match self.styles {
+                StyleParts::Plain(pixel_size) => Styles::Plain(pixel_size),
+                StyleParts::Attributed(spans, features) => {
+                    Styles::Attributed(spans.to_vec(), features.to_vec())
+                }
+            },
+            options: self.options.clone(),
+            writing_mode: self.writing_mode,
+        }
+    }
+}
+
+/// Identifies the shaping of a text.
+#[derive(Debug, Clone)]
+struct ShapeKey {
+    text: String,
+    faces: Vec<Vec<FaceId>>,
+    styles: Styles,
+    options: ShapeOptions,
+    writing_mode: WritingMode,
+}
+
+impl ShapeKey {
+    fn uses_face(&self, face: FaceId) -> bool {
+        self.faces.iter().flatten().any(|&used| used == face)
+    }
+}
+
+/// A [`ShapeKey`] or the parts of one, the map of shaped texts is searched by these.
+trait ShapeLookup {
+    fn parts(&self) -> ShapeParts<'_>;
+}
+
+impl ShapeLookup for ShapeKey {
+    fn parts(&self) -> ShapeParts<'_> {
+        ShapeParts {
+            text: &self.text,
+            faces: Faces::Ids(&self.faces),
+            styles: self.styles.parts(),
+            options: &self.options,
+            writing_mode: self.writing_mode,
+        }
+    }
+}
+
+impl ShapeLookup for ShapeParts<'_> {
+    fn parts(&self) -> ShapeParts<'_> {
+        *self
+    }
+}
+
+impl<'a> Borrow<dyn ShapeLookup + 'a> for ShapeKey {
+    fn borrow(&self) -> &(dyn ShapeLookup + 'a) {
+        self
+    }
+}
+
+impl PartialEq for dyn ShapeLookup + '_ {
+    fn eq(&self, other: &Self) -> bool {
+        self.parts() == other.parts()
+    }
+}
+
+impl Eq for dyn ShapeLookup + '_ {}
+
+impl Hash for dyn ShapeLookup + '_ {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.parts().hash(state);
+    }
+}
+
+impl PartialEq for ShapeKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.parts() == other.parts()
+    }
+}
+
+impl Eq for ShapeKey {}
+
+impl Hash for ShapeKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.parts().hash(state);
+    }
+}
+
+/// The glyphs of an ellipsis, compared by their ids.
+#[derive(Debug, Clone, Copy)]
+struct EllipsisGlyphs<'a>(&'a [ShapedGlyph]);
+
+impl PartialEq for EllipsisGlyphs<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0
+            .iter()
+            .map(|glyph| glyph.glyph_id)
+            .eq(other.0.iter().map(|glyph| glyph.glyph_id))
+    }
+}
+
+impl Eq for EllipsisGlyphs<'_> {}
+
+impl Hash for EllipsisGlyphs<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.len().hash(state);
+        for glyph in self.0 {
+            glyph.glyph_id.hash(state);
+        }
+    }
+}
+
+/// The layout options that change a layout of a shaped text.
+///
+/// Floats are compared by their bits, a width computed the same way every
+/// frame gives the same key.
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct LayoutSettings<'a> {
+    max_width: Option<u32>,
+    direction: Option<Direction>,
+    align: Align,
+    line_height: (u8, u32),
+    max_lines: Option<usize>,
+    /// The face, size and glyphs of the ellipsis.
+    ellipsis: Option<(FaceId, u32, EllipsisGlyphs<'a>)>,
+}
+
+impl<'a> LayoutSettings<'a> {
+    fn new(options: &'a LayoutOptions) -> Self {
+        Self {
+            max_width: options.max_width.map(f32::to_bits),
+            direction: options.direction,
+            align: options.align,
+            line_height: match options.line_height {
+                LineHeight::Normal => (0, 0),
+                LineHeight::Scale(scale) => (1, scale.to_bits()),
+                LineHeight::Pixels(pixels) => (2, pixels.to_bits()),
+            },
+            max_lines: options.max_lines,
+            ellipsis: options
+                .ellipsis
+                .as_ref()
+                .map(|run| (run.face.id(), run.pixel_size, EllipsisGlyphs(&run.glyphs))),
+        }
+    }
+}
+
+/// What a layout is looked up by, borrowed like [`ShapeParts`].
+///
+/// The writing mode of the options is the one of the shaped text.
+#[derive(Debug, Clone, Copy)]
+struct LayoutParts<'a> {
+    shape: ShapeParts<'a>,
+    options: &'a LayoutOptions,
+}
+
+impl LayoutParts<'_> {
+    fn to_key(self) -> LayoutKey {
+        LayoutKey {
+            shape: self.shape.to_key(),
+            options: self.options.clone(),
+        }
+    }
+}
+
+impl PartialEq for LayoutParts<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.shape == other.shape
+            && LayoutSettings::new(self.options) == LayoutSettings::new(other.options)
+    }
+}
+
+impl Eq for LayoutParts<'_> {}
+
+impl Hash for LayoutParts<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.shape.hash(state);
+        LayoutSettings::new(self.options).hash(state);
+    }
+}
+
+/// Identifies the layout of a shaped text.
+#[derive(Debug, Clone)]
+struct LayoutKey {
+    shape: ShapeKey,
+    options: LayoutOptions,
+}
+
+impl LayoutKey {
+    fn uses_face(&self, face: FaceId) -> bool {
+        self.shape.uses_face(face)
+            || self
+                .options
+                .ellipsis
+                .as_ref()
+                .is_some_and(|run| run.face.id() == face)
+    }
+}
+
+/// A [`LayoutKey`] or the parts of one, like [`ShapeLookup`].
+trait LayoutLookup {
+    fn parts(&self) -> LayoutParts<'_>;
+}
+
+impl LayoutLookup for LayoutKey {
+    fn parts(&self) -> LayoutParts<'_> {
+        LayoutParts {
+            shape: self.shape.parts(),
+            options: &self.options,
+        }
+    }
+}
+
+impl LayoutLookup for LayoutParts<'_> {
+    fn parts(&self) -> LayoutParts<'_> {
+        *self
+    }
+}
+
+impl<'a> Borrow<dyn LayoutLookup + 'a> for LayoutKey {
+    fn borrow(&self) -> &(dyn LayoutLookup + 'a) {
+        self
+    }
+}
+
+impl PartialEq for dyn LayoutLookup + '_ {
+    fn eq(&self, other: &Self) -> bool {
+        self.parts() == other.parts()
+    }
+}
+
+impl Eq for dyn LayoutLookup + '_ {}
+
+impl Hash for dyn LayoutLookup + '_ {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.parts().hash(state);
+    }
+}
+
+impl PartialEq for LayoutKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.parts() == other.parts()
+    }
+}
+
+impl Eq for LayoutKey {}
+
+impl Hash for LayoutKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.parts().hash(state);
+    }
+}
+
+/// How well a cache is doing, for profiling.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Entries dropped to make room for new ones.
+    pub evictions: u64,
+}
+
+impl CacheStats {
+    /// The share of lookups that were hits, 0 before the first lookup.
+    pub fn hit_rate(&self) -> f32 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f32 / lookups as f32
+        }
+    }
+}
+
+/// A map that forgets the least recently used entry when it is full.
+#[derive(Debug)]
+struct Lru<K, V> {
+    /// The values with the tick they were last used at.
+    entries: HashMap<K, (V, u64)>,
+    /// The keys by the tick they were last used at, the least recently used first.
+    recency: BTreeMap<u64, K>,
+    capacity: usize,
+    tick: u64,
+    stats: CacheStats,
+}
+
+impl<K: Clone + Eq + Hash, V: Clone> Lru<K, V> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::default(),
+            recency: BTreeMap::new(),
+            capacity,
+            tick: 0,
+            stats: CacheStats::default(),
+        }
+    }
+
+    fn get<Q: ?Sized + Eq + Hash>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        self.tick += 1;
+        match self.entries.get_mut(key) {
+            Some((value, used)) => {
+                if let Some(key) = self.recency.remove(used) {
+                    self.recency.insert(self.tick, key);
+                }
+                *used = self.tick;
+                self.stats.hits += 1;
+                Some(value.clone())
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.tick += 1;
+        if let Some((old, used)) = self.entries.get_mut(&key) {
+            *old = value;
+            if let Some(key) = self.recency.remove(used) {
+                self.recency.insert(self.tick, key);
+            }
+            *used = self.tick;
+            return;
+        }
+
+        self.evict(self.capacity - 1);
+        self.recency.insert(self.tick, key.clone());
+        self.entries.insert(key, (value, self.tick));
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict(capacity);
+    }
+
+    /// Drop the least recently used entries until at most `len` are left.
+    fn evict(&mut self, len: usize) {
+        while self.entries.len() > len {
+            let Some((_, oldest)) = self.recency.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+            self.stats.evictions += 1;
+        }
+    }
+
+    fn retain(&mut self, mut keep: impl FnMut(&K) -> bool) {
+        self.entries.retain(|key, _| keep(key));
+        self.recency.retain(|_, key| keep(key));
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+}
+
+/// Shaped runs and layouts by text, font, size or rich text styles and layout options.
+///
+/// Measuring and drawing the same text through one cache shapes it once and
+/// gives both the same glyphs, so a measured size always matches what is drawn.
+/// Text drawn every frame, like a HUD, is only shaped and laid out again when
+/// it changes. Both kinds of results are kept up to a capacity, the least
+/// recently used ones are dropped first.
+#[derive(Debug)]
+pub struct ShapeCache {
+    runs: Lru<ShapeKey, Rc<[ShapedRun]>>,
+    layouts: Lru<LayoutKey, Rc<Layout>>,
+}
+
+impl Default for ShapeCache {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+}
+
+impl ShapeCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A cache that keeps up to `capacity` shaped texts and as many layouts.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            runs: Lru::new(capacity),
+            layouts: Lru::new(capacity),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.runs.capacity
+    }
+
+    /// Change the capacity, dropping the least recently used entries that do not fit.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.runs.set_capacity(capacity);
+        self.layouts.set_capacity(capacity);
+    }
+
+    fn shape_parts<'a>(
+        fonts: &'a FontCollection,
+        text: &'a str,
+        pixel_size: u32,
+        options: &'a ShapeOptions,
+        writing_mode: WritingMode,
+    ) -> ShapeParts<'a> {
+        ShapeParts {
+            text,
+            faces: Faces::Collection(fonts),
+            styles: StyleParts::Plain(pixel_size),
+            options,
+            writing_mode,
+        }
+    }
+
+    fn attributed_parts<'a>(
+        fonts: &'a FontFamily,
+        text: &'a AttributedString,
+        options: &'a ShapeOptions,
+    ) -> ShapeParts<'a> {
+        ShapeParts {
+            text: text.text(),
+            faces: Faces::Family(fonts),
+            styles: StyleParts::Attributed(text.spans(), text.features()),
+            options,
+            writing_mode: WritingMode::Horizontal,
+        }
+    }
+
+    /// Shape a text with fallback, or get the runs shaped before.
+    ///
+    /// Vertical writing modes shape with
+    /// [`FontCollection::shape_vertical`](crate::collection::FontCollection::shape_vertical).
+    pub fn shape(
+        &mut self,
+        fonts: &FontCollection,
+        text: &str,
+        pixel_size: u32,
+        options: &ShapeOptions,
+        writing_mode: WritingMode,
+    ) -> Result<Rc<[ShapedRun]>, TextError> {
+        let parts = Self::shape_parts(fonts, text, pixel_size, options, writing_mode);
+
+        if let Some(runs) = self.runs.get(&parts as &dyn ShapeLookup) {
+            return Ok(runs);
+        }
+
+        let runs: Rc<[ShapedRun]> = if writing_mode.is_vertical() {
+            fonts.shape_vertical(text, pixel_size, options)?
+        } else {
+            fonts.shape(text, pixel_size, options)?
+        }
+        .into();
+
+        self.runs.insert(parts.to_key(), runs.clone());
+        Ok(runs)
+    }
+
+    /// Shape and lay out a text in the writing mode of `layout_options`, or
+    /// get the layout made before.
+    pub fn layout(
+        &mut self,
+        fonts: &FontCollection,
+        text: &str,
+        pixel_size: u32,
+        shape_options: &ShapeOptions,
+        layout_options: &LayoutOptions,
+    ) -> Result<Rc<Layout>, TextError> {
+        let parts = LayoutParts {
+            shape: Self::shape_parts(
+                fonts,
+                text,
+                pixel_size,
+                shape_options,
+                layout_options.writing_mode,
+            ),
+            options: layout_options,
+        };
+
+        if let Some(layout) = self.layouts.get(&parts as &dyn LayoutLookup) {
+            return Ok(layout);
+        }
+
+        let runs = self.shape(
+            fonts,
+            text,
+            pixel_size,
+            shape_options,
+            layout_options.writing_mode,
+        )?;
+        let layout = Rc::new(Layout::new(text, &runs, layout_options)?);
+
+        self.layouts.insert(parts.to_key(), layout.clone());
+        Ok(layout)
+    }
+
+    /// Shape rich text with [`AttributedString::shape`], or get the runs shaped before.
+    pub fn shape_attributed(
+        &mut self,
+        fonts: &FontFamily,
+        text: &AttributedString,
+        options: &ShapeOptions,
+    ) -> Result<Rc<[ShapedRun]>, TextError> {
+        let parts = Self::attributed_parts(fonts, text, options);
+
+        if let Some(runs) = self.runs.get(&parts as &dyn ShapeLookup) {
+            return Ok(runs);
+        }
+
+        let runs: Rc<[ShapedRun]> = text.shape(fonts, options)?.into();
+        self.runs.insert(parts.to_key(), runs.clone());
+        Ok(runs)
+    }
+
+    /// Shape and lay out rich text, or get the layout made before.
+    ///
+    /// Rich text is always shaped and laid out horizontally, like with
+    /// [`AttributedString::shape`], the writing mode of `layout_options` is
+    /// ignored.
+    pub fn layout_attributed(
+        &mut self,
+        fonts: &FontFamily,
+        text: &AttributedString,
+        shape_options: &ShapeOptions,
+        layout_options: &LayoutOptions,
+    ) -> Result<Rc<Layout>, TextError> {
+        let parts = LayoutParts {
+            shape: Self::attributed_parts(fonts, text, shape_options),
+            options: layout_options,
+        };
+
+        if let Some(layout) = self.layouts.get(&parts as &dyn LayoutLookup) {
+            return Ok(layout);
+        }
+
+        let runs = self.shape_attributed(fonts, text, shape_options)?;
+        let layout_options = LayoutOptions {
+            writing_mode: WritingMode::Horizontal,
+            ..layout_options.clone()
+        };
+        let layout = Rc::new(Layout::new(text.text(), &runs, &layout_options)?);
+
+        self.layouts.insert(
+            LayoutKey {
+                shape: parts.shape.to_key(),
+                options: layout_options,
+            },
+            layout.clone(),
+        );
+        Ok(layout)
+    }
+
+    /// The number of cached shaped texts.
+    pub fn len(&self) -> usize {
+        self.runs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.runs.len() == 0
+    }
+
+    /// The number of cached layouts.
+    pub fn layout_count(&self) -> usize {
+        self.layouts.len()
+    }
+
+    /// The lookups of [`ShapeCache::shape`], those of layouts included.
+    pub fn shape_stats(&self) -> CacheStats {
+        self.runs.stats
+    }
+
+    /// The lookups of [`ShapeCache::layout`] and of the measurements made through it.
+    pub fn layout_stats(&self) -> CacheStats {
+        self.layouts.stats
+    }
+
+    /// Start counting hits and misses from zero, like once per frame.
+    pub fn reset_stats(&mut self) {
+        self.runs.stats = CacheStats::default();
+        self.layouts.stats = CacheStats::default();
+    }
+
+    /// Forget everything shaped with a face, after it was unloaded or reloaded.
+    ///
+    /// A reloaded face gets a new [`FaceId`] and never finds the old entries,
+    /// this only gives their room back at once.
+    pub fn invalidate_face(&mut self, face: FaceId) {
+        self.runs.retain(|key| !key.uses_face(face));
+        self.layouts.retain(|key| !key.uses_face(face));
+    }
+
+    /// Forget every shaped text and layout.
+    ///
+    /// Changing the faces of a collection never returns stale results, but the
+    /// old ones stay in memory until they are evicted or cleared.
+    pub fn clear(&mut self) {
+        self.runs.clear();
+        self.layouts.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rich::TextStyle, test_font::TestFont};
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut lru = Lru::new(2);
+        lru.insert("a", 1);
+        lru.insert("b", 2);
+        assert_eq!(lru.get(&"a"), Some(1));
+        lru.insert("c", 3);
+        assert_eq!(lru.get(&"b"), None, "b was used least recently");
+        assert_eq!(lru.get(&"a"), Some(1));
+        assert_eq!(lru.get(&"c"), Some(3));
+        assert_eq!(
+            lru.stats,
+            CacheStats {
+                hits: 3,
+                misses: 1,
+                evictions: 1
+            }
+        );
+
+        lru.set_capacity(1);
+        assert_eq!(lru.get(&"a"), None);
+        assert_eq!(lru.get(&"c"), Some(3));
+    }
+
+    #[test]
+    fn replaces_without_evicting() {
+        let mut lru = Lru::new(2);
+        lru.insert("a", 1);
+        lru.insert("b", 2);
+        lru.insert("b", 3);
+        assert_eq!(lru.stats.evictions, 0);
+
+        // the entry inserted last is the most recently used
+        lru.insert("c", 4);
+        assert_eq!(lru.get(&"a"), None);
+        assert_eq!(lru.get(&"b"), Some(3));
+        assert_eq!(lru.get(&"c"), Some(4));
+    }
+
+    #[test]
+    fn evicts_in_order_after_retain() {
+        let mut lru = Lru::new(3);
+        lru.insert("a", 1);
+        lru.insert("b", 2);
+        lru.insert("c", 3);
+        lru.retain(|key| *key != "a");
+        assert_eq!(lru.len(), 2);
+
+        lru.insert("d", 4);
+        lru.insert("e", 5);
+        assert_eq!(lru.stats.evictions, 1);
+        assert_eq!(lru.get(&"b"), None);
+        assert_eq!(lru.get(&"c"), Some(3));
+        assert_eq!(lru.recency.len(), lru.len());
+    }
+
+    #[test]
+    fn caches_attributed_layouts() -> Result<(), TextError> {
+        let fonts = FontFamily::new(FontCollection::new([TestFont::new().face()?]));
+        let mut cache = ShapeCache::new();
+        let shape_options = ShapeOptions::default();
+        let layout_options = LayoutOptions::default();
+        let layout = |cache: &mut ShapeCache, text: &AttributedString| {
+            cache
+                .layout_attributed(&fonts, text, &shape_options, &layout_options)
+                .map(|_| cache.layout_stats())
+        };
+
+        let mut text = AttributedString::new("abc", TextStyle::default());
+        layout(&mut cache, &text)?;
+        assert_eq!(layout(&mut cache, &text)?.hits, 1);
+
+        text.update_style(1..2, |style| style.size = 20)?;
+        assert_eq!(layout(&mut cache, &text)?.misses, 2);
+        text.add_feature(0..1, Feature::new(b"smcp", 1))?;
+        assert_eq!(layout(&mut cache, &text)?.misses, 3);
+        assert_eq!(cache.len(), 3);
+
+        // plain text of the same faces is kept apart
+        let plain = cache.layout(&fonts.regular, "abc", 16, &shape_options, &layout_options)?;
+        assert_eq!(cache.layout_stats().misses, 4);
+        assert_eq!(plain.lines().len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn lays_out_rich_text_horizontally() -> Result<(), TextError> {
+        let fonts = FontFamily::new(FontCollection::new([TestFont::new().face()?]));
+        let mut cache = ShapeCache::new();
+        let text = AttributedString::new("abc", TextStyle::default());
+        let shape_options = ShapeOptions::default();
+
+        let vertical = LayoutOptions {
+            writing_mode: WritingMode::VerticalRl,
+            ..LayoutOptions::default()
+        };
+        let layout = cache.layout_attributed(&fonts, &text, &shape_options, &vertical)?;
+        assert_eq!(layout.writing_mode(), WritingMode::Horizontal);
+
+        cache.layout_attributed(&fonts, &text, &shape_options, &LayoutOptions::default())?;
+        assert_eq!(cache.layout_stats().hits, 1);
+        Ok(())
+    }
+}
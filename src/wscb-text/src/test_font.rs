@@ -0,0 +1,142 @@
+//! A small TrueType font assembled in memory, so tests do not depend on the
+//! fonts installed on the system.
+//!
+//! The font has 1000 units per em, an ascender of 800 and a descender of
+//! -200. Besides `.notdef` it has [`SPACE`], 500 units wide and mapped to
+//! `' '`, and [`SQUARE`], a full em box 1000 units wide that every letter
+//! from `'a'` to `'z'` maps to. [`BLANK`] is an empty glyph no character
+//! maps to, tests give it color layers or other extras.
+
+use crate::{
+    error::TextError,
+    font::{Face, Library},
+};
+
+pub(crate) const SPACE: u32 = 1;
+pub(crate) const SQUARE: u32 = 2;
+pub(crate) const BLANK: u32 = 3;
+
+/// Append big endian 16-bit values, negative ones in two's complement.
+pub(crate) fn words(out: &mut Vec<u8>, values: &[i32]) {
+    out.extend(
+        values
+            .iter()
+            .flat_map(|&value| (value as u16).to_be_bytes()),
+    );
+}
+
+/// Append big endian 32-bit values.
+pub(crate) fn longs(out: &mut Vec<u8>, values: &[u32]) {
+    out.extend(values.iter().flat_map(|value| value.to_be_bytes()));
+}
+
+/// The tables of a test font, extra ones can be added before building it.
+pub(crate) struct TestFont {
+    tables: Vec<([u8; 4], Vec<u8>)>,
+}
+
+impl TestFont {
+    pub fn new() -> Self {
+        let mut head = Vec::new();
+        longs(&mut head, &[0x0001_0000, 0x0001_0000, 0, 0x5F0F_3CF5]);
+        words(&mut head, &[0, 1000]);
+        longs(&mut head, &[0, 0, 0, 0]);
+        words(&mut head, &[0, -200, 1000, 800, 0, 8, 2, 0, 0]);
+
+        let mut hhea = Vec::new();
+        longs(&mut hhea, &[0x0001_0000]);
+        words(&mut hhea, &[800, -200, 0, 1000, 0, 0, 1000, 1, 0, 0]);
+        words(&mut hhea, &[0, 0, 0, 0, 0, 4]);
+
+        let mut maxp = Vec::new();
+        longs(&mut maxp, &[0x0001_0000]);
+        words(&mut maxp, &[4, 4, 1, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+        let mut hmtx = Vec::new();
+        words(&mut hmtx, &[1000, 0, 500, 0, 1000, 0, 1000, 0]);
+
+        // one contour through (0, -200), (0, 800), (1000, 800) and (1000, -200)
+        let mut glyf = Vec::new();
+        words(&mut glyf, &[1, 0, -200, 1000, 800, 3, 0]);
+        glyf.extend([1, 1, 1, 1]);
+        words(&mut glyf, &[0, 0, 1000, 0, -200, 1000, 0, -1000]);
+
+        let mut loca = Vec::new();
+        let end = glyf.len() as i32 / 2;
+        words(&mut loca, &[0, 0, 0, end, end]);
+
+        // a format 4 subtable with the segments ' ', 'a'..='z' and the final 0xFFFF
+        let mut subtable = Vec::new();
+        words(&mut subtable, &[4, 16 + 3 * 8 + 26 * 2, 0, 6, 4, 1, 2]);
+        words(&mut subtable, &[0x20, 0x7A, 0xFFFF, 0, 0x20, 0x61, 0xFFFF]);
+        words(&mut subtable, &[SPACE as i32 - 0x20, 0, 1, 0, 4, 0]);
+        words(&mut subtable, &[SQUARE as i32; 26]);
+        let mut cmap = Vec::new();
+        words(&mut cmap, &[0, 1, 3, 1]);
+        longs(&mut cmap, &[12]);
+        cmap.extend(subtable);
+
+        let mut name = Vec::new();
+        words(&mut name, &[0, 0, 6]);
+        let mut post = Vec::new();
+        longs(&mut post, &[0x0003_0000, 0, 0, 0, 0, 0, 0, 0]);
+
+        Self {
+            tables: vec![
+                (*b"cmap", cmap),
+                (*b"glyf", glyf),
+                (*b"head", head),
+                (*b"hhea", hhea),
+                (*b"hmtx", hmtx),
+                (*b"loca", loca),
+                (*b"maxp", maxp),
+                (*b"name", name),
+                (*b"post", post),
+            ],
+        }
+    }
+
+    /// Add a table, or replace the one with the same tag.
+    pub fn with_table(mut self, tag: &[u8; 4], data: Vec<u8>) -> Self {
+        self.tables.retain(|(existing, _)| existing != tag);
+        self.tables.push((*tag, data));
+        self
+    }
+
+    /// The font file.
+    pub fn build(mut self) -> Vec<u8> {
+        self.tables.sort_by_key(|(tag, _)| *tag);
+
+        let count = self.tables.len();
+        let power = 1 << count.ilog2();
+        let mut font = Vec::new();
+        longs(&mut font, &[0x0001_0000]);
+        words(
+            &mut font,
+            &[
+                count as i32,
+                power * 16,
+                count.ilog2() as i32,
+                (count as i32 - power) * 16,
+            ],
+        );
+
+        let mut offset = 12 + 16 * count;
+        for (tag, data) in &self.tables {
+            font.extend(tag);
+            longs(&mut font, &[0, offset as u32, data.len() as u32]);
+            offset += data.len().next_multiple_of(4);
+        }
+        for (_, data) in &self.tables {
+            font.extend(data);
+            font.resize(font.len().next_multiple_of(4), 0);
+        }
+
+        font
+    }
+
+    /// Load the font with a new library.
+    pub fn face(self) -> Result<Face, TextError> {
+        Library::new()?.load_face_from_memory(self.build(), 0)
+    }
+}
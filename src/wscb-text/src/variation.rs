@@ -0,0 +1,392 @@
+//! Variable fonts.
+//!
+//! A variable font can be set anywhere along its design axes, like weight or
+//! width. A [`Face`] is always one point of that design space:
+//! [`Face::with_variations`] and [`Face::named_instance`] load a new face from
+//! the same data with its own [`FaceId`](crate::font::FaceId), so glyph and
+//! shape caches never mix up instances.
+
+use ::std::{ptr::NonNull, str::FromStr};
+
+use crate::{
+    error::TextError,
+    ffi::{
+        freetype::{
+            FT_Done_MM_Var, FT_FACE_FLAG_MULTIPLE_MASTERS, FT_Fixed, FT_Get_MM_Var,
+            FT_Get_Var_Axis_Flags, FT_MM_Var, FT_Set_Var_Design_Coordinates, FT_UInt,
+            FT_VAR_AXIS_FLAG_HIDDEN,
+        },
+        harfbuzz::{hb_tag, hb_variation_from_string, hb_variation_t},
+    },
+    font::Face,
+};
+
+/// A coordinate on one variation axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Variation {
+    pub tag: [u8; 4],
+    pub value: f32,
+}
+
+impl Variation {
+    pub fn new(tag: &[u8; 4], value: f32) -> Self {
+        Self { tag: *tag, value }
+    }
+
+    /// The `wght` axis, `400` is regular and `700` bold.
+    pub fn weight(value: f32) -> Self {
+        Self::new(b"wght", value)
+    }
+
+    /// The `wdth` axis, in percent of the normal width.
+    pub fn width(value: f32) -> Self {
+        Self::new(b"wdth", value)
+    }
+
+    /// The `slnt` axis, in degrees counter-clockwise, so leaning right is negative.
+    pub fn slant(value: f32) -> Self {
+        Self::new(b"slnt", value)
+    }
+
+    /// The `ital` axis, `0` upright and `1` italic.
+    pub fn italic(value: f32) -> Self {
+        Self::new(b"ital", value)
+    }
+
+    /// The `opsz` axis, the text size in points the glyphs are designed for.
+    pub fn optical_size(value: f32) -> Self {
+        Self::new(b"opsz", value)
+    }
+
+    pub(crate) fn to_harfbuzz(self) -> hb_variation_t {
+        hb_variation_t {
+            tag: hb_tag(&self.tag),
+            value: self.value,
+        }
+    }
+}
+
+impl FromStr for Variation {
+    type Err = TextError;
+
+    /// Parse the syntax of HarfBuzz, like `"wght=650"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let length = s
+            .len()
+            .try_into()
+            .map_err(|_| TextError::HarfBuzz("parsing variation"))?;
+
+        let mut variation = hb_variation_t { tag: 0, value: 0.0 };
+        if unsafe { hb_variation_from_string(s.as_ptr().cast(), length, &mut variation) } == 0 {
+            return Err(TextError::HarfBuzz("parsing variation"));
+        }
+
+        Ok(Self {
+            tag: variation.tag.to_be_bytes(),
+            value: variation.value,
+        })
+    }
+}
+
+/// A design axis of a variable font.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariationAxis {
+    pub tag: [u8; 4],
+    pub name: String,
+    pub min: f32,
+    pub default: f32,
+    pub max: f32,
+    /// Whether the font asks not to show the axis to users.
+    pub hidden: bool,
+}
+
+/// A point of the design space the font gives a name, like "Semibold Condensed".
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamedInstance {
+    pub name: Option<String>,
+    /// The coordinate on every axis, in the order of [`Face::variation_axes`].
+    pub coords: Vec<f32>,
+}
+
+fn from_fixed(value: FT_Fixed) -> f32 {
+    value as f32 / 65536.0
+}
+
+fn to_fixed(value: f32) -> FT_Fixed {
+    (value * 65536.0).round() as FT_Fixed
+}
+
+/// The variation description FreeType allocates for a face.
+struct MmVar<'a> {
+    pointer: NonNull<FT_MM_Var>,
+    face: &'a Face,
+}
+
+impl MmVar<'_> {
+    fn raw(&self) -> &FT_MM_Var {
+        unsafe { self.pointer.as_ref() }
+    }
+}
+
+impl Drop for MmVar<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            FT_Done_MM_Var(self.face.library().get_pointer(), self.pointer.as_ptr());
+        }
+    }
+}
+
+impl Face {
+    /// Whether the face has variation axes.
+    pub fn is_variable(&self) -> bool {
+        unsafe { (*self.get_pointer()).face_flags & FT_FACE_FLAG_MULTIPLE_MASTERS != 0 }
+    }
+
+    fn mm_var(&self) -> Result<Option<MmVar<'_>>, TextError> {
+        if !self.is_variable() {
+            return Ok(None);
+        }
+
+        let mut raw: *mut FT_MM_Var = std::ptr::null_mut();
+        unsafe {
+            TextError::check_freetype(
+                FT_Get_MM_Var(self.get_pointer(), &mut raw),
+                "reading variation axes",
+            )?;
+        }
+        Ok(NonNull::new(raw).map(|pointer| MmVar {
+            pointer,
+            face: self,
+        }))
+    }
+
+    /// The design axes, empty for faces that are not variable.
+    pub fn variation_axes(&self) -> Result<Vec<VariationAxis>, TextError> {
+        let Some(master) = self.mm_var()? else {
+            return Ok(Vec::new());
+        };
+        let raw = master.raw();
+        if raw.axis.is_null() {
+            return Ok(Vec::new());
+        }
+        let axes = unsafe { std::slice::from_raw_parts(raw.axis, raw.num_axis as usize) };
+
+        Ok(axes
+            .iter()
+            .enumerate()
+            .map(|(index, axis)| {
+                let mut flags: FT_UInt = 0;
+                let hidden = unsafe {
+                    FT_Get_Var_Axis_Flags(master.pointer.as_ptr(), index as FT_UInt, &mut flags)
+                } == 0
+                    && flags & FT_VAR_AXIS_FLAG_HIDDEN != 0;
+                let name = if axis.name.is_null() {
+                    String::new()
+                } else {
+                    unsafe { std::ffi::CStr::from_ptr(axis.name) }
+                        .to_string_lossy()
+                        .into_owned()
+                };
+
+                VariationAxis {
+                    tag: (axis.tag as u32).to_be_bytes(),
+                    name,
+                    min: from_fixed(axis.minimum),
+                    default: from_fixed(axis.def),
+                    max: from_fixed(axis.maximum),
+                    hidden,
+                }
+            })
+            .collect())
+    }
+
+    /// The named instances of the font, empty for faces that are not variable.
+    pub fn named_instances(&self) -> Result<Vec<NamedInstance>, TextError> {
+        let Some(master) = self.mm_var()? else {
+            return Ok(Vec::new());
+        };
+        let raw = master.raw();
+        if raw.namedstyle.is_null() {
+            return Ok(Vec::new());
+        }
+        let styles =
+            unsafe { std::slice::from_raw_parts(raw.namedstyle, raw.num_namedstyles as usize) };
+
+        Ok(styles
+            .iter()
+            .map(|style| {
+                let coords = if style.coords.is_null() {
+                    Vec::new()
+                } else {
+                    unsafe { std::slice::from_raw_parts(style.coords, raw.num_axis as usize) }
+                        .iter()
+                        .map(|&coord| from_fixed(coord))
+                        .collect()
+                };
+                NamedInstance {
+                    name: u16::try_from(style.strid)
+                        .ok()
+                        .and_then(|strid| self.sfnt_name(strid)),
+                    coords,
+                }
+            })
+            .collect())
+    }
+
+    /// The coordinate of this face on an axis, if it was set.
+    pub fn variation(&self, tag: &[u8; 4]) -> Option<f32> {
+        self.variations()
+            .iter()
+            .find(|variation| variation.tag == *tag)
+            .map(|variation| variation.value)
+    }
+
+    /// A new face at other coordinates of the design space.
+    ///
+    /// Axes not given keep the coordinates of this face, values are clamped to
+    /// the range of their axis and unknown axes are ignored. Faces that are not
+    /// variable are returned as they are.
+    pub fn with_variations(&self, variations: &[Variation]) -> Result<Face, TextError> {
+        let axes = self.variation_axes()?;
+        if axes.is_empty() {
+            return Ok(self.clone());
+        }
+
+        let coords: Vec<f32> = axes
+            .iter()
+            .map(|axis| {
+                variations
+                    .iter()
+                    .rev()
+                    .find(|variation| variation.tag == axis.tag)
+                    .map(|variation| variation.value)
+                    .or_else(|| self.variation(&axis.tag))
+                    .unwrap_or(axis.default)
+                    .clamp(axis.min, axis.max)
+            })
+            .collect();
+        self.at_coords(&axes, &coords)
+    }
+
+    /// A new face at a named instance, by its index in [`Face::named_instances`].
+    pub fn named_instance(&self, index: u32) -> Result<Face, TextError> {
+        let instances = self.named_instances()?;
+        let instance = instances
+            .get(index as usize)
+            .ok_or(TextError::NoNamedInstance {
+                index,
+                count: instances.len() as u32,
+            })?;
+        self.at_coords(&self.variation_axes()?, &instance.coords)
+    }
+
+    fn at_coords(&self, axes: &[VariationAxis], coords: &[f32]) -> Result<Face, TextError> {
+        let variations = axes
+            .iter()
+            .zip(coords)
+            .map(|(axis, &value)| Variation::new(&axis.tag, value))
+            .collect();
+        let face = self.reload(variations)?;
+
+        let mut fixed: Vec<FT_Fixed> = coords.iter().map(|&coord| to_fixed(coord)).collect();
+        unsafe {
+            TextError::check_freetype(
+                FT_Set_Var_Design_Coordinates(
+                    face.get_pointer(),
+                    fixed.len() as FT_UInt,
+                    fixed.as_mut_ptr(),
+                ),
+                "setting variation coordinates",
+            )?;
+        }
+        Ok(face)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_font::{TestFont, longs, words};
+
+    /// The test font with a `wght` axis from 100 to 900, 400 by default,
+    /// and a named instance at 700. FreeType only takes TrueType fonts with a
+    /// `gvar` table as variable, this one has no glyph variations.
+    fn variable_face() -> Result<Face, TextError> {
+        let mut fvar = Vec::new();
+        longs(&mut fvar, &[0x0001_0000]);
+        words(&mut fvar, &[16, 2, 1, 20, 1, 8]);
+        fvar.extend(b"wght");
+        longs(&mut fvar, &[100 << 16, 400 << 16, 900 << 16]);
+        words(&mut fvar, &[0, 256]);
+        words(&mut fvar, &[257, 0]);
+        longs(&mut fvar, &[700 << 16]);
+
+        let mut gvar = Vec::new();
+        words(&mut gvar, &[1, 0, 1, 0]);
+        longs(&mut gvar, &[30]);
+        words(&mut gvar, &[4, 0]);
+        longs(&mut gvar, &[30]);
+        words(&mut gvar, &[0; 5]);
+
+        TestFont::new()
+            .with_table(b"fvar", fvar)
+            .with_table(b"gvar", gvar)
+            .face()
+    }
+
+    #[test]
+    fn parses_variations() -> Result<(), TextError> {
+        assert_eq!("wght=650".parse::<Variation>()?, Variation::weight(650.0));
+        assert_eq!("wdth=87.5".parse::<Variation>()?, Variation::width(87.5));
+        assert!("".parse::<Variation>().is_err());
+        assert!("=400".parse::<Variation>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn clamps_to_the_axes() -> Result<(), TextError> {
+        let face = variable_face()?;
+        assert!(face.is_variable());
+        let axes = face.variation_axes()?;
+        assert_eq!(
+            axes.iter()
+                .map(|axis| (axis.tag, axis.min, axis.default, axis.max))
+                .collect::<Vec<_>>(),
+            [(*b"wght", 100.0, 400.0, 900.0)]
+        );
+
+        let heavy = face.with_variations(&[Variation::weight(1000.0)])?;
+        assert_eq!(heavy.variation(b"wght"), Some(900.0));
+        assert_ne!(heavy.id(), face.id());
+        let light = heavy.with_variations(&[Variation::weight(50.0), Variation::width(50.0)])?;
+        assert_eq!(light.variation(b"wght"), Some(100.0));
+        assert_eq!(light.variation(b"wdth"), None);
+        // axes not given keep their coordinates
+        assert_eq!(heavy.with_variations(&[])?.variation(b"wght"), Some(900.0));
+
+        // faces that are not variable stay as they are
+        let face = TestFont::new().face()?;
+        let same = face.with_variations(&[Variation::weight(700.0)])?;
+        assert_eq!(same.id(), face.id());
+        Ok(())
+    }
+
+    #[test]
+    fn loads_named_instances() -> Result<(), TextError> {
+        let face = variable_face()?;
+        let instances = face.named_instances()?;
+        assert_eq!(
+            instances.first().map(|instance| &instance.coords[..]),
+            Some(&[700.0][..])
+        );
+        assert_eq!(face.named_instance(0)?.variation(b"wght"), Some(700.0));
+
+        // FreeType may add the default instance after those of the font
+        let count = instances.len() as u32;
+        assert!(matches!(
+            face.named_instance(count),
+            Err(TextError::NoNamedInstance { index, count: found }) if index == count && found == count
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,262 @@
+//! Vertical text, as used for Japanese and Chinese.
+//!
+//! Lines become columns that run top to bottom and follow each other from
+//! right to left. Characters are either set upright, like ideographs and kana,
+//! or turned 90° clockwise, like Latin words, following the
+//! `Vertical_Orientation` property of UAX #50.
+
+use ::std::ops::Range;
+
+use ::unicode_vo::{Orientation as VerticalOrientation, char_orientation};
+
+use crate::{
+    bidi::BidiText,
+    collection::FontCollection,
+    error::TextError,
+    ffi::freetype::{FT_FACE_FLAG_VERTICAL, FT_Get_Sfnt_Table, FT_SFNT_VHEA, TT_VertHeader},
+    font::{Face, FontMetrics},
+    shape::{Direction, Feature, Script, ShapeOptions, ShapedRun},
+};
+
+/// How the lines of a layout are arranged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum WritingMode {
+    /// Lines run left to right (or right to left) and stack downwards.
+    #[default]
+    Horizontal,
+    /// Columns run top to bottom and stack from right to left.
+    VerticalRl,
+}
+
+impl WritingMode {
+    pub fn is_vertical(self) -> bool {
+        self == Self::VerticalRl
+    }
+}
+
+/// How a character is set in vertical text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Orientation {
+    /// Set as it is, with the vertical alternates of the font (`vert`, `vrt2`).
+    Upright,
+    /// Turned 90° clockwise and shaped like horizontal text.
+    Rotated,
+}
+
+impl Orientation {
+    /// The orientation of a character in vertical text, from UAX #50.
+    ///
+    /// Characters that the standard wants transformed (`Tu` and `Tr`, like
+    /// brackets and the long vowel mark) are set upright, the font provides
+    /// their vertical forms through `vert`.
+    pub fn of(ch: char) -> Self {
+        match char_orientation(ch) {
+            VerticalOrientation::Rotated => Self::Rotated,
+            VerticalOrientation::Upright
+            | VerticalOrientation::TransformedOrUpright
+            | VerticalOrientation::TransformedOrRotated => Self::Upright,
+        }
+    }
+}
+
+/// Split a text into ranges of one orientation.
+///
+/// Combining marks and joiners stay with the character before them.
+pub fn orientation_runs(text: &str) -> Vec<(Range<usize>, Orientation)> {
+    let mut runs: Vec<(Range<usize>, Orientation)> = Vec::new();
+
+    for (offset, ch) in text.char_indices() {
+        let end = offset + ch.len_utf8();
+        let follows = Script::of(ch) == Script::INHERITED || ch == '\u{200D}';
+
+        match runs.last_mut() {
+            Some((range, _)) if follows => range.end = end,
+            Some((range, orientation)) if *orientation == Orientation::of(ch) => range.end = end,
+            _ => runs.push((offset..end, Orientation::of(ch))),
+        }
+    }
+
+    runs
+}
+
+impl Face {
+    /// Whether the face has vertical metrics (`vhea` and `vmtx`).
+    pub fn has_vertical_metrics(&self) -> bool {
+        unsafe { (*self.get_pointer()).face_flags & FT_FACE_FLAG_VERTICAL != 0 }
+    }
+
+    /// The metrics across a column of vertical text at the given pixel size.
+    ///
+    /// Columns are centered on the central baseline. Faces with vertical
+    /// metrics give the extent to both sides of it and the gap between
+    /// columns, the glyph advances down the column come from `vmtx` while
+    /// shaping. Other faces split the em box evenly and keep the spacing that
+    /// horizontal lines of the face have.
+    pub fn vertical_metrics(&self, pixel_size: u32) -> Result<FontMetrics, TextError> {
+        let horizontal = self.metrics(pixel_size)?;
+
+        let vhea = if self.has_vertical_metrics() && self.is_scalable() {
+            unsafe { FT_Get_Sfnt_Table(self.get_pointer(), FT_SFNT_VHEA) as *const TT_VertHeader }
+        } else {
+            std::ptr::null()
+        };
+        if let Some(vhea) = unsafe { vhea.as_ref() }
+            && self.units_per_em() != 0
+            && vhea.Ascender > vhea.Descender
+        {
+            let scale = pixel_size as f32 / self.units_per_em() as f32;
+            let ascent = vhea.Ascender as f32 * scale;
+
+            return Ok(FontMetrics {
+                ascent,
+                descent: -(vhea.Descender as f32) * scale,
+                line_gap: (vhea.Line_Gap as f32 * scale).max(0.0),
+                // side lines go on the right of the column
+                underline_position: ascent,
+                underline_thickness: horizontal.underline_thickness,
+            });
+        }
+
+        let half = pixel_size as f32 / 2.0;
+        Ok(FontMetrics {
+            ascent: half,
+            descent: half,
+            line_gap: (horizontal.line_height() - pixel_size as f32).max(0.0),
+            underline_position: half,
+            underline_thickness: horizontal.underline_thickness,
+        })
+    }
+}
+
+impl FontCollection {
+    /// Shape text for a vertical [`Layout`](crate::layout::Layout).
+    ///
+    /// Upright ranges are shaped top to bottom with the `vert` and `vrt2`
+    /// features, rotated ranges are shaped horizontally in their bidi
+    /// direction. `options.features` come after the vertical ones and can
+    /// turn them off.
+    pub fn shape_vertical(
+        &self,
+        text: &str,
+        pixel_size: u32,
+        options: &ShapeOptions,
+    ) -> Result<Vec<ShapedRun>, TextError> {
+        let base = options
+            .direction
+            .filter(|direction| direction.is_horizontal());
+        let bidi = BidiText::new(text, base);
+
+        let mut upright_features = vec![Feature::new(b"vert", 1), Feature::new(b"vrt2", 1)];
+        upright_features.extend(options.features.iter().copied());
+
+        let mut runs = Vec::new();
+
+        for font_run in self.itemize(text) {
+            let script = if font_run.script.is_neutral() {
+                options.script
+            } else {
+                Some(font_run.script)
+            };
+            let slice = &text[font_run.range.clone()];
+
+            for (range, orientation) in orientation_runs(slice) {
+                let range = range.start + font_run.range.start..range.end + font_run.range.start;
+
+                if orientation == Orientation::Upright {
+                    let run_options = ShapeOptions {
+                        direction: Some(Direction::TopToBottom),
+                        script,
+                        language: options.language,
+                        features: upright_features.clone(),
+                    };
+                    runs.push(font_run.face.shape(text, range, pixel_size, &run_options)?);
+                    continue;
+                }
+
+                for bidi_run in bidi.runs() {
+                    let run_range =
+                        bidi_run.range.start.max(range.start)..bidi_run.range.end.min(range.end);
+                    if run_range.is_empty() {
+                        continue;
+                    }
+
+                    let run_options = ShapeOptions {
+                        direction: Some(bidi_run.direction()),
+                        script,
+                        language: options.language,
+                        features: options.features.clone(),
+                    };
+                    runs.push(
+                        font_run
+                            .face
+                            .shape(text, run_range, pixel_size, &run_options)?,
+                    );
+                }
+            }
+        }
+
+        Ok(runs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_font::{TestFont, longs, words};
+
+    #[test]
+    fn splits_by_orientation() {
+        let text = "日本語ABC、テスト\u{301}x";
+        let runs: Vec<(&str, Orientation)> = orientation_runs(text)
+            .into_iter()
+            .map(|(range, orientation)| (&text[range], orientation))
+            .collect();
+
+        assert_eq!(
+            runs,
+            [
+                ("日本語", Orientation::Upright),
+                ("ABC", Orientation::Rotated),
+                ("、テスト\u{301}", Orientation::Upright),
+                ("x", Orientation::Rotated),
+            ]
+        );
+    }
+
+    /// The test font with `vhea` and `vmtx`, 600 units to the right of the
+    /// central baseline, 400 to the left and 100 between columns.
+    fn vertical_font() -> TestFont {
+        let mut vhea = Vec::new();
+        longs(&mut vhea, &[0x0001_1000]);
+        words(&mut vhea, &[600, -400, 100, 1000, 0, 0, 1000, 0, 1, 0]);
+        words(&mut vhea, &[0, 0, 0, 0, 0, 4]);
+
+        let mut vmtx = Vec::new();
+        words(&mut vmtx, &[1000, 200, 1000, 0, 1000, 200, 1000, 0]);
+
+        TestFont::new()
+            .with_table(b"vhea", vhea)
+            .with_table(b"vmtx", vmtx)
+    }
+
+    #[test]
+    fn reads_vertical_metrics() -> Result<(), TextError> {
+        let face = vertical_font().face()?;
+        assert!(face.has_vertical_metrics());
+        let metrics = face.vertical_metrics(10)?;
+        assert_eq!(
+            (metrics.ascent, metrics.descent, metrics.line_gap),
+            (6.0, 4.0, 1.0)
+        );
+
+        // without them the em box is split in half
+        let face = TestFont::new().face()?;
+        assert!(!face.has_vertical_metrics());
+        let metrics = face.vertical_metrics(10)?;
+        assert_eq!(
+            (metrics.ascent, metrics.descent, metrics.line_gap),
+            (5.0, 5.0, 0.0)
+        );
+        Ok(())
+    }
+}
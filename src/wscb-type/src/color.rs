@@ -0,0 +1,87 @@
+use ::sdl3_sys::pixels::{SDL_Color, SDL_FColor};
+
+/// A non-premultiplied 8 bit RGBA color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Color {
+    pub const TRANSPARENT: Self = Self::rgba(0, 0, 0, 0);
+    pub const BLACK: Self = Self::rgb(0, 0, 0);
+    pub const WHITE: Self = Self::rgb(255, 255, 255);
+    pub const RED: Self = Self::rgb(255, 0, 0);
+    pub const GREEN: Self = Self::rgb(0, 255, 0);
+    pub const BLUE: Self = Self::rgb(0, 0, 255);
+
+    #[must_use]
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b, a: 255 }
+    }
+
+    #[must_use]
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Parse `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`, the `#` is optional.
+    #[must_use]
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        if !hex.is_ascii() {
+            return None;
+        }
+
+        let short = |index: usize| {
+            u8::from_str_radix(hex.get(index..index + 1)?, 16)
+                .ok()
+                .map(|value| value * 17)
+        };
+        let long = |index: usize| u8::from_str_radix(hex.get(index * 2..index * 2 + 2)?, 16).ok();
+
+        match hex.len() {
+            3 => Some(Self::rgb(short(0)?, short(1)?, short(2)?)),
+            4 => Some(Self::rgba(short(0)?, short(1)?, short(2)?, short(3)?)),
+            6 => Some(Self::rgb(long(0)?, long(1)?, long(2)?)),
+            8 => Some(Self::rgba(long(0)?, long(1)?, long(2)?, long(3)?)),
+            _ => None,
+        }
+    }
+
+    /// The same color with another alpha.
+    #[must_use]
+    pub const fn with_alpha(self, a: u8) -> Self {
+        Self { a, ..self }
+    }
+}
+
+impl Default for Color {
+    fn default() -> Self {
+        Self::WHITE
+    }
+}
+
+impl From<Color> for SDL_Color {
+    fn from(value: Color) -> Self {
+        SDL_Color {
+            r: value.r,
+            g: value.g,
+            b: value.b,
+            a: value.a,
+        }
+    }
+}
+
+impl From<Color> for SDL_FColor {
+    fn from(value: Color) -> Self {
+        SDL_FColor {
+            r: f32::from(value.r) / 255.0,
+            g: f32::from(value.g) / 255.0,
+            b: f32::from(value.b) / 255.0,
+            a: f32::from(value.a) / 255.0,
+        }
+    }
+}
@@ -8,6 +8,10 @@ pub enum SdlError {
     SdlError(String),
     #[error("try from int error when convert sdl integer to our integer: {0}")]
     TryFromIntError(#[from] TryFromIntError),
+    #[error("SDL must be used from the main thread")]
+    NotMainThread,
+    #[error("SDL is already initialized by another context")]
+    AlreadyInitialized,
 }
 
 impl SdlError {
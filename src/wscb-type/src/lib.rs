@@ -1,3 +1,4 @@
+pub mod color;
 pub mod error;
 pub mod graph;
 pub mod graph_f;